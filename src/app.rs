@@ -1,30 +1,57 @@
-use crate::event::{EventHandler, GitDataUpdate, TerminalEvent};
-use crate::git_repo::GitRepo;
-use crate::util::{strip_unc_pathbuf, strip_unc_prefix};
+use crate::config::{RepoSettings, Settings, TruncateStyle};
+use crate::event::{
+    EventHandler, GitDataUpdate, TerminalEvent, fetch_repo, load_remote_status, load_status,
+};
+use crate::git_repo::{CORRUPT, GitRepo, Worktree};
+use crate::util::{shell_quote, strip_unc_pathbuf, strip_unc_prefix};
 use color_eyre::Result;
 use crossterm::{
     event::{KeyCode, KeyModifiers},
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
-use ratatui::{Terminal, backend::CrosstermBackend, widgets::TableState};
+use ratatui::{
+    Terminal, TerminalOptions, Viewport,
+    backend::CrosstermBackend,
+    widgets::{ListState, TableState},
+};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::io;
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tracing::{debug, warn};
+
+/// Maximum number of repositories whose disk usage is computed at the same time
+const MAX_CONCURRENT_SIZE_TASKS: usize = 2;
 
 /// Filter mode for displaying repositories
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FilterMode {
     All,
+    NeedsAttention,
     NoUpstream,
     Modified,
     Behind,
 }
 
 impl FilterMode {
+    /// All filter modes, in cycle order
+    pub const ALL: [FilterMode; 5] = [
+        FilterMode::All,
+        FilterMode::NeedsAttention,
+        FilterMode::NoUpstream,
+        FilterMode::Behind,
+        FilterMode::Modified,
+    ];
+
     /// Get the next filter mode in the cycle
     pub fn next(&self) -> Self {
         match self {
-            FilterMode::All => FilterMode::NoUpstream,
+            FilterMode::All => FilterMode::NeedsAttention,
+            FilterMode::NeedsAttention => FilterMode::NoUpstream,
             FilterMode::NoUpstream => FilterMode::Behind,
             FilterMode::Behind => FilterMode::Modified,
             FilterMode::Modified => FilterMode::All,
@@ -37,57 +64,266 @@ impl FilterMode {
             FilterMode::All => FilterMode::Modified,
             FilterMode::Modified => FilterMode::Behind,
             FilterMode::Behind => FilterMode::NoUpstream,
-            FilterMode::NoUpstream => FilterMode::All,
+            FilterMode::NoUpstream => FilterMode::NeedsAttention,
+            FilterMode::NeedsAttention => FilterMode::All,
         }
     }
 
     /// Get display name for the filter mode
-    pub fn display_name(&self) -> &str {
+    pub fn display_name(&self) -> &'static str {
         match self {
             FilterMode::All => "All",
+            FilterMode::NeedsAttention => "Needs Attention",
             FilterMode::NoUpstream => "No Upstream",
             FilterMode::Modified => "Modified",
             FilterMode::Behind => "Behind",
         }
     }
+
+    /// Find the filter mode with this name, ignoring case, spaces and dashes
+    /// (e.g. "Behind", "needs-attention" or "NoUpstream")
+    pub fn from_name(name: &str) -> Option<Self> {
+        let normalize = |name: &str| {
+            name.chars()
+                .filter(|c| c.is_alphanumeric())
+                .collect::<String>()
+                .to_lowercase()
+        };
+        let name = normalize(name);
+        Self::ALL
+            .into_iter()
+            .find(|mode| normalize(mode.display_name()) == name)
+    }
+}
+
+/// Sort mode for ordering repositories
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SortMode {
+    #[default]
+    Name,
+    Branch,
+    Remote,
+    Status,
+}
+
+impl SortMode {
+    /// Get the next sort mode in the cycle
+    pub fn next(&self) -> Self {
+        match self {
+            SortMode::Name => SortMode::Branch,
+            SortMode::Branch => SortMode::Remote,
+            SortMode::Remote => SortMode::Status,
+            SortMode::Status => SortMode::Name,
+        }
+    }
+
+    /// Get the sort mode of a table column, numbered from 1 like the keys selecting it
+    pub fn from_column(column: u32) -> Option<Self> {
+        match column {
+            1 => Some(SortMode::Name),
+            2 => Some(SortMode::Branch),
+            3 => Some(SortMode::Remote),
+            4 => Some(SortMode::Status),
+            _ => None,
+        }
+    }
+
+    /// Get the index of the table column sorted by this mode
+    pub fn column(&self) -> usize {
+        match self {
+            SortMode::Name => 0,
+            SortMode::Branch => 1,
+            SortMode::Remote => 2,
+            SortMode::Status => 3,
+        }
+    }
+
+    /// Get display name for the sort mode
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            SortMode::Name => "Name",
+            SortMode::Branch => "Branch",
+            SortMode::Remote => "Remote Status",
+            SortMode::Status => "Status",
+        }
+    }
+}
+
+/// Row of the repository table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableRow {
+    /// Header of the repositories sharing a remote host
+    Group {
+        host: String,
+        count: usize,
+        collapsed: bool,
+    },
+    /// Index of a repository in `App::repos`
+    Repo(usize),
+}
+
+/// Search prefix restricting the search to branch names
+const BRANCH_SEARCH_PREFIX: &str = "branch:";
+
+/// Search prefix restricting the search to remote hosts
+const HOST_SEARCH_PREFIX: &str = "host:";
+
+/// Group name of repositories without a remote host
+const LOCAL_GROUP: &str = "local";
+
+/// State of a running "clone all missing" batch
+struct CloneBatch {
+    /// Gate limiting concurrent clones; closed to cancel the batch
+    limiter: Arc<Semaphore>,
+    /// Repositories that have not finished yet
+    pending: HashSet<PathBuf>,
+    total: usize,
+    succeeded: usize,
+    failed: Vec<String>,
+    cancelled: usize,
+    /// Displayed missing repositories left out because they have no cached remote
+    no_remote: usize,
+}
+
+/// Fields a repository is sorted on, read once per sort instead of once per comparison
+struct SortKey {
+    /// Remote host, only when grouping by host
+    group: Option<String>,
+    pinned: bool,
+    missing: bool,
+    /// Lowercase branch, only when sorting by branch
+    branch: String,
+    /// Lowercase displayed name
+    name: String,
+    behind: Option<u32>,
+    ahead: Option<u32>,
+    clean: bool,
+}
+
+/// A merged branch offered for deletion, and whether it is checked
+pub type MergedBranch = (String, bool);
+
+/// Number of pending git updates applied at most between two redraws, so a steady stream
+/// of updates cannot freeze the screen
+const MAX_COALESCED_UPDATES: usize = 500;
+
+/// Entry of the upstream picker removing the upstream instead of changing it
+pub const UNSET_UPSTREAM: &str = "(unset upstream)";
+
+/// Number of failures kept in the error log
+const MAX_ERRORS: usize = 100;
+
+/// Status of a repository on a slow path that was never read
+pub const NOT_LOADED: &str = "not loaded";
+
+/// Resolve the path typed to move a repository, relative to the scanned directory
+///
+/// Absolute paths and `..` are refused: a repository moved out of the scanned directory
+/// would silently drop out of the cache.
+fn move_target(scan_path: &Path, typed: &str) -> std::result::Result<PathBuf, String> {
+    let mut relative = PathBuf::new();
+    for component in Path::new(typed).components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(format!(
+                    "Move within {}: use a relative path without ..",
+                    scan_path.display()
+                ));
+            }
+        }
+    }
+    if relative.as_os_str().is_empty() {
+        return Err("Type the new path of the repository".to_string());
+    }
+
+    let target = scan_path.join(relative);
+    if !target.starts_with(scan_path) {
+        return Err(format!("Move within {}", scan_path.display()));
+    }
+    Ok(target)
 }
 
 /// Application state
 pub struct App {
     pub repos: Vec<GitRepo>,
+    /// Whether the background scan is still adding repositories
+    scanning: bool,
+    /// Whether the first repository stays selected while the scan adds others, until a key
+    /// is pressed
+    follow_first_repo: bool,
     pub scan_path: String,
+    scan_paths: Vec<PathBuf>,
     pub table_state: TableState,
     should_quit: bool,
     needs_redraw: bool,
     event_handler: EventHandler,
     pub selected_repo: Option<String>,
     pub fetching_repos: Vec<usize>,
-    pub cloning_repos: Vec<usize>,
+    fetched_count: usize,
+    /// Last key press, or start of the last background fetch while idle
+    last_activity: std::time::Instant,
+    /// Index of the next repository fetched in the background while idle
+    idle_fetch_cursor: usize,
+    /// Repositories being fetched in the background while idle, left out of notifications
+    idle_fetches: Vec<usize>,
+    /// Whether each repository opts out of fetching in its `.git-repos.toml`, read once
+    /// instead of on every idle tick
+    idle_no_fetch: HashMap<usize, bool>,
+    pub cloning_repos: Vec<PathBuf>,
     pub deleting_repos: Vec<usize>,
+    pub updating_submodules: Vec<usize>,
+    pub archiving_repos: Vec<usize>,
     pub fetch_animation_frame: usize,
     pub filter_mode: FilterMode,
+    pub sort_mode: SortMode,
+    pub sort_descending: bool,
+    group_by_host: bool,
+    hide_missing: bool,
+    /// Repositories dropped from the list, kept in `repos` so that the indices of the
+    /// operations still running keep pointing at the same repositories
+    dropped: HashSet<usize>,
+    /// Whether both status columns are replaced by a single glyph
+    compact_status: bool,
+    pinned: HashSet<PathBuf>,
+    collapsed_groups: HashSet<String>,
     search_query: String,
     search_mode: bool,
+    regex_search: bool,
+    search_regex: Option<Result<Regex, regex::Error>>,
     delete_confirmation: Option<usize>,
+    incoming_preview: Option<(usize, Vec<String>)>,
+    /// Failures of background operations, oldest first, at most `MAX_ERRORS`
+    errors: VecDeque<String>,
+    error_log_open: bool,
+    /// Repository index, full `git status` lines and scroll offset of the status popup
+    full_status: Option<(usize, Vec<String>, usize)>,
+    /// Repository whose changes would be discarded, by path: its index can change while the
+    /// files are listed or the prompt is shown
+    discard_confirmation: Option<(PathBuf, Vec<String>)>,
+    update_all_confirmation: Option<Vec<usize>>,
+    branch_input: Option<(usize, String)>,
+    commit_input: Option<(usize, String)>,
+    move_input: Option<(usize, String)>,
+    pending_shell: Option<usize>,
+    clipboard: Option<arboard::Clipboard>,
+    color_enabled: bool,
+    upstream_picker: Option<(usize, Vec<String>, ListState)>,
+    prune_picker: Option<(usize, Vec<MergedBranch>, ListState)>,
+    worktree_picker: Option<(usize, Vec<Worktree>, ListState)>,
     root_path: Option<std::path::PathBuf>,
     pub cwd_file_enabled: bool,
+    settings: Settings,
+    size_limiter: Arc<Semaphore>,
+    clone_batch: Option<CloneBatch>,
+    status_message: Option<String>,
+    details_requested: HashSet<usize>,
 }
 
 impl App {
-    /// Sort repositories: existing first (alphabetically), then missing (alphabetically)
-    fn sort_repos(repos: &mut [GitRepo]) {
-        repos.sort_by(|a, b| match (a.is_missing(), b.is_missing()) {
-            (false, true) => std::cmp::Ordering::Less,
-            (true, false) => std::cmp::Ordering::Greater,
-            _ => {
-                let a_name = a.display_short().to_lowercase();
-                let b_name = b.display_short().to_lowercase();
-                a_name.cmp(&b_name)
-            }
-        });
-    }
-
-    /// Find repository index by path after sorting
+    /// Find repository index by path
     fn find_repo_index(repos: &[GitRepo], path: &std::path::Path) -> Option<usize> {
         repos.iter().position(|r| r.path() == path)
     }
@@ -144,23 +380,26 @@ impl App {
             .unwrap_or_else(|_| "error".to_string());
 
             // Perform fetch with fast-forward if repo has remote
-            if remote_status != "local-only" && remote_status != "error" {
+            if !matches!(remote_status.as_str(), "local-only" | "error" | CORRUPT) {
                 let fetch_result = tokio::task::spawn_blocking({
                     let path = path.clone();
                     move || GitRepo::fetch(&path, true) // Always fast-forward for manual update
                 })
                 .await;
 
-                if fetch_result.is_ok() {
+                if let Ok(result) = fetch_result {
                     // Re-read remote status after fetch
-                    let new_remote_status = tokio::task::spawn_blocking({
-                        let path = path.clone();
-                        move || GitRepo::read_remote_status(&path)
-                    })
-                    .await
-                    .unwrap_or_else(|_| "error".to_string());
-
-                    let _ = tx_clone.send(GitDataUpdate::RemoteStatus(idx, new_remote_status));
+                    load_remote_status(&tx_clone, idx, path.clone()).await;
+                    match result {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            let _ = tx_clone.send(GitDataUpdate::Diverged(idx));
+                        }
+                        Err(err) => {
+                            let _ =
+                                tx_clone.send(GitDataUpdate::UpdateFailed(idx, err.to_string()));
+                        }
+                    }
                 }
             } else {
                 let _ = tx_clone.send(GitDataUpdate::RemoteStatus(idx, remote_status));
@@ -178,83 +417,379 @@ impl App {
         });
     }
 
+    /// Spawn task to fetch a repository and read the commits it would fast-forward to
+    fn spawn_incoming_preview(
+        tx: tokio::sync::mpsc::UnboundedSender<GitDataUpdate>,
+        idx: usize,
+        path: std::path::PathBuf,
+    ) {
+        let tx_clone = tx.clone();
+        tokio::spawn(async move {
+            // Start fetch animation
+            let _ = tx_clone.send(GitDataUpdate::FetchProgress(idx));
+
+            let remote_status = tokio::task::spawn_blocking({
+                let path = path.clone();
+                move || GitRepo::read_remote_status(&path)
+            })
+            .await
+            .unwrap_or_else(|_| "error".to_string());
+
+            // Fetch without updating, then list what the upstream has on top of HEAD
+            if !matches!(remote_status.as_str(), "local-only" | "error" | CORRUPT) {
+                let fetch_result = tokio::task::spawn_blocking({
+                    let path = path.clone();
+                    move || GitRepo::fetch(&path, false)
+                })
+                .await;
+
+                match fetch_result {
+                    Ok(Ok(_)) => {
+                        // The working tree is untouched, only the remote status can change
+                        load_remote_status(&tx_clone, idx, path.clone()).await;
+
+                        let commits = tokio::task::spawn_blocking(move || {
+                            GitRepo::read_incoming_commits(&path)
+                        })
+                        .await
+                        .unwrap_or_default();
+
+                        let _ = tx_clone.send(GitDataUpdate::IncomingCommits(idx, commits));
+                    }
+                    // The commits of a stale upstream would look like the incoming ones
+                    Ok(Err(err)) => {
+                        warn!(path = %path.display(), "fetch failed: {}", err);
+                        let _ = tx_clone.send(GitDataUpdate::PreviewFailed(idx, err.to_string()));
+                    }
+                    Err(err) => warn!(path = %path.display(), "fetch task failed: {}", err),
+                }
+            } else {
+                let _ = tx_clone.send(GitDataUpdate::RemoteStatus(idx, remote_status));
+            }
+
+            // End fetch animation
+            let _ = tx_clone.send(GitDataUpdate::FetchComplete(idx));
+        });
+    }
+
+    /// Spawn task to fast-forward a repository that was already fetched
+    fn spawn_fast_forward(
+        tx: tokio::sync::mpsc::UnboundedSender<GitDataUpdate>,
+        idx: usize,
+        path: std::path::PathBuf,
+    ) {
+        let tx_clone = tx.clone();
+        tokio::spawn(async move {
+            // Start fetch animation
+            let _ = tx_clone.send(GitDataUpdate::FetchProgress(idx));
+
+            let fast_forwarded = tokio::task::spawn_blocking({
+                let path = path.clone();
+                move || GitRepo::fast_forward(&path)
+            })
+            .await;
+
+            let remote_status = tokio::task::spawn_blocking({
+                let path = path.clone();
+                move || GitRepo::read_remote_status(&path)
+            })
+            .await
+            .unwrap_or_else(|_| "error".to_string());
+
+            let status = tokio::task::spawn_blocking(move || GitRepo::read_status(&path))
+                .await
+                .unwrap_or_else(|_| "error".to_string());
+
+            let _ = tx_clone.send(GitDataUpdate::RemoteStatus(idx, remote_status));
+            let _ = tx_clone.send(GitDataUpdate::Status(idx, status));
+            match fast_forwarded {
+                Ok(Ok(false)) => {
+                    let _ = tx_clone.send(GitDataUpdate::Diverged(idx));
+                }
+                Ok(Err(err)) => {
+                    let _ = tx_clone.send(GitDataUpdate::UpdateFailed(idx, err.to_string()));
+                }
+                _ => {}
+            }
+
+            // End fetch animation
+            let _ = tx_clone.send(GitDataUpdate::FetchComplete(idx));
+        });
+    }
+
+    /// Spawn task to compute the on-disk size of a repository
+    ///
+    /// Size computations walk the whole repository, so they are throttled through
+    /// `limiter` to avoid thrashing the disk on a large root.
+    fn spawn_disk_usage_load(
+        tx: tokio::sync::mpsc::UnboundedSender<GitDataUpdate>,
+        limiter: Arc<Semaphore>,
+        idx: usize,
+        path: std::path::PathBuf,
+        exclude_objects: bool,
+    ) {
+        tokio::spawn(async move {
+            let Ok(_permit) = limiter.acquire_owned().await else {
+                return;
+            };
+
+            let size = tokio::task::spawn_blocking(move || {
+                GitRepo::read_disk_usage(&path, exclude_objects)
+            })
+            .await;
+
+            if let Ok(size) = size {
+                let _ = tx.send(GitDataUpdate::Size(idx, size));
+            }
+        });
+    }
+
     /// Create a new App instance
     pub fn new(repos: Vec<GitRepo>, scan_path: &Path, fetch: bool, update: bool) -> Self {
-        Self::new_with_root(repos, scan_path, fetch, update, None, false)
+        Self::new_with_root(
+            repos,
+            &[scan_path.to_path_buf()],
+            fetch,
+            update,
+            None,
+            false,
+            Settings::default(),
+        )
     }
 
     /// Create a new App instance with optional root path
     pub fn new_with_root(
-        mut repos: Vec<GitRepo>,
-        scan_path: &Path,
+        repos: Vec<GitRepo>,
+        scan_paths: &[PathBuf],
         fetch: bool,
         update: bool,
         root_path: Option<std::path::PathBuf>,
         cwd_file_enabled: bool,
+        settings: Settings,
     ) -> Self {
-        Self::sort_repos(&mut repos);
-
-        let mut table_state = TableState::default();
-        if !repos.is_empty() {
-            table_state.select(Some(0));
-        }
         // Convert to normal path display (strip \?\ prefix on Windows)
-        let path_str = scan_path.display().to_string();
-        let display_path = strip_unc_prefix(&path_str).to_string();
+        let scan_paths: Vec<PathBuf> = scan_paths
+            .iter()
+            .map(|path| strip_unc_pathbuf(path))
+            .collect();
+        let display_path = scan_paths
+            .iter()
+            .map(|path| strip_unc_prefix(&path.display().to_string()).to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
 
-        // Create event handler and spawn git data loading tasks
-        let repos_clone = repos.clone();
         let event_handler = EventHandler::new(
-            repos.len(),
-            move |idx| repos_clone[idx].path().to_path_buf(),
             fetch,
             update,
+            settings.skip_fetch_statuses.clone(),
+            settings.max_concurrent(),
         );
+        let size_limiter = Arc::new(Semaphore::new(MAX_CONCURRENT_SIZE_TASKS));
 
-        Self {
-            repos,
+        let mut app = Self {
+            repos: Vec::with_capacity(repos.len()),
+            scanning: false,
+            follow_first_repo: false,
             scan_path: display_path,
-            table_state,
+            scan_paths,
+            table_state: TableState::default(),
             should_quit: false,
             needs_redraw: false,
             event_handler,
             selected_repo: None,
             fetching_repos: Vec::new(),
+            fetched_count: 0,
+            last_activity: std::time::Instant::now(),
+            idle_fetch_cursor: 0,
+            idle_fetches: Vec::new(),
+            idle_no_fetch: HashMap::new(),
             cloning_repos: Vec::new(),
             deleting_repos: Vec::new(),
+            updating_submodules: Vec::new(),
+            archiving_repos: Vec::new(),
             fetch_animation_frame: 0,
             filter_mode: FilterMode::All,
+            sort_mode: settings.sort_mode,
+            sort_descending: false,
+            group_by_host: false,
+            hide_missing: false,
+            dropped: HashSet::new(),
+            compact_status: false,
+            pinned: settings.pinned_paths().into_iter().collect(),
+            collapsed_groups: HashSet::new(),
             search_query: String::new(),
             search_mode: false,
+            regex_search: false,
+            search_regex: None,
             delete_confirmation: None,
+            incoming_preview: None,
+            errors: VecDeque::new(),
+            error_log_open: false,
+            full_status: None,
+            discard_confirmation: None,
+            update_all_confirmation: None,
+            branch_input: None,
+            commit_input: None,
+            move_input: None,
+            pending_shell: None,
+            clipboard: None,
+            color_enabled: true,
+            upstream_picker: None,
+            prune_picker: None,
+            worktree_picker: None,
             root_path,
             cwd_file_enabled,
+            settings,
+            size_limiter,
+            clone_batch: None,
+            status_message: None,
+            details_requested: HashSet::new(),
+        };
+        for repo in repos {
+            app.add_repo(repo);
+        }
+        app.select_first();
+        app
+    }
+
+    /// Add a repository to the list and start reading its statuses
+    fn add_repo(&mut self, mut repo: GitRepo) {
+        let idx = self.repos.len();
+        if repo.is_missing() {
+            self.repos.push(repo);
+            return;
+        }
+
+        // Repositories on slow paths keep their last known status until refreshed
+        if self.settings.is_slow_path(repo.path()) {
+            let (remote_status, status) = repo.loaded_statuses();
+            let (has_remote_status, has_status) = (remote_status.is_some(), status.is_some());
+            if !has_remote_status {
+                repo.set_remote_status(NOT_LOADED.to_string());
+            }
+            if !has_status {
+                repo.set_status(NOT_LOADED.to_string());
+            }
+        } else {
+            // Remote status cached by the previous run, to skip fetching some repositories
+            let cached_status = repo.loaded_statuses().0.map(str::to_string);
+            repo.reset_status();
+            self.event_handler
+                .load_repo(idx, repo.path().to_path_buf(), cached_status);
+        }
+
+        if self.settings.show_disk_usage {
+            Self::spawn_disk_usage_load(
+                self.event_handler.git_tx(),
+                self.size_limiter.clone(),
+                idx,
+                repo.path().to_path_buf(),
+                self.settings.disk_usage_exclude_objects,
+            );
+        }
+        self.repos.push(repo);
+    }
+
+    /// Scan directories in the background, adding the repositories to the list as they are
+    /// found, so large roots populate live instead of delaying the TUI
+    ///
+    /// See `cache::scan_repos` for `cache_root`. The walk runs on its own thread, which
+    /// quitting does not wait for.
+    pub fn scan(
+        &mut self,
+        scan_paths: Vec<PathBuf>,
+        cache_root: Option<PathBuf>,
+        include_hidden: bool,
+    ) {
+        self.scanning = true;
+        self.follow_first_repo = true;
+        let tx = self.event_handler.git_tx();
+        std::thread::spawn(move || {
+            let skipped = crate::cache::scan_repos(
+                &scan_paths,
+                cache_root.as_deref(),
+                include_hidden,
+                |repo| {
+                    let _ = tx.send(GitDataUpdate::RepoFound(Box::new(repo)));
+                },
+            );
+            let _ = tx.send(GitDataUpdate::ScanComplete(skipped));
+        });
+    }
+
+    /// Check if the background scan is still looking for repositories
+    pub fn is_scanning(&self) -> bool {
+        self.scanning
+    }
+
+    /// Refuse an operation while the scan runs, as the status loads it starts refer to
+    /// repositories by index
+    fn wait_for_scan(&mut self) -> bool {
+        if self.scanning {
+            self.status_message = Some("Wait for the scan to finish".to_string());
+            self.needs_redraw = true;
         }
+        self.scanning
     }
 
     /// Run the TUI application
     pub async fn run(&mut self) -> Result<()> {
         // Setup terminal
         enable_raw_mode()?;
-        let mut stdout = io::stdout();
-        execute!(stdout, EnterAlternateScreen)?;
-        let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend)?;
+        let mut terminal = self.new_terminal()?;
 
         // Main loop
         let result = self.run_loop(&mut terminal).await;
 
         // Restore terminal
         disable_raw_mode()?;
-        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-        terminal.show_cursor()?;
+        self.release_terminal(&mut terminal)?;
 
         result
     }
 
+    /// Create the terminal, on the alternate screen or inline below the cursor
+    fn new_terminal(&self) -> Result<Terminal<CrosstermBackend<io::Stdout>>> {
+        let mut stdout = io::stdout();
+        if !self.settings.inline {
+            execute!(stdout, EnterAlternateScreen)?;
+            return Ok(Terminal::new(CrosstermBackend::new(stdout))?);
+        }
+
+        // The table needs a few lines besides the detail pane and status bar
+        let height = self.settings.inline_height.max(10);
+        Ok(Terminal::with_options(
+            CrosstermBackend::new(stdout),
+            TerminalOptions {
+                viewport: Viewport::Inline(height),
+            },
+        )?)
+    }
+
+    /// Give the terminal back, leaving the last frame in the scrollback when inline
+    fn release_terminal(
+        &self,
+        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    ) -> Result<()> {
+        if self.settings.inline {
+            let area = terminal.get_frame().area();
+            terminal.set_cursor_position((0, area.bottom().saturating_sub(1)))?;
+            terminal.show_cursor()?;
+            println!();
+        } else {
+            execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+            terminal.show_cursor()?;
+        }
+        Ok(())
+    }
+
     /// Get the repositories (for saving cache)
-    pub fn repos(&self) -> &[GitRepo] {
-        &self.repos
+    pub fn repos(&self) -> impl Iterator<Item = &GitRepo> {
+        self.repos
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !self.dropped.contains(idx))
+            .map(|(_, repo)| repo)
     }
 
     /// Main event loop
@@ -262,10 +797,27 @@ impl App {
         &mut self,
         terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     ) -> Result<()> {
-        // Create a timer for animation updates
-        let mut animation_interval = tokio::time::interval(tokio::time::Duration::from_millis(100));
+        // Create a timer for animation updates, only polled while an operation is running
+        let mut animation_interval = tokio::time::interval(tokio::time::Duration::from_millis(
+            self.settings.animation_interval_ms.max(1),
+        ));
+        animation_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        // Background fetches while idle, checked every second when enabled
+        let idle_fetch_after = self
+            .settings
+            .idle_fetch_secs
+            .filter(|&secs| secs > 0)
+            .map(tokio::time::Duration::from_secs);
+        let mut idle_interval = tokio::time::interval(tokio::time::Duration::from_secs(1));
+        idle_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
 
         loop {
+            // Repositories found since the last frame may sort before the selected one
+            if self.follow_first_repo {
+                self.select_first();
+            }
+            self.load_selected_details();
             terminal.draw(|f| f.render_widget(&mut *self, f.area()))?;
             self.needs_redraw = false;
 
@@ -279,11 +831,25 @@ impl App {
                     if let Some(event) = result? {
                         self.handle_event(event)?;
                     }
+                    // Apply the rest of a burst of updates (e.g. statuses read at startup)
+                    // before drawing, so it costs a single redraw
+                    for _ in 0..MAX_COALESCED_UPDATES {
+                        let Some(update) = self.event_handler.try_next_git_update() else {
+                            break;
+                        };
+                        self.handle_git_update(update);
+                    }
+                    if let Some(idx) = self.pending_shell.take() {
+                        self.open_shell(terminal, idx)?;
+                    }
                 }
-                _ = animation_interval.tick() => {
-                    if !self.fetching_repos.is_empty() || !self.cloning_repos.is_empty() || !self.deleting_repos.is_empty() {
-                        self.fetch_animation_frame = (self.fetch_animation_frame + 1) % 10;
-                        self.needs_redraw = true;
+                _ = animation_interval.tick(), if self.is_busy() => {
+                    self.fetch_animation_frame = self.fetch_animation_frame.wrapping_add(1);
+                    self.needs_redraw = true;
+                }
+                _ = idle_interval.tick(), if idle_fetch_after.is_some() => {
+                    if let Some(idle) = idle_fetch_after {
+                        self.fetch_while_idle(idle);
                     }
                 }
             }
@@ -291,71 +857,548 @@ impl App {
         Ok(())
     }
 
-    /// Handle terminal events
-    fn handle_event(&mut self, event: TerminalEvent) -> Result<()> {
-        match event {
-            TerminalEvent::Key(code, modifiers) => {
-                if self.is_confirmation_mode() {
-                    self.handle_confirmation_key(code);
-                } else if self.search_mode {
-                    self.handle_search_key(code);
-                } else {
-                    self.handle_normal_key(code, modifiers);
-                }
-            }
-            TerminalEvent::GitUpdate(update) => self.handle_git_update(update),
+    /// Fetch the next few repositories once the TUI has been idle for `idle`
+    ///
+    /// Nothing is fetched while another operation is running, so user-initiated fetches,
+    /// clones and the like never compete with background fetches. Repositories are fetched
+    /// in turn, without fast-forwarding, and the next batch waits for another idle period.
+    fn fetch_while_idle(&mut self, idle: std::time::Duration) {
+        if self.is_busy() || self.last_activity.elapsed() < idle || self.repos.is_empty() {
+            return;
         }
-        Ok(())
-    }
 
-    /// Handle key press in search mode
-    fn handle_search_key(&mut self, code: KeyCode) {
-        match code {
-            KeyCode::Esc => {
-                self.search_mode = false;
-                self.search_query.clear();
-                self.table_state.select(Some(0));
-                self.needs_redraw = true;
-            }
-            KeyCode::Enter => {
-                self.search_mode = false;
-                self.needs_redraw = true;
+        let count = self.repos.len();
+        let batch_size = self.settings.idle_fetch_batch.max(1);
+        let mut batch = Vec::new();
+        for idx in (0..count).map(|offset| (self.idle_fetch_cursor + offset) % count) {
+            let repo = &self.repos[idx];
+            // Slow paths are only read on demand, even with a cached remote status
+            if repo.is_missing()
+                || self.dropped.contains(&idx)
+                || self.settings.is_slow_path(repo.path())
+                || matches!(
+                    repo.remote_status(),
+                    "local-only" | "error" | "loading..." | NOT_LOADED | CORRUPT
+                )
+            {
+                continue;
             }
-            KeyCode::Backspace => {
-                self.search_query.pop();
-                self.table_state.select(Some(0));
-                self.needs_redraw = true;
-            }
-            KeyCode::Char(c) => {
-                self.search_query.push(c);
-                self.table_state.select(Some(0));
-                self.needs_redraw = true;
+            let no_fetch = *self
+                .idle_no_fetch
+                .entry(idx)
+                .or_insert_with(|| RepoSettings::load(repo.path()).no_fetch == Some(true));
+            if !no_fetch {
+                batch.push(idx);
+                if batch.len() == batch_size {
+                    break;
+                }
             }
-            _ => {}
+        }
+        let Some(&last) = batch.last() else {
+            return;
+        };
+        self.idle_fetch_cursor = last + 1;
+        self.last_activity = std::time::Instant::now();
+
+        for idx in batch {
+            debug!(path = %self.repos[idx].path().display(), "fetching while idle");
+            self.idle_fetches.push(idx);
+            let tx = self.event_handler.git_tx();
+            let path = self.repos[idx].path().to_path_buf();
+            tokio::spawn(fetch_repo(tx, idx, path, false));
         }
     }
 
-    /// Handle key press in normal mode
+    /// Copy a report of the displayed repositories to the clipboard.
     ///
-    /// Shortcuts:
-    ///   q / Q: Quit
-    ///   Ctrl+C: Quit
-    ///   Enter: Select repo (if cwd_file_enabled)
-    ///   j / Down: Next repo
-    ///   k / Up: Previous repo
-    ///   [ / ]: Cycle filter mode
-    ///   /: Search
-    ///   d / D: Drop repo
-    ///   c / C: Clone missing repo
-    ///   u / U: Update selected repo (fetch + status)
-    fn handle_normal_key(&mut self, code: KeyCode, modifiers: KeyModifiers) {
-        match code {
-            KeyCode::Char('q') | KeyCode::Char('Q') => {
-                self.should_quit = true;
+    /// This is triggered by the 'y' shortcut in normal mode. When no clipboard is
+    /// available (e.g. over SSH), the report is written to a file instead.
+    fn handle_copy_report(&mut self) {
+        let report = self.build_report();
+        let count = self.filtered_repos().len();
+        let repos_text = if count == 1 {
+            "1 repository".to_string()
+        } else {
+            format!("{} repositories", count)
+        };
+
+        let copied = self.copy_to_clipboard(report.clone());
+        self.status_message = Some(if copied {
+            format!("Copied the status of {} to the clipboard", repos_text)
+        } else {
+            let report_path = std::env::temp_dir().join("git-repos-report.txt");
+            match std::fs::write(&report_path, report) {
+                Ok(()) => format!(
+                    "Wrote the status of {} to {}",
+                    repos_text,
+                    report_path.display()
+                ),
+                Err(err) => format!("Failed to write the report: {}", err),
             }
-            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+        });
+        self.needs_redraw = true;
+    }
+
+    /// Select the repository to change directory to on exit, and copy its path, without
+    /// quitting
+    ///
+    /// This is what Enter does when `keep_open` is set.
+    fn select_and_stay(&mut self) {
+        let Some(repo) = self.table_state.selected().and_then(|i| self.repos.get(i)) else {
+            return;
+        };
+        let name = repo.display_short();
+        let path = strip_unc_prefix(&repo.path().display().to_string()).to_string();
+        if self.cwd_file_enabled {
+            self.selected_repo = Some(path.clone());
+        }
+
+        let copied = self.copy_to_clipboard(path.clone());
+        self.status_message = Some(match (self.cwd_file_enabled, copied) {
+            (true, true) => format!("Selected {}, path copied, quit to go there", name),
+            (true, false) => format!("Selected {}, quit to go there", name),
+            (false, true) => format!("Copied the path of {}", name),
+            (false, false) => format!("No clipboard, path of {} is {}", name, path),
+        });
+        self.needs_redraw = true;
+    }
+
+    /// Hint about what Enter does, shown in the status bar
+    pub fn enter_hint(&self) -> Option<&'static str> {
+        if self.settings.keep_open {
+            Some("Enter: select, stay open | Alt+Enter: select and quit")
+        } else if self.cwd_file_enabled {
+            Some("Enter: go to repo and quit")
+        } else {
+            None
+        }
+    }
+
+    /// Copy text to the clipboard, returning false when there is no clipboard
+    fn copy_to_clipboard(&mut self, text: String) -> bool {
+        // The clipboard is kept alive: on X11, its content is lost when it is dropped
+        if self.clipboard.is_none() {
+            self.clipboard = arboard::Clipboard::new().ok();
+        }
+        self.clipboard
+            .as_mut()
+            .is_some_and(|clipboard| clipboard.set_text(text).is_ok())
+    }
+
+    /// Copy the `git clone` command recreating the selected repository, at the same path
+    /// relative to the scanned directory.
+    ///
+    /// This is triggered by the Ctrl+Y shortcut in normal mode. Missing repositories use
+    /// their cached remote URL.
+    fn handle_copy_clone_command(&mut self) {
+        let Some(repo) = self.table_state.selected().and_then(|i| self.repos.get(i)) else {
+            return;
+        };
+        let name = repo.display_short();
+        let Some(remote_url) = repo.get_remote_url() else {
+            self.status_message = Some(format!("No remote URL for {}, nothing to clone", name));
+            self.needs_redraw = true;
+            return;
+        };
+        let cleaned_path = strip_unc_pathbuf(repo.path());
+        let directory = self
+            .scan_path_of(repo.path())
+            .and_then(|scan_path| cleaned_path.strip_prefix(scan_path).ok())
+            .map(|relative_path| relative_path.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_else(|| cleaned_path.display().to_string());
+
+        let command = format!(
+            "git clone {} {}",
+            shell_quote(&remote_url),
+            shell_quote(&directory)
+        );
+        self.status_message = Some(if self.copy_to_clipboard(command.clone()) {
+            format!("Copied the clone command of {} to the clipboard", name)
+        } else {
+            format!("No clipboard, clone {} with: {}", name, command)
+        });
+        self.needs_redraw = true;
+    }
+
+    /// Read the commit checked out in the selected repository, to copy its SHA.
+    ///
+    /// This is triggered by the 'Y' shortcut in normal mode.
+    fn handle_copy_commit(&mut self) {
+        let Some(selected) = self.table_state.selected() else {
+            return;
+        };
+        let Some(repo) = self.repos.get(selected) else {
+            return;
+        };
+        if repo.is_missing() {
+            return;
+        }
+
+        let tx = self.event_handler.git_tx();
+        let path = repo.path().to_path_buf();
+        tokio::spawn(async move {
+            let commit = tokio::task::spawn_blocking(move || GitRepo::read_head_commit(&path))
+                .await
+                .unwrap_or_default();
+            let _ = tx.send(GitDataUpdate::HeadCommit(selected, commit));
+        });
+    }
+
+    /// Build a plain text table of the displayed repositories, as shown on screen
+    fn build_report(&self) -> String {
+        let rows: Vec<[String; 4]> = self
+            .filtered_repos()
+            .into_iter()
+            .map(|idx| {
+                let repo = &self.repos[idx];
+                if repo.is_missing() {
+                    let missing = if repo.get_remote_url().is_some() {
+                        "missing"
+                    } else {
+                        "missing (no remote)"
+                    };
+                    [
+                        repo.display_name(),
+                        String::new(),
+                        missing.to_string(),
+                        String::new(),
+                    ]
+                } else {
+                    [
+                        repo.display_name(),
+                        repo.branch().to_string(),
+                        repo.remote_status().to_string(),
+                        repo.status().to_string(),
+                    ]
+                }
+            })
+            .collect();
+
+        let mut widths = [0; 3];
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        rows.iter()
+            .map(|[name, branch, remote, status]| {
+                let line = format!(
+                    "{:<name_width$}  {:<branch_width$}  {:<remote_width$}  {}",
+                    name,
+                    branch,
+                    remote,
+                    status,
+                    name_width = widths[0],
+                    branch_width = widths[1],
+                    remote_width = widths[2],
+                );
+                format!("{}\n", line.trim_end())
+            })
+            .collect()
+    }
+
+    /// Suspend the TUI and run an interactive shell in a repository until it exits
+    fn open_shell(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        idx: usize,
+    ) -> Result<()> {
+        let Some(repo) = self.repos.get(idx) else {
+            return Ok(());
+        };
+        let path = repo.path().to_path_buf();
+        let shell = std::env::var("SHELL").unwrap_or_else(|_| {
+            if cfg!(windows) {
+                "powershell".to_string()
+            } else {
+                "/bin/sh".to_string()
+            }
+        });
+
+        // Hand the terminal over to the shell
+        self.event_handler.reset_terminal_events();
+        disable_raw_mode()?;
+        self.release_terminal(terminal)?;
+
+        let result = std::process::Command::new(&shell)
+            .current_dir(&path)
+            .status();
+
+        // Inline, the list is drawn again below the output of the shell
+        enable_raw_mode()?;
+        *terminal = self.new_terminal()?;
+        terminal.clear()?;
+        self.needs_redraw = true;
+
+        if let Err(err) = result {
+            self.status_message = Some(format!("Failed to run {}: {}", shell, err));
+            return Ok(());
+        }
+
+        // Commands run in the shell may have changed the repository
+        if let Some(repo) = self.repos.get_mut(idx) {
+            repo.reset_status();
+        }
+        let tx = self.event_handler.git_tx();
+        tokio::spawn(async move {
+            load_status(&tx, idx, path).await;
+        });
+        Ok(())
+    }
+
+    /// Check if a scan, fetch, clone or delete is running
+    pub fn is_busy(&self) -> bool {
+        self.scanning
+            || !self.fetching_repos.is_empty()
+            || !self.cloning_repos.is_empty()
+            || !self.deleting_repos.is_empty()
+            || !self.updating_submodules.is_empty()
+            || !self.archiving_repos.is_empty()
+    }
+
+    /// Lazily read the details shown in the detail pane for the selected repository
+    fn load_selected_details(&mut self) {
+        let Some(selected) = self.table_state.selected() else {
+            return;
+        };
+        let Some(repo) = self.repos.get(selected) else {
+            return;
+        };
+        let loaded = if repo.is_corrupt() {
+            repo.corruption().is_some()
+        } else {
+            repo.upstream().is_some() && repo.latest_tag().is_some() && repo.identity().is_some()
+        };
+        if repo.is_missing() || loaded {
+            return;
+        }
+        if !self.details_requested.insert(selected) {
+            return;
+        }
+
+        let tx = self.event_handler.git_tx();
+        let path = repo.path().to_path_buf();
+        if repo.is_corrupt() {
+            tokio::spawn(async move {
+                let corruption =
+                    tokio::task::spawn_blocking(move || GitRepo::read_corruption(&path))
+                        .await
+                        .ok()
+                        .flatten()
+                        // Repaired since its status was read
+                        .unwrap_or_else(|| "none, refresh with r".to_string());
+                let _ = tx.send(GitDataUpdate::Corruption(selected, corruption));
+            });
+            return;
+        }
+        tokio::spawn(async move {
+            let (latest_tag, identity, upstream) = tokio::task::spawn_blocking(move || {
+                (
+                    GitRepo::read_latest_tag(&path),
+                    GitRepo::read_identity(&path),
+                    GitRepo::read_upstream(&path),
+                )
+            })
+            .await
+            .unwrap_or_else(|_| {
+                (
+                    "error".to_string(),
+                    "error".to_string(),
+                    "error".to_string(),
+                )
+            });
+            // The upstream completes the request, so it is sent last
+            let _ = tx.send(GitDataUpdate::LatestTag(selected, latest_tag));
+            let _ = tx.send(GitDataUpdate::Identity(selected, identity));
+            let _ = tx.send(GitDataUpdate::Upstream(selected, upstream));
+        });
+    }
+
+    /// Fetch the tags of the selected repository, then show its latest tag
+    fn handle_fetch_tags(&mut self) {
+        let Some(selected) = self.table_state.selected() else {
+            return;
+        };
+        let Some(repo) = self.repos.get(selected) else {
+            return;
+        };
+        if repo.is_missing() || repo.get_remote_url().is_none() {
+            return;
+        }
+
+        self.status_message = Some(format!("Fetching tags of {}...", repo.display_short()));
+        self.needs_redraw = true;
+        let tx = self.event_handler.git_tx();
+        let path = repo.path().to_path_buf();
+        tokio::spawn(async move {
+            let result = tokio::task::spawn_blocking(move || {
+                GitRepo::fetch_tags(&path)
+                    .map(|()| GitRepo::read_latest_tag(&path))
+                    .map_err(|err| err.to_string())
+            })
+            .await
+            .unwrap_or_else(|err| Err(err.to_string()));
+            let _ = tx.send(GitDataUpdate::TagsFetched(selected, result));
+        });
+    }
+
+    /// Handle terminal events
+    fn handle_event(&mut self, event: TerminalEvent) -> Result<()> {
+        match event {
+            TerminalEvent::Key(code, modifiers) => {
+                self.last_activity = std::time::Instant::now();
+                self.follow_first_repo = false;
+
+                // Any key dismisses the last status message
+                if self.status_message.take().is_some() {
+                    self.needs_redraw = true;
+                }
+
+                if self.is_confirmation_mode() {
+                    self.handle_confirmation_key(code);
+                } else if self.is_discard_mode() {
+                    self.handle_discard_key(code);
+                } else if self.is_update_all_mode() {
+                    self.handle_update_all_key(code);
+                } else if self.error_log_open {
+                    self.handle_error_log_key(code);
+                } else if self.is_full_status_mode() {
+                    self.handle_full_status_key(code);
+                } else if self.is_incoming_mode() {
+                    self.handle_incoming_key(code);
+                } else if self.is_upstream_picker_mode() {
+                    self.handle_upstream_picker_key(code);
+                } else if self.is_prune_picker_mode() {
+                    self.handle_prune_picker_key(code);
+                } else if self.is_worktree_picker_mode() {
+                    self.handle_worktree_picker_key(code);
+                } else if self.is_branch_input_mode() {
+                    self.handle_branch_input_key(code);
+                } else if self.is_commit_input_mode() {
+                    self.handle_commit_input_key(code);
+                } else if self.is_move_input_mode() {
+                    self.handle_move_input_key(code);
+                } else if self.search_mode {
+                    self.handle_search_key(code, modifiers);
+                } else {
+                    self.handle_normal_key(code, modifiers);
+                }
+            }
+            TerminalEvent::GitUpdate(update) => self.handle_git_update(update),
+        }
+        Ok(())
+    }
+
+    /// Handle key press in search mode
+    ///
+    /// Ctrl+R toggles between substring and regex matching.
+    fn handle_search_key(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+        match code {
+            KeyCode::Esc => {
+                self.search_mode = false;
+                self.search_query.clear();
+                self.update_search_regex();
+                self.select_first();
+                self.needs_redraw = true;
+            }
+            KeyCode::Enter => {
+                self.search_mode = false;
+                self.needs_redraw = true;
+            }
+            KeyCode::Backspace => {
+                self.search_query.pop();
+                self.update_search_regex();
+                self.select_first();
+                self.needs_redraw = true;
+            }
+            KeyCode::Char('r') if modifiers.contains(KeyModifiers::CONTROL) => {
+                self.regex_search = !self.regex_search;
+                self.update_search_regex();
+                self.select_first();
+                self.needs_redraw = true;
+            }
+            KeyCode::Char(c) => {
+                self.search_query.push(c);
+                self.update_search_regex();
+                self.select_first();
+                self.needs_redraw = true;
+            }
+            _ => {}
+        }
+    }
+
+    /// Recompile the search regex after the query or the search mode changed
+    fn update_search_regex(&mut self) {
+        self.search_regex = if self.regex_search && !self.search_query.is_empty() {
+            Some(
+                RegexBuilder::new(&self.search_query)
+                    .case_insensitive(true)
+                    .build(),
+            )
+        } else {
+            None
+        };
+    }
+
+    /// Handle key press in normal mode
+    ///
+    /// Shortcuts:
+    ///   q / Q: Quit
+    ///   Ctrl+C: Quit
+    ///   Enter: Select repo (if cwd_file_enabled), without quitting when keep_open is set
+    ///   Alt+Enter: Select repo and quit, when keep_open is set
+    ///   j / Down: Next repo
+    ///   k / Up: Previous repo
+    ///   n / N: Jump to the next / previous repo needing attention
+    ///   [ / ]: Cycle filter mode
+    ///   s / S: Cycle sort mode
+    ///   1-4: Sort by a column, pressing again reverses the order
+    ///   g / G: Group repos by remote host
+    ///   z: Collapse or expand the group of selected repo
+    ///   Z: Expand all groups
+    ///   o / O: Hide or show missing repos
+    ///   *: Pin or unpin selected repo at the top of the list
+    ///   /: Search
+    ///   d / D: Drop repo
+    ///   c: Clone missing repo
+    ///   C: Clone all missing repos
+    ///   Esc: Cancel the running batch clone
+    ///   u: Update selected repo (fetch + status)
+    ///   U: Update all displayed repos
+    ///   p / P: Preview incoming commits before updating
+    ///   r / R: Refresh status of selected repo (no fetch)
+    ///   b / B: Create a new branch in selected repo
+    ///   t / T: Set upstream of selected repo when it has none, or fix a stale one
+    ///   m / M: Update the submodules of selected repo
+    ///   h / H: Detect the default branch of selected repo from origin
+    ///   f / F: Fetch the tags of selected repo
+    ///   l / L: Delete local branches of selected repo merged into the default branch
+    ///   w / W: Pick a worktree of selected repo to change directory to
+    ///   !: Open a shell in selected repo
+    ///   i / I: Commit all changes of selected repo
+    ///   x / X: Discard all local changes of selected repo, after confirmation
+    ///   v / V: Move selected repo within the scanned directory
+    ///   a / A: Archive selected repo to a git bundle
+    ///   e: Cycle how diverged branches are updated (ff-only, rebase, merge)
+    ///   E: Show the failures of background operations
+    ///   Tab: Show the full `git status` of selected repo
+    ///   y: Copy a status report of the displayed repos
+    ///   Y: Copy the SHA of the commit checked out in selected repo
+    ///   Ctrl+Y: Copy a `git clone` command for selected repo
+    ///   @: Show only the repos on the remote host of selected repo
+    ///   .: Toggle compact status glyphs
+    fn handle_normal_key(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+        match code {
+            KeyCode::Char('q') | KeyCode::Char('Q') => {
+                self.should_quit = true;
+            }
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
                 self.should_quit = true;
             }
+            KeyCode::Enter if self.settings.keep_open && !modifiers.contains(KeyModifiers::ALT) => {
+                self.select_and_stay();
+            }
             KeyCode::Enter => {
                 if self.cwd_file_enabled
                     && let Some(repo) = self.table_state.selected().and_then(|i| self.repos.get(i))
@@ -370,30 +1413,142 @@ impl App {
             KeyCode::Up | KeyCode::Char('k') => {
                 self.previous();
             }
+            KeyCode::Char('e') => {
+                self.cycle_update_strategy();
+            }
+            KeyCode::Char('E') => {
+                self.handle_show_errors();
+            }
+            KeyCode::Char('@') => {
+                self.handle_filter_by_host();
+            }
+            KeyCode::Char('n') => {
+                self.jump_to_attention(true);
+            }
+            KeyCode::Char('N') => {
+                self.jump_to_attention(false);
+            }
             KeyCode::Char('[') => {
                 self.filter_mode = self.filter_mode.previous();
-                self.table_state.select(Some(0));
+                self.select_first();
                 self.needs_redraw = true;
             }
             KeyCode::Char(']') => {
                 self.filter_mode = self.filter_mode.next();
-                self.table_state.select(Some(0));
+                self.select_first();
                 self.needs_redraw = true;
             }
+            KeyCode::Char('s') | KeyCode::Char('S') => {
+                self.cycle_sort_mode();
+            }
+            KeyCode::Char('f') | KeyCode::Char('F') => {
+                self.handle_fetch_tags();
+            }
+            KeyCode::Char('*') => {
+                self.toggle_pin();
+            }
+            KeyCode::Char('o') | KeyCode::Char('O') => {
+                self.toggle_hide_missing();
+            }
+            KeyCode::Char('g') | KeyCode::Char('G') => {
+                self.toggle_group_by_host();
+            }
+            KeyCode::Char('.') => {
+                self.toggle_compact_status();
+            }
+            KeyCode::Char('z') => {
+                self.toggle_collapse_group();
+            }
+            KeyCode::Char('Z') => {
+                self.expand_all_groups();
+            }
+            KeyCode::Char(digit @ '1'..='4') => {
+                if let Some(sort_mode) = digit.to_digit(10).and_then(SortMode::from_column) {
+                    self.sort_by_column(sort_mode);
+                }
+            }
             KeyCode::Char('/') => {
                 self.search_mode = true;
                 self.search_query.clear();
+                self.update_search_regex();
                 self.needs_redraw = true;
             }
             KeyCode::Char('d') | KeyCode::Char('D') => {
                 self.handle_drop_repo();
             }
-            KeyCode::Char('c') | KeyCode::Char('C') => {
+            KeyCode::Char('c') => {
                 self.handle_clone_repo();
             }
-            KeyCode::Char('u') | KeyCode::Char('U') => {
+            KeyCode::Char('C') => {
+                self.handle_clone_all_missing();
+            }
+            KeyCode::Esc => {
+                self.cancel_clone_batch();
+            }
+            KeyCode::Char('u') => {
                 self.handle_update_repo();
             }
+            KeyCode::Char('U') => {
+                self.handle_update_all();
+            }
+            KeyCode::Char('p') | KeyCode::Char('P') => {
+                self.handle_preview_incoming();
+            }
+            KeyCode::Char('r') | KeyCode::Char('R') => {
+                self.handle_refresh_repo();
+            }
+            KeyCode::Char('b') | KeyCode::Char('B') => {
+                self.handle_new_branch();
+            }
+            KeyCode::Char('t') | KeyCode::Char('T') => {
+                self.handle_set_upstream();
+            }
+            KeyCode::Char('m') | KeyCode::Char('M') => {
+                self.handle_update_submodules();
+            }
+            KeyCode::Char('h') | KeyCode::Char('H') => {
+                self.handle_detect_default_branch();
+            }
+            KeyCode::Char('l') | KeyCode::Char('L') => {
+                self.handle_prune_branches();
+            }
+            KeyCode::Char('w') | KeyCode::Char('W') => {
+                self.handle_pick_worktree();
+            }
+            KeyCode::Char('i') | KeyCode::Char('I') => {
+                self.handle_commit_all();
+            }
+            KeyCode::Char('v') | KeyCode::Char('V') => {
+                self.handle_move_repo();
+            }
+            KeyCode::Char('x') | KeyCode::Char('X') => {
+                self.handle_discard_changes();
+            }
+            KeyCode::Char('a') | KeyCode::Char('A') => {
+                self.handle_archive_repo();
+            }
+            KeyCode::Char('y') if modifiers.contains(KeyModifiers::CONTROL) => {
+                self.handle_copy_clone_command();
+            }
+            KeyCode::Char('y') => {
+                self.handle_copy_report();
+            }
+            KeyCode::Char('Y') => {
+                self.handle_copy_commit();
+            }
+            KeyCode::Tab => {
+                self.handle_show_full_status();
+            }
+            KeyCode::Char('!') => {
+                if let Some(selected) = self.table_state.selected()
+                    && self
+                        .repos
+                        .get(selected)
+                        .is_some_and(|repo| !repo.is_missing())
+                {
+                    self.pending_shell = Some(selected);
+                }
+            }
             _ => {}
         }
     }
@@ -411,116 +1566,1902 @@ impl App {
         }
     }
 
-    /// Update the selected repository (fetch + status), with animation.
-    ///
-    /// This is triggered by the 'u' shortcut in normal mode.
-    fn handle_update_repo(&mut self) {
-        let Some(selected) = self.table_state.selected() else {
-            return;
-        };
-        let Some(repo) = self.repos.get(selected) else {
+    /// Handle key press while typing a new branch name
+    fn handle_branch_input_key(&mut self, code: KeyCode) {
+        let Some((_, name)) = &mut self.branch_input else {
             return;
         };
-        if repo.is_missing() {
-            return;
-        }
-        // Mark as fetching for animation
-        if !self.fetching_repos.contains(&selected) {
-            self.fetching_repos.push(selected);
-        }
-        self.needs_redraw = true;
+        match code {
+            KeyCode::Esc => {
+                self.branch_input = None;
+            }
+            KeyCode::Enter => {
+                self.perform_new_branch();
+            }
+            KeyCode::Backspace => {
+                name.pop();
+            }
+            KeyCode::Char(c) => {
+                name.push(c);
+            }
+            _ => {}
+        }
+        self.needs_redraw = true;
+    }
+
+    /// Prompt for the name of a branch to create in the selected repository.
+    ///
+    /// This is triggered by the 'b' shortcut in normal mode.
+    fn handle_new_branch(&mut self) {
+        let Some(selected) = self.table_state.selected() else {
+            return;
+        };
+        let Some(repo) = self.repos.get(selected) else {
+            return;
+        };
+        if repo.is_missing() {
+            return;
+        }
+        self.branch_input = Some((selected, String::new()));
+        self.needs_redraw = true;
+    }
+
+    /// Create the branch typed in the prompt
+    fn perform_new_branch(&mut self) {
+        let Some((idx, name)) = self.branch_input.take() else {
+            return;
+        };
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return;
+        }
+        let Some(repo) = self.repos.get(idx) else {
+            return;
+        };
+
         let tx = self.event_handler.git_tx();
         let path = repo.path().to_path_buf();
-        let idx = selected;
-        // Manual update always fetches with fast-forward
-        Self::spawn_manual_update(tx, idx, path);
+        tokio::spawn(async move {
+            let result = tokio::task::spawn_blocking(move || {
+                GitRepo::create_branch(&path, &name)
+                    .map(|_| name)
+                    .map_err(|err| err.to_string())
+            })
+            .await
+            .unwrap_or_else(|err| Err(err.to_string()));
+
+            let _ = tx.send(GitDataUpdate::BranchCreated(idx, result));
+        });
+    }
+
+    /// Handle key press while typing a commit message
+    fn handle_commit_input_key(&mut self, code: KeyCode) {
+        let Some((_, message)) = &mut self.commit_input else {
+            return;
+        };
+        match code {
+            KeyCode::Esc => {
+                self.commit_input = None;
+            }
+            KeyCode::Enter => {
+                self.perform_commit_all();
+            }
+            KeyCode::Backspace => {
+                message.pop();
+            }
+            KeyCode::Char(c) => {
+                message.push(c);
+            }
+            _ => {}
+        }
+        self.needs_redraw = true;
+    }
+
+    /// Prompt for a message to commit all changes of the selected repository.
+    ///
+    /// This is triggered by the 'i' shortcut in normal mode.
+    fn handle_commit_all(&mut self) {
+        let Some(selected) = self.table_state.selected() else {
+            return;
+        };
+        let Some(repo) = self.repos.get(selected) else {
+            return;
+        };
+        if repo.is_missing() || repo.is_clean() {
+            return;
+        }
+        self.commit_input = Some((selected, String::new()));
+        self.needs_redraw = true;
+    }
+
+    /// Commit all changes with the message typed in the prompt, then reload the status
+    fn perform_commit_all(&mut self) {
+        let Some((idx, message)) = self.commit_input.take() else {
+            return;
+        };
+        let message = message.trim().to_string();
+        if message.is_empty() {
+            return;
+        }
+        let Some(repo) = self.repos.get(idx) else {
+            return;
+        };
+
+        let tx = self.event_handler.git_tx();
+        let path = repo.path().to_path_buf();
+        tokio::spawn(async move {
+            let result = tokio::task::spawn_blocking({
+                let path = path.clone();
+                move || GitRepo::commit_all(&path, &message).map_err(|err| err.to_string())
+            })
+            .await
+            .unwrap_or_else(|err| Err(err.to_string()));
+
+            let _ = tx.send(GitDataUpdate::Committed(idx, result));
+            load_status(&tx, idx, path).await;
+        });
+    }
+
+    /// Handle key press while typing the new path of a repository
+    fn handle_move_input_key(&mut self, code: KeyCode) {
+        let Some((_, target)) = &mut self.move_input else {
+            return;
+        };
+        match code {
+            KeyCode::Esc => {
+                self.move_input = None;
+            }
+            KeyCode::Enter => {
+                self.perform_move_repo();
+            }
+            KeyCode::Backspace => {
+                target.pop();
+            }
+            KeyCode::Char(c) => {
+                target.push(c);
+            }
+            _ => {}
+        }
+        self.needs_redraw = true;
+    }
+
+    /// Prompt for the new path of the selected repository, relative to the scanned directory.
+    ///
+    /// This is triggered by the 'v' shortcut in normal mode.
+    fn handle_move_repo(&mut self) {
+        let Some(selected) = self.table_state.selected() else {
+            return;
+        };
+        let Some(repo) = self.repos.get(selected) else {
+            return;
+        };
+        if repo.is_missing() {
+            return;
+        }
+        // Running operations refer to repositories by index, which moving would shuffle
+        if self.is_busy() {
+            self.status_message = Some("Wait for running operations to finish".to_string());
+            self.needs_redraw = true;
+            return;
+        }
+
+        let Some(scan_path) = self.scan_path_of(repo.path()) else {
+            return;
+        };
+        let cleaned_path = strip_unc_pathbuf(repo.path());
+        let Ok(relative_path) = cleaned_path.strip_prefix(scan_path) else {
+            return;
+        };
+        let relative_path = relative_path.to_string_lossy().replace('\\', "/");
+        self.move_input = Some((selected, relative_path));
+        self.needs_redraw = true;
+    }
+
+    /// Get the scanned directory containing a repository
+    fn scan_path_of(&self, repo_path: &Path) -> Option<&Path> {
+        let cleaned_path = strip_unc_pathbuf(repo_path);
+        self.scan_paths
+            .iter()
+            .find(|scan_path| cleaned_path.starts_with(scan_path))
+            .map(PathBuf::as_path)
+    }
+
+    /// Move the repository to the path typed in the prompt
+    ///
+    /// The cache is rebuilt from the repository list on exit, so it picks up the new path.
+    fn perform_move_repo(&mut self) {
+        let Some((idx, target)) = self.move_input.take() else {
+            return;
+        };
+        let target = target.trim();
+        if target.is_empty() {
+            return;
+        }
+        let Some(scan_path) = self
+            .repos
+            .get(idx)
+            .and_then(|repo| self.scan_path_of(repo.path()))
+        else {
+            return;
+        };
+        let target = match move_target(scan_path, target) {
+            Ok(target) => target,
+            Err(err) => {
+                self.status_message = Some(err);
+                return;
+            }
+        };
+        let Some(repo) = self.repos.get_mut(idx) else {
+            return;
+        };
+
+        if strip_unc_pathbuf(repo.path()) == target {
+            return;
+        }
+        if let Err(err) = GitRepo::move_to(repo.path(), &target) {
+            self.status_message = Some(format!("Failed to move repository: {}", err));
+            return;
+        }
+
+        // A pin follows the repository
+        let old_path = strip_unc_pathbuf(repo.path());
+        repo.set_path(target.clone());
+        if self.pinned.remove(&old_path) {
+            self.pinned.insert(target.clone());
+            let saved = Settings::load().and_then(|mut settings| {
+                settings.set_pinned(&old_path, false)?;
+                settings.set_pinned(&target, true)
+            });
+            if let Err(err) = saved {
+                self.status_message = Some(format!("Failed to save pinned repositories: {}", err));
+            }
+        }
+        self.details_requested.clear();
+        if let Some(new_idx) = Self::find_repo_index(&self.repos, &target) {
+            self.table_state.select(Some(new_idx));
+        }
+    }
+
+    /// Handle key press in the upstream branch picker
+    fn handle_upstream_picker_key(&mut self, code: KeyCode) {
+        let Some((_, candidates, list_state)) = &mut self.upstream_picker else {
+            return;
+        };
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.upstream_picker = None;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let next = list_state
+                    .selected()
+                    .map_or(0, |i| (i + 1) % candidates.len());
+                list_state.select(Some(next));
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                let previous = list_state
+                    .selected()
+                    .map_or(0, |i| (i + candidates.len() - 1) % candidates.len());
+                list_state.select(Some(previous));
+            }
+            KeyCode::Enter => {
+                if let Some((idx, candidates, list_state)) = self.upstream_picker.take()
+                    && let Some(upstream) = list_state.selected().and_then(|i| candidates.get(i))
+                {
+                    if upstream == UNSET_UPSTREAM {
+                        self.spawn_unset_upstream(idx);
+                    } else {
+                        self.spawn_set_upstream(idx, Some(upstream.clone()));
+                    }
+                }
+            }
+            _ => {}
+        }
+        self.needs_redraw = true;
+    }
+
+    /// Set the upstream of the selected repository when its branch has none, or fix it when
+    /// it is gone or named after another branch.
+    ///
+    /// This is triggered by the 't' shortcut in normal mode. Without upstream, it is guessed
+    /// as `origin/<branch>`; if that does not exist, a picker of remote branches is shown.
+    /// A stale upstream, typically left by a branch rename, is never changed without asking:
+    /// the picker offers `origin/<branch>` first, and to unset the upstream.
+    fn handle_set_upstream(&mut self) {
+        let Some(selected) = self.table_state.selected() else {
+            return;
+        };
+        let Some(repo) = self.repos.get(selected) else {
+            return;
+        };
+        if repo.is_missing() {
+            return;
+        }
+        if repo.remote_status() == "no-tracking" {
+            self.spawn_set_upstream(selected, None);
+            return;
+        }
+        // The upstream is only read for the selected repository, shown in the detail pane
+        let mismatched = repo.upstream().is_some_and(|upstream| {
+            upstream
+                .split_once('/')
+                .is_some_and(|(_, branch)| branch != repo.branch())
+        });
+        if repo.remote_status() == "upstream-gone" || mismatched {
+            self.spawn_repoint_upstream(selected);
+        }
+    }
+
+    /// Spawn task to list the remote branches a stale upstream can be replaced with
+    fn spawn_repoint_upstream(&self, idx: usize) {
+        let Some(repo) = self.repos.get(idx) else {
+            return;
+        };
+        let tx = self.event_handler.git_tx();
+        let path = repo.path().to_path_buf();
+        let guess = format!("origin/{}", repo.branch());
+
+        tokio::spawn(async move {
+            let mut candidates =
+                tokio::task::spawn_blocking(move || GitRepo::read_remote_branches(&path))
+                    .await
+                    .unwrap_or_default();
+            if let Some(pos) = candidates.iter().position(|name| *name == guess) {
+                let guess = candidates.remove(pos);
+                candidates.insert(0, guess);
+            }
+            candidates.push(UNSET_UPSTREAM.to_string());
+            let _ = tx.send(GitDataUpdate::UpstreamCandidates(idx, candidates));
+        });
+    }
+
+    /// Spawn task to remove the upstream of a repository
+    fn spawn_unset_upstream(&self, idx: usize) {
+        let Some(repo) = self.repos.get(idx) else {
+            return;
+        };
+        let tx = self.event_handler.git_tx();
+        let path = repo.path().to_path_buf();
+
+        tokio::spawn(async move {
+            let result = tokio::task::spawn_blocking({
+                let path = path.clone();
+                move || GitRepo::untrack_upstream(&path).map_err(|err| err.to_string())
+            })
+            .await
+            .unwrap_or_else(|err| Err(err.to_string()));
+
+            let succeeded = result.is_ok();
+            let _ = tx.send(GitDataUpdate::UpstreamUnset(idx, result));
+            if succeeded {
+                load_remote_status(&tx, idx, path).await;
+            }
+        });
+    }
+
+    /// Spawn task to set the upstream of a repository, guessing it if `upstream` is None
+    fn spawn_set_upstream(&self, idx: usize, upstream: Option<String>) {
+        let Some(repo) = self.repos.get(idx) else {
+            return;
+        };
+        let tx = self.event_handler.git_tx();
+        let path = repo.path().to_path_buf();
+        let branch = repo.branch().to_string();
+
+        tokio::spawn(async move {
+            let result = tokio::task::spawn_blocking({
+                let path = path.clone();
+                move || {
+                    let upstream = match upstream {
+                        Some(upstream) => upstream,
+                        None => {
+                            let guess = format!("origin/{}", branch);
+                            if !GitRepo::remote_branch_exists(&path, &guess) {
+                                return Err(GitRepo::read_remote_branches(&path));
+                            }
+                            guess
+                        }
+                    };
+                    Ok(GitRepo::track_upstream(&path, &upstream)
+                        .map(|_| upstream)
+                        .map_err(|err| err.to_string()))
+                }
+            })
+            .await;
+
+            match result {
+                Ok(Ok(result)) => {
+                    let succeeded = result.is_ok();
+                    let _ = tx.send(GitDataUpdate::UpstreamSet(idx, result));
+                    if succeeded {
+                        load_remote_status(&tx, idx, path).await;
+                    }
+                }
+                // The guessed upstream does not exist, let the user pick one
+                Ok(Err(candidates)) => {
+                    let _ = tx.send(GitDataUpdate::UpstreamCandidates(idx, candidates));
+                }
+                Err(err) => {
+                    let _ = tx.send(GitDataUpdate::UpstreamSet(idx, Err(err.to_string())));
+                }
+            }
+        });
+    }
+
+    /// Handle keys while the error log is shown
+    fn handle_error_log_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char('c') | KeyCode::Char('C') => {
+                self.errors.clear();
+                self.error_log_open = false;
+                self.needs_redraw = true;
+            }
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char('E') => {
+                self.error_log_open = false;
+                self.needs_redraw = true;
+            }
+            _ => {}
+        }
+    }
+
+    /// Handle keys while the full status of a repository is shown
+    fn handle_full_status_key(&mut self, code: KeyCode) {
+        let Some((_, lines, scroll)) = self.full_status.as_mut() else {
+            return;
+        };
+        match code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                *scroll = (*scroll + 1).min(lines.len().saturating_sub(1));
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                *scroll = scroll.saturating_sub(1);
+            }
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Tab => {
+                self.full_status = None;
+            }
+            _ => return,
+        }
+        self.needs_redraw = true;
+    }
+
+    /// Keep the failure of a background operation for the error log
+    fn record_error(&mut self, name: &str, message: String) {
+        if self.errors.len() == MAX_ERRORS {
+            self.errors.pop_front();
+        }
+        self.errors.push_back(format!("{}: {}", name, message));
+    }
+
+    /// Show the failures of background operations, or say there is none
+    fn handle_show_errors(&mut self) {
+        if self.errors.is_empty() {
+            self.status_message = Some("No failed operations".to_string());
+        } else {
+            self.error_log_open = true;
+        }
+        self.needs_redraw = true;
+    }
+
+    /// Search the repositories on the remote host of the selected one.
+    ///
+    /// This is triggered by the '@' shortcut in normal mode. The selection stays on the
+    /// same repository, which matches the search.
+    fn handle_filter_by_host(&mut self) {
+        let Some(repo) = self
+            .table_state
+            .selected()
+            .and_then(|idx| self.repos.get(idx))
+        else {
+            return;
+        };
+        let Some(host) = repo.remote_host() else {
+            self.status_message = Some(format!("{} has no remote host", repo.display_name()));
+            self.needs_redraw = true;
+            return;
+        };
+        self.search_query = format!("{}{}", HOST_SEARCH_PREFIX, host);
+        self.regex_search = false;
+        self.update_search_regex();
+        self.needs_redraw = true;
+    }
+
+    /// Handle keys in incoming commits preview mode
+    fn handle_incoming_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                self.perform_incoming_update();
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.incoming_preview = None;
+                self.needs_redraw = true;
+            }
+            _ => {}
+        }
+    }
+
+    /// Handle keys while confirming an update of all displayed repositories
+    fn handle_update_all_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                self.perform_update_all();
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.update_all_confirmation = None;
+                self.needs_redraw = true;
+            }
+            _ => {}
+        }
+    }
+
+    /// Handle key press while confirming that local changes will be discarded
+    ///
+    /// Only an explicit 'y' confirms: the changes cannot be recovered.
+    fn handle_discard_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                self.perform_discard_changes();
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.discard_confirmation = None;
+                self.needs_redraw = true;
+            }
+            _ => {}
+        }
+    }
+
+    /// List the local changes of the selected repository, then ask to discard them.
+    ///
+    /// This is triggered by the 'x' shortcut in normal mode.
+    fn handle_discard_changes(&mut self) {
+        let Some(selected) = self.table_state.selected() else {
+            return;
+        };
+        let Some(repo) = self.repos.get(selected) else {
+            return;
+        };
+        if repo.is_missing() {
+            return;
+        }
+
+        let tx = self.event_handler.git_tx();
+        let path = repo.path().to_path_buf();
+        tokio::spawn(async move {
+            let files = tokio::task::spawn_blocking({
+                let path = path.clone();
+                move || GitRepo::read_changed_files(&path)
+            })
+            .await
+            .unwrap_or_default();
+            let _ = tx.send(GitDataUpdate::ChangedFiles(path, files));
+        });
+    }
+
+    /// Read the full `git status` of the selected repository, shown once loaded
+    ///
+    /// This is triggered by the Tab shortcut in normal mode.
+    fn handle_show_full_status(&mut self) {
+        let Some(selected) = self.table_state.selected() else {
+            return;
+        };
+        let Some(repo) = self.repos.get(selected) else {
+            return;
+        };
+        if repo.is_missing() {
+            return;
+        }
+
+        let tx = self.event_handler.git_tx();
+        let path = repo.path().to_path_buf();
+        tokio::spawn(async move {
+            let result = tokio::task::spawn_blocking(move || {
+                GitRepo::read_full_status(&path).map_err(|err| err.to_string())
+            })
+            .await
+            .unwrap_or_else(|err| Err(err.to_string()));
+            let _ = tx.send(GitDataUpdate::FullStatus(selected, result));
+        });
+    }
+
+    /// Reset the repository waiting for confirmation and remove its untracked files
+    fn perform_discard_changes(&mut self) {
+        let Some((path, _)) = self.discard_confirmation.take() else {
+            return;
+        };
+        let Some(idx) = Self::find_repo_index(&self.repos, &path) else {
+            return;
+        };
+        let repo = &mut self.repos[idx];
+        // Show the loading state until the new status arrives
+        repo.reset_status();
+        self.needs_redraw = true;
+
+        let tx = self.event_handler.git_tx();
+        tokio::spawn(async move {
+            let result = tokio::task::spawn_blocking({
+                let path = path.clone();
+                move || GitRepo::discard_changes(&path).map_err(|err| err.to_string())
+            })
+            .await
+            .unwrap_or_else(|err| Err(err.to_string()));
+
+            load_status(&tx, idx, path.clone()).await;
+            let _ = tx.send(GitDataUpdate::ChangesDiscarded(path, result));
+        });
+    }
+
+    /// Fetch the selected repository and preview its incoming commits.
+    ///
+    /// This is triggered by the 'p' shortcut in normal mode.
+    fn handle_preview_incoming(&mut self) {
+        let Some(selected) = self.table_state.selected() else {
+            return;
+        };
+        let Some(repo) = self.repos.get(selected) else {
+            return;
+        };
+        if repo.is_missing() {
+            return;
+        }
+        // Mark as fetching for animation
+        if !self.fetching_repos.contains(&selected) {
+            self.fetching_repos.push(selected);
+        }
+        self.needs_redraw = true;
+        let tx = self.event_handler.git_tx();
+        let path = repo.path().to_path_buf();
+        Self::spawn_incoming_preview(tx, selected, path);
+    }
+
+    /// Fast-forward the previewed repository after confirmation
+    fn perform_incoming_update(&mut self) {
+        let Some((idx, _)) = self.incoming_preview.take() else {
+            return;
+        };
+        self.needs_redraw = true;
+
+        let Some(repo) = self.repos.get(idx) else {
+            return;
+        };
+        if !self.fetching_repos.contains(&idx) {
+            self.fetching_repos.push(idx);
+        }
+        let tx = self.event_handler.git_tx();
+        let path = repo.path().to_path_buf();
+        Self::spawn_fast_forward(tx, idx, path);
+    }
+
+    /// Re-read the remote and working tree status of the selected repository.
+    ///
+    /// This is triggered by the 'r' shortcut in normal mode. Unlike 'u', it does not fetch.
+    fn handle_refresh_repo(&mut self) {
+        let Some(selected) = self.table_state.selected() else {
+            return;
+        };
+        let Some(repo) = self.repos.get_mut(selected) else {
+            return;
+        };
+        if repo.is_missing() {
+            return;
+        }
+        // Show the loading state until the new status arrives
+        repo.reset_status();
+        self.needs_redraw = true;
+
+        let tx = self.event_handler.git_tx();
+        let path = repo.path().to_path_buf();
+        tokio::spawn(async move {
+            load_status(&tx, selected, path).await;
+        });
+    }
+
+    /// Save the selected repository to a bundle in the archive directory.
+    ///
+    /// This is triggered by the 'a' shortcut in normal mode. The bundle keeps the whole
+    /// history, so the repository can be dropped afterwards even without a remote.
+    fn handle_archive_repo(&mut self) {
+        let Some(selected) = self.table_state.selected() else {
+            return;
+        };
+        let Some(repo) = self.repos.get(selected) else {
+            return;
+        };
+        if repo.is_missing() || self.archiving_repos.contains(&selected) {
+            return;
+        }
+        let archive_dir = match self.settings.archive_dir() {
+            Ok(archive_dir) => archive_dir,
+            Err(err) => {
+                self.status_message = Some(format!("Failed to archive repository: {}", err));
+                self.needs_redraw = true;
+                return;
+            }
+        };
+
+        // Keep earlier archives of the same repository
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let bundle_name = format!(
+            "{}-{}.bundle",
+            repo.display_short().replace('/', "-"),
+            timestamp
+        );
+        let bundle_path = archive_dir.join(bundle_name);
+
+        let tx = self.event_handler.git_tx();
+        let path = repo.path().to_path_buf();
+        tokio::spawn(async move {
+            let _ = tx.send(GitDataUpdate::ArchiveProgress(selected));
+
+            let result = tokio::task::spawn_blocking(move || {
+                GitRepo::create_bundle(&path, &bundle_path)
+                    .map(|_| bundle_path)
+                    .map_err(|err| err.to_string())
+            })
+            .await
+            .unwrap_or_else(|err| Err(err.to_string()));
+
+            let _ = tx.send(GitDataUpdate::ArchiveComplete(selected, result));
+        });
+    }
+
+    /// Check out the recorded submodule commits in the selected repository
+    fn handle_update_submodules(&mut self) {
+        let Some(selected) = self.table_state.selected() else {
+            return;
+        };
+        let Some(repo) = self.repos.get(selected) else {
+            return;
+        };
+        if repo.is_missing() || self.updating_submodules.contains(&selected) {
+            return;
+        }
+
+        let tx = self.event_handler.git_tx();
+        let path = repo.path().to_path_buf();
+        tokio::spawn(async move {
+            let _ = tx.send(GitDataUpdate::SubmoduleProgress(selected));
+
+            let result = tokio::task::spawn_blocking({
+                let path = path.clone();
+                move || GitRepo::update_submodules(&path).map_err(|err| err.to_string())
+            })
+            .await
+            .unwrap_or_else(|err| Err(err.to_string()));
+
+            load_status(&tx, selected, path).await;
+            let _ = tx.send(GitDataUpdate::SubmoduleComplete(selected, result));
+        });
+    }
+
+    /// Set `origin/HEAD` of the selected repository from the default branch of the remote
+    fn handle_detect_default_branch(&mut self) {
+        let Some(selected) = self.table_state.selected() else {
+            return;
+        };
+        let Some(repo) = self.repos.get(selected) else {
+            return;
+        };
+        if repo.is_missing() || repo.get_remote_url().is_none() {
+            return;
+        }
+
+        self.status_message = Some(format!(
+            "Detecting default branch of {}...",
+            repo.display_short()
+        ));
+        self.needs_redraw = true;
+        let tx = self.event_handler.git_tx();
+        let path = repo.path().to_path_buf();
+        tokio::spawn(async move {
+            let result = tokio::task::spawn_blocking(move || {
+                GitRepo::detect_default_branch(&path).map_err(|err| err.to_string())
+            })
+            .await
+            .unwrap_or_else(|err| Err(err.to_string()));
+            let _ = tx.send(GitDataUpdate::DefaultBranch(selected, result));
+        });
+    }
+
+    /// List the local branches of the selected repository merged into its default branch,
+    /// to pick the ones to delete.
+    ///
+    /// This is triggered by the 'l' shortcut in normal mode.
+    fn handle_prune_branches(&mut self) {
+        let Some(selected) = self.table_state.selected() else {
+            return;
+        };
+        let Some(repo) = self.repos.get(selected) else {
+            return;
+        };
+        if repo.is_missing() {
+            return;
+        }
+        let Some(default_branch) = repo.default_branch().map(str::to_string) else {
+            self.status_message = Some(format!(
+                "Default branch of {} is unknown, press h to detect it",
+                repo.display_short()
+            ));
+            self.needs_redraw = true;
+            return;
+        };
+
+        let tx = self.event_handler.git_tx();
+        let path = repo.path().to_path_buf();
+        let branch = repo.branch().to_string();
+        tokio::spawn(async move {
+            let branches = tokio::task::spawn_blocking(move || {
+                GitRepo::read_merged_branches(&path, &default_branch, &branch)
+            })
+            .await
+            .unwrap_or_default();
+            let _ = tx.send(GitDataUpdate::MergedBranches(selected, branches));
+        });
+    }
+
+    /// Handle key press in the merged branches picker
+    fn handle_prune_picker_key(&mut self, code: KeyCode) {
+        let Some((_, branches, list_state)) = &mut self.prune_picker else {
+            return;
+        };
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.prune_picker = None;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let next = list_state
+                    .selected()
+                    .map_or(0, |i| (i + 1) % branches.len());
+                list_state.select(Some(next));
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                let previous = list_state
+                    .selected()
+                    .map_or(0, |i| (i + branches.len() - 1) % branches.len());
+                list_state.select(Some(previous));
+            }
+            KeyCode::Char(' ') => {
+                if let Some((_, checked)) = list_state.selected().and_then(|i| branches.get_mut(i))
+                {
+                    *checked = !*checked;
+                }
+            }
+            KeyCode::Char('a') => {
+                let check = !branches.iter().all(|(_, checked)| *checked);
+                for (_, checked) in branches.iter_mut() {
+                    *checked = check;
+                }
+            }
+            KeyCode::Enter => {
+                if let Some((idx, branches, _)) = self.prune_picker.take() {
+                    let names: Vec<String> = branches
+                        .into_iter()
+                        .filter_map(|(name, checked)| checked.then_some(name))
+                        .collect();
+                    if !names.is_empty() {
+                        self.spawn_delete_branches(idx, names);
+                    }
+                }
+            }
+            _ => {}
+        }
+        self.needs_redraw = true;
+    }
+
+    /// Spawn task to delete local branches of a repository, then reload its status
+    fn spawn_delete_branches(&self, idx: usize, names: Vec<String>) {
+        let Some(repo) = self.repos.get(idx) else {
+            return;
+        };
+        let tx = self.event_handler.git_tx();
+        let path = repo.path().to_path_buf();
+
+        tokio::spawn(async move {
+            let result = tokio::task::spawn_blocking({
+                let path = path.clone();
+                move || {
+                    let mut deleted = Vec::new();
+                    let mut errors = Vec::new();
+                    for name in names {
+                        match GitRepo::delete_branch(&path, &name) {
+                            Ok(()) => deleted.push(name),
+                            Err(err) => errors.push(err.to_string()),
+                        }
+                    }
+                    (deleted, errors)
+                }
+            })
+            .await;
+
+            let (deleted, errors) =
+                result.unwrap_or_else(|err| (Vec::new(), vec![err.to_string()]));
+            let _ = tx.send(GitDataUpdate::BranchesPruned(idx, deleted, errors));
+            load_status(&tx, idx, path).await;
+        });
+    }
+
+    /// List the worktrees of the selected repository, to pick the one to change directory to.
+    ///
+    /// This is triggered by the 'w' shortcut in normal mode.
+    fn handle_pick_worktree(&mut self) {
+        let Some(selected) = self.table_state.selected() else {
+            return;
+        };
+        let Some(repo) = self.repos.get(selected) else {
+            return;
+        };
+        if repo.is_missing() {
+            return;
+        }
+        if !self.cwd_file_enabled {
+            self.status_message =
+                Some("Changing directory needs the shell integration (--cwd-file)".to_string());
+            self.needs_redraw = true;
+            return;
+        }
+
+        let tx = self.event_handler.git_tx();
+        let path = repo.path().to_path_buf();
+        tokio::spawn(async move {
+            let worktrees = tokio::task::spawn_blocking(move || GitRepo::read_worktrees(&path))
+                .await
+                .unwrap_or_default();
+            let _ = tx.send(GitDataUpdate::Worktrees(selected, worktrees));
+        });
+    }
+
+    /// Handle key press in the worktree picker
+    fn handle_worktree_picker_key(&mut self, code: KeyCode) {
+        let Some((_, worktrees, list_state)) = &mut self.worktree_picker else {
+            return;
+        };
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.worktree_picker = None;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let next = list_state
+                    .selected()
+                    .map_or(0, |i| (i + 1) % worktrees.len());
+                list_state.select(Some(next));
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                let previous = list_state
+                    .selected()
+                    .map_or(0, |i| (i + worktrees.len() - 1) % worktrees.len());
+                list_state.select(Some(previous));
+            }
+            KeyCode::Enter => {
+                if let Some((_, worktrees, list_state)) = self.worktree_picker.take()
+                    && let Some((path, _)) = list_state.selected().and_then(|i| worktrees.get(i))
+                {
+                    self.selected_repo = Some(path.display().to_string());
+                    self.should_quit = true;
+                }
+            }
+            _ => {}
+        }
+        self.needs_redraw = true;
+    }
+
+    /// Ask to update all displayed repositories with a remote, summarizing what will happen.
+    ///
+    /// This is triggered by the 'U' shortcut in normal mode.
+    fn handle_update_all(&mut self) {
+        let targets: Vec<usize> = self
+            .filtered_repos()
+            .into_iter()
+            .filter(|&idx| {
+                let repo = &self.repos[idx];
+                !repo.is_missing()
+                    && !matches!(repo.remote_status(), "local-only" | "up-to-date")
+                    && !self.fetching_repos.contains(&idx)
+            })
+            .collect();
+        if targets.is_empty() {
+            self.status_message = Some("No repository to update".to_string());
+        } else {
+            self.update_all_confirmation = Some(targets);
+        }
+        self.needs_redraw = true;
+    }
+
+    /// Update the repositories confirmed after pressing 'U'
+    fn perform_update_all(&mut self) {
+        let Some(targets) = self.update_all_confirmation.take() else {
+            return;
+        };
+
+        let tx = self.event_handler.git_tx();
+        for idx in targets {
+            let Some(repo) = self.repos.get(idx) else {
+                continue;
+            };
+            if !self.fetching_repos.contains(&idx) {
+                self.fetching_repos.push(idx);
+            }
+            Self::spawn_manual_update(tx.clone(), idx, repo.path().to_path_buf());
+        }
+        self.needs_redraw = true;
+    }
+
+    /// Update the selected repository (fetch + status), with animation.
+    ///
+    /// This is triggered by the 'u' shortcut in normal mode.
+    fn handle_update_repo(&mut self) {
+        let Some(selected) = self.table_state.selected() else {
+            return;
+        };
+        let Some(repo) = self.repos.get(selected) else {
+            return;
+        };
+        if repo.is_missing() {
+            return;
+        }
+        // Mark as fetching for animation
+        if !self.fetching_repos.contains(&selected) {
+            self.fetching_repos.push(selected);
+        }
+        self.needs_redraw = true;
+        let tx = self.event_handler.git_tx();
+        let path = repo.path().to_path_buf();
+        let idx = selected;
+        // Manual update always fetches with fast-forward
+        Self::spawn_manual_update(tx, idx, path);
+    }
+
+    /// Handle git data updates
+    fn handle_git_update(&mut self, update: GitDataUpdate) {
+        match update {
+            GitDataUpdate::RemoteStatus(idx, status) => {
+                if let Some(repo) = self.repos.get_mut(idx) {
+                    repo.set_remote_status(status);
+                    self.needs_redraw = true;
+                }
+                self.finish_fetches_if_done();
+            }
+            GitDataUpdate::Status(idx, status) => {
+                if let Some(repo) = self.repos.get_mut(idx) {
+                    repo.set_status(status);
+                    self.needs_redraw = true;
+                }
+            }
+            GitDataUpdate::FetchProgress(idx) => {
+                if !self.fetching_repos.contains(&idx) {
+                    self.fetching_repos.push(idx);
+                    self.needs_redraw = true;
+                }
+            }
+            GitDataUpdate::FetchComplete(idx) => {
+                self.fetching_repos.retain(|&i| i != idx);
+                if let Some(pos) = self.idle_fetches.iter().position(|&i| i == idx) {
+                    self.idle_fetches.swap_remove(pos);
+                } else {
+                    self.fetched_count += 1;
+                }
+                self.fetch_animation_frame = self.fetch_animation_frame.wrapping_add(1);
+                self.needs_redraw = true;
+                self.finish_fetches_if_done();
+            }
+            GitDataUpdate::CloneProgress(path) => {
+                if !self.cloning_repos.contains(&path) {
+                    self.cloning_repos.push(path);
+                    self.needs_redraw = true;
+                }
+            }
+            GitDataUpdate::PostCloneHookFailed(path, err) => {
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| path.display().to_string());
+                self.status_message = Some(format!("Post-clone hook failed in {}: {}", name, err));
+                self.record_error(&name, format!("post-clone hook failed: {}", err));
+                self.needs_redraw = true;
+            }
+            GitDataUpdate::CloneCancelled(path) => {
+                self.cloning_repos.retain(|p| p != &path);
+                if let Some(batch) = &mut self.clone_batch
+                    && batch.pending.remove(&path)
+                {
+                    batch.cancelled += 1;
+                }
+                self.finish_clone_batch_if_done();
+                self.needs_redraw = true;
+            }
+            GitDataUpdate::CloneComplete(path, cloned_path) => {
+                self.cloning_repos.retain(|p| p != &path);
+
+                let name = Self::find_repo_index(&self.repos, &path)
+                    .map(|idx| self.repos[idx].display_short())
+                    .unwrap_or_else(|| path.display().to_string());
+                let in_batch = self
+                    .clone_batch
+                    .as_mut()
+                    .is_some_and(|batch| batch.pending.remove(&path));
+                match &cloned_path {
+                    Ok(_) => {
+                        if let Some(batch) = self.clone_batch.as_mut().filter(|_| in_batch) {
+                            batch.succeeded += 1;
+                        }
+                    }
+                    Err(err) => {
+                        self.record_error(&name, format!("clone failed: {}", err));
+                        match self.clone_batch.as_mut().filter(|_| in_batch) {
+                            Some(batch) => batch.failed.push(name),
+                            None => {
+                                self.status_message =
+                                    Some(format!("Failed to clone {}: {}", name, err));
+                            }
+                        }
+                    }
+                }
+
+                // Refresh the repository by recreating it as a normal repo, where it landed
+                if let Some(idx) = Self::find_repo_index(&self.repos, &path) {
+                    // Only refresh if the clone was successful
+                    if let Ok(cloned_path) = cloned_path {
+                        // Keep the selection on the same repository, following it to where it landed
+                        let selected_path = self
+                            .table_state
+                            .selected()
+                            .and_then(|i| self.repos.get(i))
+                            .map(|repo| repo.path().to_path_buf())
+                            .map(|selected| {
+                                if selected == path {
+                                    cloned_path.clone()
+                                } else {
+                                    selected
+                                }
+                            });
+
+                        self.repos[idx] = GitRepo::new(cloned_path.clone());
+
+                        if let Some(selected_path) = selected_path
+                            && let Some(selected_idx) =
+                                Self::find_repo_index(&self.repos, &selected_path)
+                        {
+                            self.table_state.select(Some(selected_idx));
+                        }
+
+                        if let Some(new_idx) = Self::find_repo_index(&self.repos, &cloned_path) {
+                            if self.settings.show_disk_usage {
+                                Self::spawn_disk_usage_load(
+                                    self.event_handler.git_tx(),
+                                    self.size_limiter.clone(),
+                                    new_idx,
+                                    cloned_path.clone(),
+                                    self.settings.disk_usage_exclude_objects,
+                                );
+                            }
+                            Self::spawn_git_data_load(
+                                self.event_handler.git_tx(),
+                                new_idx,
+                                cloned_path,
+                            );
+                        }
+                    }
+                }
+
+                self.finish_clone_batch_if_done();
+                self.needs_redraw = true;
+            }
+            GitDataUpdate::SubmoduleProgress(idx) => {
+                if !self.updating_submodules.contains(&idx) {
+                    self.updating_submodules.push(idx);
+                    self.needs_redraw = true;
+                }
+            }
+            GitDataUpdate::SubmoduleComplete(idx, result) => {
+                self.updating_submodules.retain(|&i| i != idx);
+                if let Err(err) = result {
+                    self.status_message = Some(format!("Failed to update submodules: {}", err));
+                    if let Some(name) = self.repos.get(idx).map(|repo| repo.display_short()) {
+                        self.record_error(&name, format!("submodule update failed: {}", err));
+                    }
+                }
+                self.needs_redraw = true;
+            }
+            GitDataUpdate::ArchiveProgress(idx) => {
+                if !self.archiving_repos.contains(&idx) {
+                    self.archiving_repos.push(idx);
+                    self.needs_redraw = true;
+                }
+            }
+            GitDataUpdate::ArchiveComplete(idx, result) => {
+                self.archiving_repos.retain(|&i| i != idx);
+                if let Err(err) = &result
+                    && let Some(name) = self.repos.get(idx).map(|repo| repo.display_short())
+                {
+                    self.record_error(&name, format!("archive failed: {}", err));
+                }
+                self.status_message = Some(match result {
+                    Ok(bundle_path) => format!(
+                        "Archived to {} (press d to drop the repository)",
+                        strip_unc_prefix(&bundle_path.display().to_string())
+                    ),
+                    Err(err) => format!("Failed to archive repository: {}", err),
+                });
+                self.needs_redraw = true;
+            }
+            GitDataUpdate::DeleteProgress(idx) => {
+                if !self.deleting_repos.contains(&idx) {
+                    self.deleting_repos.push(idx);
+                    self.needs_redraw = true;
+                }
+            }
+            GitDataUpdate::DeleteComplete(idx, result) => {
+                self.deleting_repos.retain(|&i| i != idx);
+
+                // A failed deletion may have removed part of the files, keep the repository
+                if let Err(err) = result {
+                    if let Some(name) = self.repos.get(idx).map(|repo| repo.display_short()) {
+                        self.status_message = Some(format!("Failed to delete {}: {}", name, err));
+                        self.record_error(&name, format!("delete failed: {}", err));
+                    }
+                } else if let Some(repo) = self.repos.get_mut(idx) {
+                    let repo_path = repo.path().to_path_buf();
+                    repo.set_missing();
+
+                    if let Some(new_idx) = Self::find_repo_index(&self.repos, &repo_path) {
+                        self.table_state.select(Some(new_idx));
+                    }
+                }
+
+                self.needs_redraw = true;
+            }
+            GitDataUpdate::Diverged(idx) => {
+                if let Some(repo) = self.repos.get_mut(idx) {
+                    repo.set_diverged();
+                    self.needs_redraw = true;
+                }
+            }
+            GitDataUpdate::UpdateFailed(idx, err) => {
+                if let Some(name) = self.repos.get(idx).map(|repo| repo.display_short()) {
+                    self.status_message = Some(format!("Failed to update {}: {}", name, err));
+                    self.record_error(&name, format!("update failed: {}", err));
+                    self.needs_redraw = true;
+                }
+            }
+            GitDataUpdate::FetchFailed(idx, err) => {
+                if let Some(name) = self.repos.get(idx).map(|repo| repo.display_short()) {
+                    self.record_error(&name, err);
+                }
+            }
+            GitDataUpdate::PreviewFailed(idx, err) => {
+                if let Some(name) = self.repos.get(idx).map(|repo| repo.display_short()) {
+                    self.status_message = Some(format!("Failed to fetch {}: {}", name, err));
+                    self.record_error(&name, format!("fetch failed: {}", err));
+                    self.needs_redraw = true;
+                }
+            }
+            GitDataUpdate::Size(idx, size) => {
+                if let Some(repo) = self.repos.get_mut(idx) {
+                    repo.set_size(size);
+                    self.needs_redraw = true;
+                }
+            }
+            GitDataUpdate::Upstream(idx, upstream) => {
+                self.details_requested.remove(&idx);
+                if let Some(repo) = self.repos.get_mut(idx) {
+                    repo.set_upstream(upstream);
+                    self.needs_redraw = true;
+                }
+            }
+            GitDataUpdate::RepoFound(repo) => {
+                // The selection follows the first repository when drawing
+                self.add_repo(*repo);
+                self.needs_redraw = true;
+            }
+            GitDataUpdate::ScanComplete(skipped) => {
+                self.scanning = false;
+                if self.follow_first_repo {
+                    self.follow_first_repo = false;
+                    self.select_first();
+                }
+                self.report_skipped_dirs(&skipped);
+                self.finish_fetches_if_done();
+                self.needs_redraw = true;
+            }
+            GitDataUpdate::Corruption(idx, corruption) => {
+                self.details_requested.remove(&idx);
+                if let Some(repo) = self.repos.get_mut(idx) {
+                    repo.set_corruption(corruption);
+                    self.needs_redraw = true;
+                }
+            }
+            GitDataUpdate::LatestTag(idx, latest_tag) => {
+                if let Some(repo) = self.repos.get_mut(idx) {
+                    repo.set_latest_tag(latest_tag);
+                    self.needs_redraw = true;
+                }
+            }
+            GitDataUpdate::Identity(idx, identity) => {
+                if let Some(repo) = self.repos.get_mut(idx) {
+                    repo.set_identity(identity);
+                    self.needs_redraw = true;
+                }
+            }
+            GitDataUpdate::TagsFetched(idx, result) => {
+                match result {
+                    Ok(latest_tag) => {
+                        self.status_message = Some(format!("Latest tag is {}", latest_tag));
+                        if let Some(repo) = self.repos.get_mut(idx) {
+                            repo.set_latest_tag(latest_tag);
+                        }
+                    }
+                    Err(err) => {
+                        self.status_message = Some(format!("Failed to fetch tags: {}", err));
+                    }
+                }
+                self.needs_redraw = true;
+            }
+            GitDataUpdate::UpstreamCandidates(idx, candidates) => {
+                if candidates.is_empty() {
+                    self.status_message = Some("No remote branch to track".to_string());
+                } else {
+                    self.upstream_picker =
+                        Some((idx, candidates, ListState::default().with_selected(Some(0))));
+                }
+                self.needs_redraw = true;
+            }
+            GitDataUpdate::MergedBranches(idx, branches) => {
+                if branches.is_empty() {
+                    self.status_message = Some("No merged branch to delete".to_string());
+                } else {
+                    let branches = branches.into_iter().map(|name| (name, true)).collect();
+                    self.prune_picker =
+                        Some((idx, branches, ListState::default().with_selected(Some(0))));
+                }
+                self.needs_redraw = true;
+            }
+            GitDataUpdate::Worktrees(idx, worktrees) => {
+                // The main worktree is always listed
+                if worktrees.len() < 2 {
+                    let name = self
+                        .repos
+                        .get(idx)
+                        .map(|repo| repo.display_short())
+                        .unwrap_or_default();
+                    self.status_message = Some(format!("No linked worktree in {}", name));
+                } else {
+                    self.worktree_picker =
+                        Some((idx, worktrees, ListState::default().with_selected(Some(0))));
+                }
+                self.needs_redraw = true;
+            }
+            GitDataUpdate::BranchesPruned(idx, deleted, errors) => {
+                let name = self
+                    .repos
+                    .get(idx)
+                    .map(|repo| repo.display_short())
+                    .unwrap_or_default();
+                let deleted = match deleted.len() {
+                    1 => "1 branch".to_string(),
+                    count => format!("{} branches", count),
+                };
+                self.status_message = Some(match errors.first() {
+                    Some(err) => format!("Deleted {} in {}, failed: {}", deleted, name, err),
+                    None => format!("Deleted {} in {}", deleted, name),
+                });
+                for err in errors {
+                    self.record_error(&name, format!("branch deletion failed: {}", err));
+                }
+                self.needs_redraw = true;
+            }
+            GitDataUpdate::UpstreamSet(idx, result) => {
+                match result {
+                    Ok(upstream) => {
+                        if let Some(repo) = self.repos.get_mut(idx) {
+                            repo.set_upstream(upstream);
+                        }
+                    }
+                    Err(err) => {
+                        self.status_message = Some(format!("Failed to set upstream: {}", err));
+                    }
+                }
+                self.needs_redraw = true;
+            }
+            GitDataUpdate::UpstreamUnset(idx, result) => {
+                let Some(repo) = self.repos.get_mut(idx) else {
+                    return;
+                };
+                match result {
+                    Ok(()) => {
+                        repo.set_upstream("none".to_string());
+                        self.status_message =
+                            Some(format!("Unset the upstream of {}", repo.display_short()));
+                    }
+                    Err(err) => {
+                        self.status_message = Some(format!("Failed to unset upstream: {}", err));
+                    }
+                }
+                self.needs_redraw = true;
+            }
+            GitDataUpdate::DefaultBranch(idx, result) => {
+                match result {
+                    Ok(default_branch) => {
+                        self.status_message = Some(format!("Default branch is {}", default_branch));
+                        if let Some(repo) = self.repos.get_mut(idx) {
+                            repo.set_default_branch(default_branch);
+                        }
+                    }
+                    Err(err) => {
+                        self.status_message =
+                            Some(format!("Failed to detect default branch: {}", err));
+                    }
+                }
+                self.needs_redraw = true;
+            }
+            GitDataUpdate::HeadCommit(idx, commit) => {
+                let name = self
+                    .repos
+                    .get(idx)
+                    .map(|repo| repo.display_short())
+                    .unwrap_or_default();
+                self.status_message = Some(match commit {
+                    None => format!("No commits in {}", name),
+                    Some(commit) => {
+                        let short = commit.chars().take(7).collect::<String>();
+                        if self.copy_to_clipboard(commit.clone()) {
+                            format!("Copied {} ({}) to the clipboard", short, name)
+                        } else {
+                            format!("No clipboard, HEAD of {} is {}", name, commit)
+                        }
+                    }
+                });
+                self.needs_redraw = true;
+            }
+            GitDataUpdate::Committed(idx, result) => {
+                let name = self
+                    .repos
+                    .get(idx)
+                    .map(|repo| repo.display_short())
+                    .unwrap_or_default();
+                self.status_message = Some(match result {
+                    Ok(()) => format!("Committed all changes in {}", name),
+                    Err(err) => format!("Failed to commit in {}: {}", name, err),
+                });
+                self.needs_redraw = true;
+            }
+            GitDataUpdate::BranchCreated(idx, result) => {
+                match result {
+                    Ok(branch) => {
+                        if let Some(repo) = self.repos.get_mut(idx) {
+                            repo.set_branch(branch);
+                            repo.reset_status();
+
+                            // The new branch has no upstream yet
+                            let tx = self.event_handler.git_tx();
+                            let path = repo.path().to_path_buf();
+                            tokio::spawn(async move {
+                                load_status(&tx, idx, path).await;
+                            });
+                        }
+                    }
+                    Err(err) => {
+                        self.status_message = Some(format!("Failed to create branch: {}", err));
+                    }
+                }
+                self.needs_redraw = true;
+            }
+            GitDataUpdate::ChangedFiles(path, files) => {
+                if files.is_empty() {
+                    self.status_message = Some("No local changes to discard".to_string());
+                } else if Self::find_repo_index(&self.repos, &path).is_some() {
+                    self.discard_confirmation = Some((path, files));
+                }
+                self.needs_redraw = true;
+            }
+            GitDataUpdate::ChangesDiscarded(path, result) => {
+                if let Err(err) = result
+                    && let Some(name) = Self::find_repo_index(&self.repos, &path)
+                        .map(|idx| self.repos[idx].display_short())
+                {
+                    self.status_message =
+                        Some(format!("Failed to discard changes in {}: {}", name, err));
+                    self.record_error(&name, format!("discard failed: {}", err));
+                    self.needs_redraw = true;
+                }
+            }
+            GitDataUpdate::FullStatus(idx, result) => match result {
+                Ok(lines) => {
+                    self.full_status = Some((idx, lines, 0));
+                    self.needs_redraw = true;
+                }
+                Err(err) => {
+                    if let Some(repo) = self.repos.get(idx) {
+                        self.status_message = Some(format!(
+                            "Failed to read the status of {}: {}",
+                            repo.display_short(),
+                            err
+                        ));
+                        self.needs_redraw = true;
+                    }
+                }
+            },
+            GitDataUpdate::IncomingCommits(idx, commits) => {
+                // Nothing to preview when the repository is already up-to-date
+                if !commits.is_empty() {
+                    self.incoming_preview = Some((idx, commits));
+                    self.needs_redraw = true;
+                }
+            }
+        }
+    }
+
+    /// Notify once all fetches are done
+    ///
+    /// Statuses are read by a bounded pool of workers, so the fetches of the first
+    /// repositories can finish before the last ones are even started: wait until every
+    /// remote status is known.
+    fn finish_fetches_if_done(&mut self) {
+        if self.fetched_count == 0 || self.scanning || !self.fetching_repos.is_empty() {
+            return;
+        }
+        if self
+            .repos
+            .iter()
+            .any(|repo| !repo.is_missing() && repo.remote_status() == "loading...")
+        {
+            return;
+        }
+
+        if self.settings.notify_on_fetch {
+            self.notify_fetch_complete();
+        }
+        self.fetched_count = 0;
+    }
+
+    /// Sum the commits ahead and behind upstream over all repositories
+    ///
+    /// Returns the totals and the number of repositories that are not in sync.
+    pub fn sync_totals(&self) -> (u32, u32, usize) {
+        self.repos
+            .iter()
+            .filter(|repo| !repo.is_missing())
+            .map(|repo| {
+                (
+                    repo.ahead_count().unwrap_or(0),
+                    repo.behind_count().unwrap_or(0),
+                )
+            })
+            .filter(|&(ahead, behind)| ahead > 0 || behind > 0)
+            .fold(
+                (0, 0, 0),
+                |(total_ahead, total_behind, count), (ahead, behind)| {
+                    (total_ahead + ahead, total_behind + behind, count + 1)
+                },
+            )
+    }
+
+    /// Show a desktop notification summarizing the fetches that just finished
+    fn notify_fetch_complete(&self) {
+        let behind = self.repos().filter(|repo| repo.is_behind()).count();
+        let fetched = if self.fetched_count == 1 {
+            "1 repo fetched".to_string()
+        } else {
+            format!("{} repos fetched", self.fetched_count)
+        };
+        let body = format!("{}, {} behind", fetched, behind);
+
+        // Showing a notification may block on the notification daemon
+        tokio::task::spawn_blocking(move || {
+            let _ = notify_rust::Notification::new()
+                .summary("git-repos")
+                .body(&body)
+                .show();
+        });
+    }
+
+    /// Get filtered list of repository indices based on current filter mode,
+    /// in display order
+    pub fn filtered_repos(&self) -> Vec<usize> {
+        let mut keyed: Vec<(SortKey, usize)> = self
+            .repos
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !self.dropped.contains(idx))
+            .filter(|(_, repo)| !(self.hide_missing && repo.is_missing()))
+            .filter(|(_, repo)| self.matches_search(repo) && self.matches_filter(repo))
+            .map(|(idx, repo)| (self.sort_key(repo), idx))
+            .collect();
+        keyed.sort_by(|(a, _), (b, _)| self.compare_repos(a, b));
+        keyed.into_iter().map(|(_, idx)| idx).collect()
+    }
+
+    /// Get the filtered repositories that can be selected, leaving out collapsed groups
+    fn visible_repos(&self) -> Vec<usize> {
+        self.filtered_repos()
+            .into_iter()
+            .filter(|&idx| !self.is_collapsed(&self.repos[idx]))
+            .collect()
+    }
+
+    /// Get the rows of the table: the filtered repositories, under a header per remote
+    /// host when grouping
+    pub fn table_rows(&self) -> Vec<TableRow> {
+        let filtered = self.filtered_repos();
+        if !self.group_by_host {
+            return filtered.into_iter().map(TableRow::Repo).collect();
+        }
+
+        let mut rows = Vec::new();
+        let mut position = 0;
+        while position < filtered.len() {
+            let host = Self::group_of(&self.repos[filtered[position]]);
+            let count = filtered[position..]
+                .iter()
+                .take_while(|&&idx| Self::group_of(&self.repos[idx]) == host)
+                .count();
+            let collapsed = self.collapsed_groups.contains(&host);
+            rows.push(TableRow::Group {
+                host,
+                count,
+                collapsed,
+            });
+            if !collapsed {
+                rows.extend(
+                    filtered[position..position + count]
+                        .iter()
+                        .map(|&idx| TableRow::Repo(idx)),
+                );
+            }
+            position += count;
+        }
+        rows
+    }
+
+    /// Get the group of a repository: the host of its remote, or "local"
+    fn group_of(repo: &GitRepo) -> String {
+        repo.remote_host()
+            .unwrap_or_else(|| LOCAL_GROUP.to_string())
+    }
+
+    /// Order groups by host name, with local repositories last
+    fn compare_groups(a: &str, b: &str) -> std::cmp::Ordering {
+        (a == LOCAL_GROUP)
+            .cmp(&(b == LOCAL_GROUP))
+            .then_with(|| a.cmp(b))
+    }
+
+    /// Check if a repository is hidden in a collapsed group
+    fn is_collapsed(&self, repo: &GitRepo) -> bool {
+        self.group_by_host && self.collapsed_groups.contains(&Self::group_of(repo))
+    }
+
+    /// Check if a repository is pinned to the top of the list
+    pub fn is_pinned(&self, repo: &GitRepo) -> bool {
+        self.pinned.contains(&strip_unc_pathbuf(repo.path()))
+    }
+
+    /// Pin the selected repository to the top of the list, or unpin it, for the next
+    /// sessions too
+    fn toggle_pin(&mut self) {
+        let Some(repo) = self.current_repo() else {
+            return;
+        };
+        let path = strip_unc_pathbuf(repo.path());
+        let pinned = !self.pinned.remove(&path);
+        if pinned {
+            self.pinned.insert(path.clone());
+        }
+        self.needs_redraw = true;
+
+        // Reload to avoid overwriting settings changed since startup
+        let saved = Settings::load().and_then(|mut settings| settings.set_pinned(&path, pinned));
+        if let Err(err) = saved {
+            self.status_message = Some(format!("Failed to save pinned repositories: {}", err));
+        }
+    }
+
+    /// Show or hide the missing repositories, whatever the view mode
+    fn toggle_hide_missing(&mut self) {
+        self.hide_missing = !self.hide_missing;
+        if self.hide_missing && self.current_repo().is_some_and(|repo| repo.is_missing()) {
+            self.select_first();
+        }
+        self.status_message = Some(if self.hide_missing {
+            "Hiding missing repositories".to_string()
+        } else {
+            "Showing missing repositories".to_string()
+        });
+        self.needs_redraw = true;
+    }
+
+    /// Switch between the remote and working tree status columns and a single glyph
+    fn toggle_compact_status(&mut self) {
+        self.compact_status = !self.compact_status;
+        self.needs_redraw = true;
+    }
+
+    /// Check if the statuses are shown as a single glyph
+    pub fn is_compact_status(&self) -> bool {
+        self.compact_status
+    }
+
+    /// Switch between the flat list and the list grouped by remote host
+    fn toggle_group_by_host(&mut self) {
+        self.group_by_host = !self.group_by_host;
+        if self.group_by_host
+            && self
+                .current_repo()
+                .is_some_and(|repo| self.is_collapsed(repo))
+        {
+            self.select_first();
+        }
+        self.needs_redraw = true;
+    }
+
+    /// Collapse the group of the selected repository, moving the selection to the next
+    /// visible repository; with nothing left to select, expand all groups again
+    fn toggle_collapse_group(&mut self) {
+        if !self.group_by_host {
+            return;
+        }
+        let Some(repo) = self.current_repo() else {
+            self.collapsed_groups.clear();
+            self.select_first();
+            self.needs_redraw = true;
+            return;
+        };
+
+        let group = Self::group_of(repo);
+        let visible = self.visible_repos();
+        let position = self
+            .table_state
+            .selected()
+            .and_then(|selected| visible.iter().position(|&idx| idx == selected))
+            .unwrap_or(0);
+        self.collapsed_groups.insert(group);
+
+        // Prefer the next repository in display order, then the previous one
+        let still_visible = |idx: &&usize| !self.is_collapsed(&self.repos[**idx]);
+        let next = visible[position..]
+            .iter()
+            .find(still_visible)
+            .or_else(|| visible[..position].iter().rev().find(still_visible))
+            .copied();
+        self.table_state.select(next);
+        self.needs_redraw = true;
+    }
+
+    /// Expand all collapsed groups
+    fn expand_all_groups(&mut self) {
+        self.collapsed_groups.clear();
+        if self.table_state.selected().is_none() {
+            self.select_first();
+        }
+        self.needs_redraw = true;
+    }
+
+    /// Read the fields the current sort mode compares
+    fn sort_key(&self, repo: &GitRepo) -> SortKey {
+        SortKey {
+            group: self.group_by_host.then(|| Self::group_of(repo)),
+            pinned: self.is_pinned(repo),
+            missing: repo.is_missing(),
+            branch: match self.sort_mode {
+                SortMode::Branch => repo.branch().to_lowercase(),
+                _ => String::new(),
+            },
+            name: repo.display_name().to_lowercase(),
+            behind: repo.behind_count(),
+            ahead: repo.ahead_count(),
+            clean: repo.is_clean(),
+        }
+    }
+
+    /// Compare two repositories according to the current sort mode and direction
+    ///
+    /// Groups come first when grouping by host, then pinned repositories always come first
+    /// and missing ones last, unless sorted inline; ties are broken by name, ignoring case.
+    fn compare_repos(&self, a: &SortKey, b: &SortKey) -> std::cmp::Ordering {
+        let ordering = match self.sort_mode {
+            SortMode::Name => std::cmp::Ordering::Equal,
+            SortMode::Branch => a.branch.cmp(&b.branch),
+            // Most behind first, then most ahead
+            SortMode::Remote => b.behind.cmp(&a.behind).then_with(|| b.ahead.cmp(&a.ahead)),
+            // Repositories with changes first
+            SortMode::Status => a.clean.cmp(&b.clean),
+        }
+        .then_with(|| a.name.cmp(&b.name));
+
+        let group = match (&a.group, &b.group) {
+            (Some(a), Some(b)) => Self::compare_groups(a, b),
+            _ => std::cmp::Ordering::Equal,
+        };
+        let missing = if self.settings.sort_missing_inline {
+            std::cmp::Ordering::Equal
+        } else {
+            a.missing.cmp(&b.missing)
+        };
+        group
+            .then(b.pinned.cmp(&a.pinned))
+            .then(missing)
+            .then(if self.sort_descending {
+                ordering.reverse()
+            } else {
+                ordering
+            })
+    }
+
+    /// Switch to the next sort mode and remember it for the next sessions
+    fn cycle_sort_mode(&mut self) {
+        self.sort_mode = self.sort_mode.next();
+        self.sort_descending = false;
+        self.needs_redraw = true;
+
+        // Reload to avoid overwriting settings changed since startup
+        if let Ok(mut settings) = Settings::load() {
+            let _ = settings.set_sort_mode(self.sort_mode);
+        }
     }
 
-    /// Handle git data updates
-    fn handle_git_update(&mut self, update: GitDataUpdate) {
-        match update {
-            GitDataUpdate::RemoteStatus(idx, status) => {
-                if let Some(repo) = self.repos.get_mut(idx) {
-                    repo.set_remote_status(status);
-                    self.needs_redraw = true;
-                }
-            }
-            GitDataUpdate::Status(idx, status) => {
-                if let Some(repo) = self.repos.get_mut(idx) {
-                    repo.set_status(status);
-                    self.needs_redraw = true;
-                }
-            }
-            GitDataUpdate::FetchProgress(idx) => {
-                if !self.fetching_repos.contains(&idx) {
-                    self.fetching_repos.push(idx);
-                    self.needs_redraw = true;
-                }
-            }
-            GitDataUpdate::FetchComplete(idx) => {
-                self.fetching_repos.retain(|&i| i != idx);
-                self.fetch_animation_frame = (self.fetch_animation_frame + 1) % 10;
-                self.needs_redraw = true;
-            }
-            GitDataUpdate::CloneProgress(idx) => {
-                if !self.cloning_repos.contains(&idx) {
-                    self.cloning_repos.push(idx);
-                    self.needs_redraw = true;
-                }
-            }
-            GitDataUpdate::CloneComplete(idx) => {
-                self.cloning_repos.retain(|&i| i != idx);
+    /// Switch to the next update strategy and remember it for the next sessions
+    fn cycle_update_strategy(&mut self) {
+        let strategy = self.settings.update_strategy.next();
+        self.settings.update_strategy = strategy;
+        GitRepo::set_update_strategy(strategy);
+        self.status_message = Some(format!("Update strategy: {}", strategy.name()));
+        self.needs_redraw = true;
 
-                // Refresh the repository by recreating it as a normal repo
-                if let Some(repo) = self.repos.get(idx) {
-                    let path = repo.path().to_path_buf();
+        // Reload to avoid overwriting settings changed since startup
+        if let Ok(mut settings) = Settings::load() {
+            let _ = settings.set_update_strategy(strategy);
+        }
+    }
 
-                    // Only refresh if the clone was successful (directory exists)
-                    if path.exists() {
-                        self.repos[idx] = GitRepo::new(path.clone());
-                        Self::sort_repos(&mut self.repos);
+    /// Sort by a table column, or reverse the direction if it is already sorted by it
+    ///
+    /// The selection stays on the same repository, wherever it ends up.
+    fn sort_by_column(&mut self, sort_mode: SortMode) {
+        if self.sort_mode == sort_mode {
+            self.sort_descending = !self.sort_descending;
+            self.needs_redraw = true;
+            return;
+        }
 
-                        if let Some(new_idx) = Self::find_repo_index(&self.repos, &path) {
-                            self.table_state.select(Some(new_idx));
-                            Self::spawn_git_data_load(self.event_handler.git_tx(), new_idx, path);
-                        }
-                    }
-                }
+        self.sort_mode = sort_mode;
+        self.sort_descending = false;
+        self.needs_redraw = true;
 
-                self.needs_redraw = true;
-            }
-            GitDataUpdate::DeleteProgress(idx) => {
-                if !self.deleting_repos.contains(&idx) {
-                    self.deleting_repos.push(idx);
-                    self.needs_redraw = true;
-                }
-            }
-            GitDataUpdate::DeleteComplete(idx) => {
-                self.deleting_repos.retain(|&i| i != idx);
+        // Reload to avoid overwriting settings changed since startup
+        if let Ok(mut settings) = Settings::load() {
+            let _ = settings.set_sort_mode(self.sort_mode);
+        }
+    }
 
-                if let Some(repo) = self.repos.get_mut(idx) {
-                    let repo_path = repo.path().to_path_buf();
-                    repo.set_missing();
-                    Self::sort_repos(&mut self.repos);
+    /// Show only the repositories matching a filter mode, selecting the first one
+    pub fn set_filter_mode(&mut self, filter_mode: FilterMode) {
+        self.filter_mode = filter_mode;
+        self.select_first();
+    }
 
-                    if let Some(new_idx) = Self::find_repo_index(&self.repos, &repo_path) {
-                        self.table_state.select(Some(new_idx));
-                    }
-                }
+    /// Select the first repository in display order
+    fn select_first(&mut self) {
+        self.table_state
+            .select(self.visible_repos().first().copied());
+    }
 
-                self.needs_redraw = true;
-            }
-        }
+    /// Get the table row of the selected repository in display order
+    pub fn selected_row(&self) -> Option<usize> {
+        let selected = TableRow::Repo(self.table_state.selected()?);
+        self.table_rows().iter().position(|row| *row == selected)
     }
 
-    /// Get filtered list of repository indices based on current filter mode
-    pub fn filtered_repos(&self) -> Vec<usize> {
-        self.repos
-            .iter()
-            .enumerate()
-            .filter(|(_, repo)| self.matches_search(repo) && self.matches_filter(repo))
-            .map(|(idx, _)| idx)
-            .collect()
+    /// Get the lowercase query matched against branch names, if searching them
+    ///
+    /// Regex searches only match the repository names.
+    pub fn branch_query(&self) -> Option<String> {
+        if self.regex_search || self.search_query.starts_with(HOST_SEARCH_PREFIX) {
+            return None;
+        }
+        let query = self
+            .search_query
+            .strip_prefix(BRANCH_SEARCH_PREFIX)
+            .unwrap_or(&self.search_query)
+            .to_lowercase();
+        (!query.is_empty()).then_some(query)
     }
 
     /// Check if repository matches search query
@@ -529,7 +3470,36 @@ impl App {
             return true;
         }
 
+        // In regex mode, match against the displayed name ("parent/repo" or the full path).
+        // An invalid regex filters nothing; the status bar reports the error.
+        if self.regex_search {
+            return match &self.search_regex {
+                Some(Ok(regex)) => regex.is_match(&repo.display_name()),
+                _ => true,
+            };
+        }
+
+        // "host:" restricts the search to remote hosts, local repositories never match
+        if let Some(host) = self.search_query.strip_prefix(HOST_SEARCH_PREFIX) {
+            let host = host.to_lowercase();
+            return repo
+                .remote_host()
+                .is_some_and(|repo_host| repo_host.to_lowercase().contains(&host));
+        }
+
+        let branch_match = self
+            .branch_query()
+            .is_none_or(|query| repo.branch().to_lowercase().contains(&query));
+        // "branch:" restricts the search to branch names
+        if self.search_query.starts_with(BRANCH_SEARCH_PREFIX) || branch_match {
+            return branch_match;
+        }
+
         let query_lower = self.search_query.to_lowercase();
+        // Full paths are searched as displayed
+        if self.settings.full_paths {
+            return repo.display_name().to_lowercase().contains(&query_lower);
+        }
         let name_match = repo
             .name()
             .map(|n| n.to_lowercase().contains(&query_lower))
@@ -551,18 +3521,44 @@ impl App {
 
         match self.filter_mode {
             FilterMode::All => true,
+            FilterMode::NeedsAttention => self.needs_attention(repo),
             FilterMode::NoUpstream => {
                 let remote = repo.remote_status();
-                remote == "local-only" || remote == "no-tracking"
+                matches!(remote, "local-only" | "no-tracking" | "upstream-gone")
             }
             FilterMode::Modified => {
-                let status = repo.status();
-                status != "clean" && status != "loading..."
+                !repo.is_clean() && !matches!(repo.status(), "loading..." | NOT_LOADED)
             }
-            FilterMode::Behind => repo.remote_status().contains('↓'),
+            FilterMode::Behind => repo.is_behind(),
         }
     }
 
+    /// Count the displayed repositories needing attention and sum their known sizes
+    pub fn filtered_summary(&self) -> (usize, u64) {
+        self.filtered_repos()
+            .into_iter()
+            .map(|idx| &self.repos[idx])
+            .filter(|repo| !repo.is_missing())
+            .fold((0, 0), |(attention, size), repo| {
+                (
+                    attention + usize::from(self.needs_attention(repo)),
+                    size + repo.size().unwrap_or(0),
+                )
+            })
+    }
+
+    /// Check if repository matches the configured "needs attention" criteria
+    fn needs_attention(&self, repo: &GitRepo) -> bool {
+        let criteria = &self.settings.attention;
+
+        (criteria.include_behind && repo.is_behind())
+            || (criteria.include_ahead && repo.is_ahead())
+            || (criteria.include_dirty && repo.has_changes())
+            || (criteria.include_untracked && repo.has_untracked())
+            || (criteria.include_no_tracking
+                && matches!(repo.remote_status(), "no-tracking" | "upstream-gone"))
+    }
+
     /// Check if search mode is active
     pub fn is_search_mode(&self) -> bool {
         self.search_mode
@@ -573,6 +3569,16 @@ impl App {
         &self.search_query
     }
 
+    /// Check if the search query is interpreted as a regex
+    pub fn is_regex_search(&self) -> bool {
+        self.regex_search
+    }
+
+    /// Check if the search query is an invalid regex
+    pub fn is_search_regex_invalid(&self) -> bool {
+        matches!(self.search_regex, Some(Err(_)))
+    }
+
     /// Check if in delete confirmation mode
     pub fn is_confirmation_mode(&self) -> bool {
         self.delete_confirmation.is_some()
@@ -585,6 +3591,204 @@ impl App {
             .map(|repo| repo.display_short().to_string())
     }
 
+    /// Tell in the status bar how many directories the scan skipped because they could not
+    /// be read; the paths are in the log file
+    pub fn report_skipped_dirs(&mut self, skipped: &[PathBuf]) {
+        if let Some(message) = crate::git_repo::skipped_dirs_message(skipped) {
+            self.status_message = Some(message);
+        }
+    }
+
+    /// Check if the disk usage column is enabled
+    pub fn show_disk_usage(&self) -> bool {
+        self.settings.show_disk_usage
+    }
+
+    /// Enable or disable colors in the interface
+    pub fn set_color_enabled(&mut self, enabled: bool) {
+        self.color_enabled = enabled;
+    }
+
+    /// Check if the interface is drawn with colors
+    pub fn color_enabled(&self) -> bool {
+        self.color_enabled
+    }
+
+    /// Get the current frame of the progress spinner
+    pub fn spinner(&self) -> char {
+        let frames = &self.settings.spinner;
+        let frame_count = frames.chars().count().max(1);
+        frames
+            .chars()
+            .nth(self.fetch_animation_frame % frame_count)
+            .unwrap_or(' ')
+    }
+
+    /// Check if every other row is drawn with a different background
+    pub fn striped_rows(&self) -> bool {
+        self.settings.striped_rows
+    }
+
+    /// Get how repository names too long for their column are shortened
+    pub fn truncate_style(&self) -> TruncateStyle {
+        self.settings.truncate
+    }
+
+    /// Get the selected repository
+    pub fn current_repo(&self) -> Option<&GitRepo> {
+        self.table_state.selected().and_then(|i| self.repos.get(i))
+    }
+
+    /// Check if a new branch name is being typed
+    pub fn is_branch_input_mode(&self) -> bool {
+        self.branch_input.is_some()
+    }
+
+    /// Get the repository name and the branch name typed so far
+    pub fn branch_input(&self) -> Option<(String, &str)> {
+        let (idx, name) = self.branch_input.as_ref()?;
+        let repo = self.repos.get(*idx)?;
+        Some((repo.display_short(), name.as_str()))
+    }
+
+    /// Check if a commit message is being typed
+    pub fn is_commit_input_mode(&self) -> bool {
+        self.commit_input.is_some()
+    }
+
+    /// Get the repository name and the commit message typed so far
+    pub fn commit_input(&self) -> Option<(String, &str)> {
+        let (idx, message) = self.commit_input.as_ref()?;
+        let repo = self.repos.get(*idx)?;
+        Some((repo.display_short(), message.as_str()))
+    }
+
+    /// Check if the new path of a repository is being typed
+    pub fn is_move_input_mode(&self) -> bool {
+        self.move_input.is_some()
+    }
+
+    /// Get the repository name and the new path typed so far
+    pub fn move_input(&self) -> Option<(String, &str)> {
+        let (idx, target) = self.move_input.as_ref()?;
+        let repo = self.repos.get(*idx)?;
+        Some((repo.display_short(), target.as_str()))
+    }
+
+    /// Check if discarding local changes is waiting for confirmation
+    pub fn is_discard_mode(&self) -> bool {
+        self.discard_confirmation.is_some()
+    }
+
+    /// Check if an update of all displayed repositories is waiting for confirmation
+    pub fn is_update_all_mode(&self) -> bool {
+        self.update_all_confirmation.is_some()
+    }
+
+    /// Summarize the pending update of all displayed repositories: how many repositories
+    /// will be fetched, how many of them are behind, and how many local-only and up-to-date
+    /// ones are skipped
+    pub fn update_all_summary(&self) -> Option<(usize, usize, usize, usize)> {
+        let targets = self.update_all_confirmation.as_ref()?;
+        let behind = targets
+            .iter()
+            .filter_map(|&idx| self.repos.get(idx))
+            .filter(|repo| repo.is_behind())
+            .count();
+        let skipped = |status: &str| {
+            self.filtered_repos()
+                .into_iter()
+                .filter(|&idx| {
+                    let repo = &self.repos[idx];
+                    !repo.is_missing() && repo.remote_status() == status
+                })
+                .count()
+        };
+        Some((
+            targets.len(),
+            behind,
+            skipped("local-only"),
+            skipped("up-to-date"),
+        ))
+    }
+
+    /// Get the repository name and the files whose changes would be discarded
+    pub fn discard_confirmation(&self) -> Option<(String, &[String])> {
+        let (path, files) = self.discard_confirmation.as_ref()?;
+        let repo = &self.repos[Self::find_repo_index(&self.repos, path)?];
+        Some((repo.display_short(), files))
+    }
+
+    /// Check if the upstream branch picker is shown
+    pub fn is_upstream_picker_mode(&self) -> bool {
+        self.upstream_picker.is_some()
+    }
+
+    /// Get the repository name, candidate upstreams and picker state
+    pub fn upstream_picker(&mut self) -> Option<(String, &[String], &mut ListState)> {
+        let (idx, candidates, list_state) = self.upstream_picker.as_mut()?;
+        let repo = self.repos.get(*idx)?;
+        Some((repo.display_short(), candidates.as_slice(), list_state))
+    }
+
+    /// Check if the merged branches picker is shown
+    pub fn is_prune_picker_mode(&self) -> bool {
+        self.prune_picker.is_some()
+    }
+
+    /// Get the repository name, merged branches with whether they are checked, and picker state
+    pub fn prune_picker(&mut self) -> Option<(String, &[MergedBranch], &mut ListState)> {
+        let (idx, branches, list_state) = self.prune_picker.as_mut()?;
+        let repo = self.repos.get(*idx)?;
+        Some((repo.display_short(), branches.as_slice(), list_state))
+    }
+
+    /// Check if the worktree picker is shown
+    pub fn is_worktree_picker_mode(&self) -> bool {
+        self.worktree_picker.is_some()
+    }
+
+    /// Get the repository name, worktree paths and branches, and picker state
+    pub fn worktree_picker(&mut self) -> Option<(String, &[Worktree], &mut ListState)> {
+        let (idx, worktrees, list_state) = self.worktree_picker.as_mut()?;
+        let repo = self.repos.get(*idx)?;
+        Some((repo.display_short(), worktrees.as_slice(), list_state))
+    }
+
+    /// Check if the incoming commits preview is shown
+    pub fn is_incoming_mode(&self) -> bool {
+        self.incoming_preview.is_some()
+    }
+
+    /// Get the repository name and incoming commits being previewed
+    pub fn incoming_preview(&self) -> Option<(String, &[String])> {
+        let (idx, commits) = self.incoming_preview.as_ref()?;
+        let repo = self.repos.get(*idx)?;
+        Some((repo.display_short(), commits.as_slice()))
+    }
+
+    /// Check if the full status of a repository is shown
+    pub fn is_full_status_mode(&self) -> bool {
+        self.full_status.is_some()
+    }
+
+    /// Get the repository name, full status lines and scroll offset of the status popup
+    pub fn full_status(&mut self) -> Option<(String, &[String], &mut usize)> {
+        let (idx, lines, scroll) = self.full_status.as_mut()?;
+        let repo = self.repos.get(*idx)?;
+        Some((repo.display_short(), lines.as_slice(), scroll))
+    }
+
+    /// Check if the log of failed operations is shown
+    pub fn is_error_log_mode(&self) -> bool {
+        self.error_log_open
+    }
+
+    /// Get the failures of background operations, oldest first
+    pub fn error_log(&self) -> &VecDeque<String> {
+        &self.errors
+    }
+
     /// Cancel the delete confirmation
     fn cancel_confirmation(&mut self) {
         self.delete_confirmation = None;
@@ -593,7 +3797,7 @@ impl App {
 
     /// Move to next item
     fn next(&mut self) {
-        let filtered = self.filtered_repos();
+        let filtered = self.visible_repos();
         if filtered.is_empty() {
             return;
         }
@@ -612,7 +3816,7 @@ impl App {
 
     /// Move to previous item
     fn previous(&mut self) {
-        let filtered = self.filtered_repos();
+        let filtered = self.visible_repos();
         if filtered.is_empty() {
             return;
         }
@@ -628,8 +3832,41 @@ impl App {
         self.table_state.select(Some(filtered[prev_pos]));
     }
 
+    /// Select the next (or previous) displayed repository needing attention, wrapping around
+    ///
+    /// The "needs attention" criteria are checked whatever the view mode is.
+    fn jump_to_attention(&mut self, forward: bool) {
+        let visible = self.visible_repos();
+        let current = self
+            .table_state
+            .selected()
+            .and_then(|selected| visible.iter().position(|&idx| idx == selected));
+
+        // Start after the selection, and end on it so it is only picked when it is the only one
+        let count = visible.len();
+        let start = current.unwrap_or(if forward { count.wrapping_sub(1) } else { 0 });
+        let found = (1..=count)
+            .map(|offset| {
+                let pos = if forward {
+                    (start + offset) % count
+                } else {
+                    (start + count - offset) % count
+                };
+                visible[pos]
+            })
+            .find(|&idx| self.needs_attention(&self.repos[idx]));
+
+        if let Some(idx) = found {
+            self.table_state.select(Some(idx));
+            self.needs_redraw = true;
+        }
+    }
+
     /// Handle dropping a repository
     fn handle_drop_repo(&mut self) {
+        if self.wait_for_scan() {
+            return;
+        }
         let Some(selected) = self.table_state.selected() else {
             return;
         };
@@ -662,22 +3899,18 @@ impl App {
                 let cleaned_path = strip_unc_pathbuf(repo_path.as_path());
 
                 if let Ok(relative_path) = cleaned_path.strip_prefix(root_path)
-                    && crate::config::remove_from_cache(relative_path).is_ok()
+                    && crate::config::remove_from_cache(root_path, relative_path).is_ok()
                 {
-                    // Remove from repos list
-                    self.repos.remove(selected);
-
-                    // Adjust selection
-                    if !self.repos.is_empty() {
-                        let new_selected = if selected >= self.repos.len() {
-                            self.repos.len() - 1
-                        } else {
-                            selected
-                        };
-                        self.table_state.select(Some(new_selected));
-                    } else {
-                        self.table_state.select(None);
-                    }
+                    // Hide it from the list, then select the repository that took its row
+                    let visible = self.visible_repos();
+                    let position = visible.iter().position(|&idx| idx == selected);
+                    self.dropped.insert(selected);
+                    let visible = self.visible_repos();
+                    let next = position
+                        .and_then(|position| visible.get(position))
+                        .or(visible.last())
+                        .copied();
+                    self.table_state.select(next);
 
                     self.needs_redraw = true;
                 }
@@ -695,19 +3928,31 @@ impl App {
                 let _ = tx.send(GitDataUpdate::DeleteProgress(idx));
 
                 // Perform deletion
-                let delete_result =
-                    tokio::task::spawn_blocking(move || std::fs::remove_dir_all(&repo_path)).await;
+                debug!(path = %repo_path.display(), "deleting");
+                let delete_result = tokio::task::spawn_blocking({
+                    let repo_path = repo_path.clone();
+                    move || GitRepo::delete(&repo_path)
+                })
+                .await;
+                let result = match delete_result {
+                    Ok(result) => result.map_err(|err| err.to_string()),
+                    Err(err) => Err(err.to_string()),
+                };
+                if let Err(err) = &result {
+                    warn!(path = %repo_path.display(), "delete failed: {}", err);
+                }
 
                 // Send delete complete
-                let _ = tx.send(GitDataUpdate::DeleteComplete(idx));
-
-                drop(delete_result); // Ignore result
+                let _ = tx.send(GitDataUpdate::DeleteComplete(idx, result));
             });
         }
     }
 
     /// Handle cloning a missing repository
     fn handle_clone_repo(&mut self) {
+        if self.wait_for_scan() {
+            return;
+        }
         let Some(selected) = self.table_state.selected() else {
             return;
         };
@@ -720,31 +3965,287 @@ impl App {
         if !repo.is_missing() {
             return;
         }
+        if repo.get_remote_url().is_none() {
+            self.status_message = Some(format!(
+                "No remote URL for {}, it cannot be cloned",
+                repo.display_short()
+            ));
+            self.needs_redraw = true;
+            return;
+        }
+
+        // Already being cloned
+        if self.cloning_repos.iter().any(|p| p == repo.path()) {
+            return;
+        }
 
         // Mark as cloning
-        self.cloning_repos.push(selected);
+        self.cloning_repos.push(repo.path().to_path_buf());
         self.needs_redraw = true;
 
-        // Clone the repository in background
-        let repo_clone = repo.clone();
+        Self::spawn_clone(
+            self.event_handler.git_tx(),
+            repo.clone(),
+            None,
+            self.settings.post_clone_hook.clone(),
+        );
+    }
+
+    /// Clone every missing repository, a few at a time.
+    ///
+    /// This is triggered by the 'C' shortcut in normal mode; Esc cancels the batch.
+    fn handle_clone_all_missing(&mut self) {
+        if self.clone_batch.is_some() || self.wait_for_scan() {
+            return;
+        }
+
+        // Only the displayed repositories, so a search or a group narrows down the batch
+        let (to_clone, no_remote): (Vec<GitRepo>, Vec<GitRepo>) = self
+            .visible_repos()
+            .into_iter()
+            .map(|idx| &self.repos[idx])
+            .filter(|repo| {
+                repo.is_missing() && !self.cloning_repos.iter().any(|p| p == repo.path())
+            })
+            .cloned()
+            .partition(|repo| repo.get_remote_url().is_some());
+        if to_clone.is_empty() {
+            if !no_remote.is_empty() {
+                self.status_message = Some(if no_remote.len() == 1 {
+                    "Nothing to clone, 1 repo has no remote URL".to_string()
+                } else {
+                    format!(
+                        "Nothing to clone, {} repos have no remote URL",
+                        no_remote.len()
+                    )
+                });
+                self.needs_redraw = true;
+            }
+            return;
+        }
+
+        let limiter = Arc::new(Semaphore::new(self.settings.max_concurrent_clones.max(1)));
         let tx = self.event_handler.git_tx();
-        let idx = selected;
+        let mut pending = HashSet::new();
+
+        for repo in to_clone {
+            let path = repo.path().to_path_buf();
+            self.cloning_repos.push(path.clone());
+            pending.insert(path);
+            Self::spawn_clone(
+                tx.clone(),
+                repo,
+                Some(limiter.clone()),
+                self.settings.post_clone_hook.clone(),
+            );
+        }
+
+        self.clone_batch = Some(CloneBatch {
+            limiter,
+            total: pending.len(),
+            pending,
+            succeeded: 0,
+            failed: Vec::new(),
+            cancelled: 0,
+            no_remote: no_remote.len(),
+        });
+        self.needs_redraw = true;
+    }
+
+    /// Cancel the running batch clone: queued clones are dropped, running ones finish
+    fn cancel_clone_batch(&mut self) {
+        if let Some(batch) = &self.clone_batch {
+            batch.limiter.close();
+            self.needs_redraw = true;
+        }
+    }
+
+    /// Report the batch clone results once every repository of the batch is done
+    fn finish_clone_batch_if_done(&mut self) {
+        if !self
+            .clone_batch
+            .as_ref()
+            .is_some_and(|batch| batch.pending.is_empty())
+        {
+            return;
+        }
+        let Some(batch) = self.clone_batch.take() else {
+            return;
+        };
+
+        let mut message = format!("Cloned {} repos", batch.succeeded);
+        if !batch.failed.is_empty() {
+            message.push_str(&format!(
+                ", {} failed ({})",
+                batch.failed.len(),
+                batch.failed.join(", ")
+            ));
+        }
+        if batch.cancelled > 0 {
+            message.push_str(&format!(", {} cancelled", batch.cancelled));
+        }
+        if batch.no_remote > 0 {
+            message.push_str(&format!(", {} skipped (no remote URL)", batch.no_remote));
+        }
+        self.status_message = Some(message);
+    }
 
+    /// Spawn task to clone a missing repository, optionally gated by `limiter`
+    ///
+    /// If the limiter is closed before the clone starts, the clone is cancelled.
+    fn spawn_clone(
+        tx: tokio::sync::mpsc::UnboundedSender<GitDataUpdate>,
+        repo: GitRepo,
+        limiter: Option<Arc<Semaphore>>,
+        post_clone_hook: Option<String>,
+    ) {
         tokio::spawn(async move {
+            let path = repo.path().to_path_buf();
+
+            let _permit = match limiter {
+                Some(limiter) => match limiter.acquire_owned().await {
+                    Ok(permit) => Some(permit),
+                    Err(_) => {
+                        let _ = tx.send(GitDataUpdate::CloneCancelled(path));
+                        return;
+                    }
+                },
+                None => None,
+            };
+
             // Send clone progress
-            let _ = tx.send(GitDataUpdate::CloneProgress(idx));
+            let _ = tx.send(GitDataUpdate::CloneProgress(path.clone()));
+            debug!(path = %path.display(), "cloning");
 
             // Perform clone
-            let clone_result =
-                tokio::task::spawn_blocking(move || repo_clone.clone_repository()).await;
+            let clone_result = tokio::task::spawn_blocking(move || repo.clone_repository()).await;
+            let cloned_path = match clone_result {
+                Ok(Ok(cloned_path)) => {
+                    debug!(path = %cloned_path.display(), "cloned");
+                    Ok(cloned_path)
+                }
+                Ok(Err(err)) => {
+                    warn!(path = %path.display(), "clone failed: {}", err);
+                    Err(err.to_string())
+                }
+                Err(err) => {
+                    warn!(path = %path.display(), "clone task failed: {}", err);
+                    Err(err.to_string())
+                }
+            };
 
-            // Send clone complete
-            let _ = tx.send(GitDataUpdate::CloneComplete(idx));
+            // Set up the new repository before it is shown as cloned
+            let hook_result = match (&cloned_path, post_clone_hook) {
+                (Ok(cloned_path), Some(command)) => {
+                    let cloned_path = cloned_path.clone();
+                    tokio::task::spawn_blocking(move || {
+                        GitRepo::run_hook(&cloned_path, &command).map_err(|err| err.to_string())
+                    })
+                    .await
+                    .unwrap_or_else(|err| Err(err.to_string()))
+                }
+                _ => Ok(()),
+            };
 
-            // If successful, the UI will be updated through CloneComplete handler
-            if clone_result.is_ok() {
-                // Repository will be refreshed when user selects it again or on next scan
+            // Send clone complete, the UI is refreshed by the CloneComplete handler
+            let _ = tx.send(GitDataUpdate::CloneComplete(path.clone(), cloned_path));
+            if let Err(err) = hook_result {
+                warn!(path = %path.display(), "post-clone hook failed: {}", err);
+                let _ = tx.send(GitDataUpdate::PostCloneHookFailed(path, err));
             }
         });
     }
+
+    /// Get the message reporting the result of the last batch operation
+    pub fn status_message(&self) -> Option<&str> {
+        self.status_message.as_deref()
+    }
+
+    /// Get the number of finished clones and the size of the running batch clone, if any
+    pub fn clone_batch_progress(&self) -> Option<(usize, usize)> {
+        self.clone_batch.as_ref().map(|batch| {
+            (
+                batch.succeeded + batch.failed.len() + batch.cancelled,
+                batch.total,
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn move_target_stays_within_the_scanned_directory() {
+        let root = Path::new("/repos");
+        assert_eq!(
+            move_target(root, "work/project"),
+            Ok(PathBuf::from("/repos/work/project"))
+        );
+        assert_eq!(
+            move_target(root, "./work/./project/"),
+            Ok(PathBuf::from("/repos/work/project"))
+        );
+        assert!(move_target(root, "/tmp/project").is_err());
+        assert!(move_target(root, "../project").is_err());
+        assert!(move_target(root, "work/../../project").is_err());
+        assert!(move_target(root, ".").is_err());
+    }
+
+    /// Names of the repositories, in the order they are displayed
+    fn displayed_names(app: &App) -> Vec<String> {
+        app.filtered_repos()
+            .into_iter()
+            .map(|idx| app.repos[idx].display_short())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn repositories_sort_present_first_then_by_name() {
+        let repos = vec![
+            GitRepo::new_missing(PathBuf::from("/work/alpha"), None),
+            GitRepo::new(PathBuf::from("/work/Zeta")),
+            GitRepo::new_missing(PathBuf::from("/work/Beta"), None),
+            GitRepo::new(PathBuf::from("/work/gamma")),
+        ];
+        let mut app = App::new(repos, Path::new("/work"), false, false);
+
+        assert_eq!(
+            displayed_names(&app),
+            ["work/gamma", "work/Zeta", "work/alpha", "work/Beta"]
+        );
+
+        app.settings.sort_missing_inline = true;
+        assert_eq!(
+            displayed_names(&app),
+            ["work/alpha", "work/Beta", "work/gamma", "work/Zeta"]
+        );
+
+        // Pinned repositories stay first whatever the direction
+        app.pinned.insert(PathBuf::from("/work/Zeta"));
+        app.sort_descending = true;
+        assert_eq!(
+            displayed_names(&app),
+            ["work/Zeta", "work/gamma", "work/Beta", "work/alpha"]
+        );
+    }
+
+    #[tokio::test]
+    async fn update_all_skips_up_to_date_repositories() {
+        let repos = ["/work/behind", "/work/current", "/work/local"]
+            .map(|path| GitRepo::new(PathBuf::from(path)));
+        let mut app = App::new(repos.into(), Path::new("/work"), false, false);
+        for (repo, status) in app
+            .repos
+            .iter_mut()
+            .zip(["↑0 ↓2", "up-to-date", "local-only"])
+        {
+            repo.set_remote_status(status.to_string());
+        }
+
+        app.handle_update_all();
+        assert_eq!(app.update_all_confirmation, Some(vec![0]));
+        assert_eq!(app.update_all_summary(), Some((1, 1, 1, 1)));
+    }
 }
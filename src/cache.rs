@@ -15,39 +15,32 @@ fn get_relative_path(repo_path: &Path, root_path: &Path) -> Option<PathBuf> {
         .map(|p| p.to_path_buf())
 }
 
-/// Build a set of existing repo relative paths
-fn build_existing_paths(repos: &[GitRepo], root_path: &Path) -> HashSet<PathBuf> {
-    repos
+/// Restore the last known statuses of a discovered repo
+fn restore_statuses(repo: &mut GitRepo, cached_repos: &[CachedRepo], root_path: &Path) {
+    let Some(relative_path) = get_relative_path(repo.path(), root_path) else {
+        return;
+    };
+    let Some(cached) = cached_repos
         .iter()
-        .filter_map(|repo| get_relative_path(repo.path(), root_path))
-        .collect()
-}
-
-/// Add missing repos from cache to the repo list
-fn add_missing_repos(
-    repos: &mut Vec<GitRepo>,
-    cached_repos: &[CachedRepo],
-    existing_paths: &HashSet<PathBuf>,
-    root_path: &Path,
-) {
-    for cached in cached_repos {
-        if !existing_paths.contains(&cached.path) {
-            let full_path = root_path.join(&cached.path);
-            repos.push(GitRepo::new_missing(full_path, cached.remote.clone()));
-        }
+        .find(|cached| cached.path == relative_path)
+    else {
+        return;
+    };
+    if let Some(remote_status) = &cached.remote_status {
+        repo.set_remote_status(remote_status.clone());
+    }
+    if let Some(status) = &cached.status {
+        repo.set_status(status.clone());
     }
-}
-
-/// Merge discovered repos with cached repos by adding missing repos
-fn merge_with_cache(repos: &mut Vec<GitRepo>, root_path: &Path, cached_repos: &[CachedRepo]) {
-    let existing_paths = build_existing_paths(repos, root_path);
-    add_missing_repos(repos, cached_repos, &existing_paths, root_path);
 }
 
 /// Build cache from all repos, sorted alphabetically
-fn build_cache_from_repos(repos: &[GitRepo], root_path: &Path) -> Vec<CachedRepo> {
+fn build_cache_from_repos<'a>(
+    repos: impl IntoIterator<Item = &'a GitRepo>,
+    root_path: &Path,
+) -> Vec<CachedRepo> {
     let mut cache: Vec<CachedRepo> = repos
-        .iter()
+        .into_iter()
         .filter_map(|repo| {
             let relative_path = get_relative_path(repo.path(), root_path)?;
 
@@ -58,9 +51,12 @@ fn build_cache_from_repos(repos: &[GitRepo], root_path: &Path) -> Vec<CachedRepo
                 return None;
             }
 
+            let (remote_status, status) = repo.loaded_statuses();
             Some(CachedRepo {
                 path: relative_path,
                 remote: repo.get_remote_url(),
+                remote_status: remote_status.map(str::to_string),
+                status: status.map(str::to_string),
             })
         })
         .collect();
@@ -70,23 +66,139 @@ fn build_cache_from_repos(repos: &[GitRepo], root_path: &Path) -> Vec<CachedRepo
     cache
 }
 
+/// Get the root directory whose cache is merged when scanning `scan_paths`, if any
+///
+/// The cache is only used when scanning the root directory alone. A flat scan misses nested
+/// repositories, which would show as missing and be dropped from the cache when saving it.
+pub fn cache_root<'a>(scan_paths: &[PathBuf], root_path: Option<&'a Path>) -> Option<&'a Path> {
+    match (scan_paths, root_path) {
+        ([scan_path], Some(root)) if scan_path == root && !GitRepo::is_flat_scan() => Some(root),
+        _ => None,
+    }
+}
+
+/// Scan several directories, handing each repository to `found` as soon as it is discovered
+///
+/// Repositories found twice are handed once. With a `cache_root`, discovered repositories
+/// get their last known statuses, and the cached repositories not found on disk are handed
+/// as missing once the scan is over. Returns the directories skipped because they could not
+/// be read.
+pub fn scan_repos(
+    scan_paths: &[PathBuf],
+    cache_root: Option<&Path>,
+    include_hidden: bool,
+    mut found: impl FnMut(GitRepo),
+) -> Vec<PathBuf> {
+    let cached = cache_root
+        .map(|root| load_repo_cache(root).unwrap_or_default())
+        .unwrap_or_default();
+
+    let mut seen = HashSet::new();
+    let mut skipped = Vec::new();
+    for scan_path in scan_paths {
+        skipped.extend(crate::git_repo::walk_git_repos(
+            scan_path,
+            include_hidden,
+            |mut repo| {
+                if !seen.insert(repo.path().to_path_buf()) {
+                    return;
+                }
+                if let Some(root) = cache_root {
+                    restore_statuses(&mut repo, &cached, root);
+                }
+                found(repo);
+            },
+        ));
+    }
+
+    // Add missing repos from cache
+    if let Some(root) = cache_root {
+        let existing_paths: HashSet<PathBuf> = seen
+            .iter()
+            .filter_map(|path| get_relative_path(path, root))
+            .collect();
+        for cached in cached {
+            if !existing_paths.contains(&cached.path) {
+                found(GitRepo::new_missing(root.join(&cached.path), cached.remote));
+            }
+        }
+    }
+
+    skipped.sort();
+    skipped.dedup();
+    skipped
+}
+
 /// Load repositories, merging with cache if scanning root directory
-pub fn load_repos_with_cache(scan_path: &Path, root_path: Option<&Path>) -> (Vec<GitRepo>, bool) {
-    let mut repos = crate::git_repo::find_git_repos(scan_path);
-    let is_root = if let Some(root) = root_path
-        && scan_path == root
-    {
-        let cached = load_repo_cache().unwrap_or_default();
-        merge_with_cache(&mut repos, root, &cached);
-        true
-    } else {
-        false
-    };
-    (repos, is_root)
+///
+/// Also returns whether the root directory was scanned, and the directories skipped
+/// because they could not be read.
+pub fn load_repos_with_cache(
+    scan_path: &Path,
+    root_path: Option<&Path>,
+    include_hidden: bool,
+) -> (Vec<GitRepo>, bool, Vec<PathBuf>) {
+    load_repos_from_paths(&[scan_path.to_path_buf()], root_path, include_hidden)
 }
 
 /// Save cache from repositories to disk
-pub fn save_repos_to_cache(repos: &[GitRepo], root_path: &Path) -> color_eyre::Result<()> {
+pub fn save_repos_to_cache<'a>(
+    repos: impl IntoIterator<Item = &'a GitRepo>,
+    root_path: &Path,
+) -> color_eyre::Result<()> {
     let cache = build_cache_from_repos(repos, root_path);
     save_repo_cache(root_path, &cache)
 }
+
+/// Load repositories from several directories, skipping the ones found twice
+///
+/// The cache is only used when scanning the root directory alone.
+pub fn load_repos_from_paths(
+    scan_paths: &[PathBuf],
+    root_path: Option<&Path>,
+    include_hidden: bool,
+) -> (Vec<GitRepo>, bool, Vec<PathBuf>) {
+    let cache_root = cache_root(scan_paths, root_path);
+    let mut repos = Vec::new();
+    let skipped = scan_repos(scan_paths, cache_root, include_hidden, |repo| {
+        repos.push(repo)
+    });
+    (repos, cache_root.is_some(), skipped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    fn git(path: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(path)
+            .status()
+            .expect("Failed to run git");
+        assert!(status.success());
+    }
+
+    #[test]
+    fn cache_keeps_the_remote_read_when_scanning() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let path = dir.path().join("project");
+        std::fs::create_dir(&path).expect("Failed to create directory");
+        git(&path, &["init", "--quiet"]);
+        git(&path, &["remote", "add", "origin", "gh:owner/project"]);
+        // The URL is cached as written, not rewritten
+        git(
+            &path,
+            &["config", "url.https://github.com/.insteadOf", "gh:"],
+        );
+        let repo = GitRepo::new(path.clone());
+
+        // Building the cache does not read the config again
+        git(&path, &["remote", "set-url", "origin", "gh:owner/renamed"]);
+        let cache = build_cache_from_repos([&repo], dir.path());
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache[0].path, PathBuf::from("project"));
+        assert_eq!(cache[0].remote.as_deref(), Some("gh:owner/project"));
+    }
+}
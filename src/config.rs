@@ -1,10 +1,72 @@
+use crate::app::SortMode;
 use crate::util::strip_unc_pathbuf;
 use color_eyre::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tracing::warn;
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+/// Criteria deciding which repositories show up in the "Needs Attention" view
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AttentionSettings {
+    /// Include repositories behind their upstream
+    pub include_behind: bool,
+    /// Include repositories ahead of their upstream
+    pub include_ahead: bool,
+    /// Include repositories with staged or modified files
+    pub include_dirty: bool,
+    /// Include repositories with untracked files
+    pub include_untracked: bool,
+    /// Include repositories whose branch has no upstream
+    pub include_no_tracking: bool,
+}
+
+impl Default for AttentionSettings {
+    fn default() -> Self {
+        Self {
+            include_behind: true,
+            include_ahead: false,
+            include_dirty: true,
+            include_untracked: true,
+            include_no_tracking: true,
+        }
+    }
+}
+
+/// Per-repository overrides, read from a `.git-repos.toml` file at the root of a repository
+///
+/// Values set here take precedence over the command line and the global configuration.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RepoSettings {
+    /// Skip this repository during the fetch pass at startup
+    pub no_fetch: Option<bool>,
+    /// Whether to fast-forward this repository after fetching it at startup
+    pub update: Option<bool>,
+}
+
+impl RepoSettings {
+    /// Name of the override file, looked up at the root of each repository
+    const FILE_NAME: &str = ".git-repos.toml";
+
+    /// Load the overrides of a repository; a missing or invalid file overrides nothing
+    pub fn load(repo_path: &Path) -> Self {
+        let path = repo_path.join(Self::FILE_NAME);
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        toml::from_str(&contents).unwrap_or_else(|err| {
+            warn!(path = %path.display(), "invalid repository settings: {}", err);
+            Self::default()
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Settings {
     /// The default root directory to scan for git repositories
     pub root_path: Option<PathBuf>,
@@ -12,6 +74,245 @@ pub struct Settings {
     /// Whether to enable fast-forward merge updates by default
     #[serde(default)]
     pub update_by_default: bool,
+
+    /// Whether to fetch the repositories with remotes at startup, unless `--fetch` or
+    /// `--no-fetch` is passed
+    #[serde(default = "default_fetch_by_default")]
+    pub fetch_by_default: bool,
+
+    /// Whether to scan for repositories inside hidden (dot) directories
+    #[serde(default)]
+    pub include_hidden: bool,
+
+    /// Whether to compute and show the on-disk size of each repository
+    #[serde(default)]
+    pub show_disk_usage: bool,
+
+    /// Whether to leave `.git/objects` out of the disk usage computation
+    #[serde(default)]
+    pub disk_usage_exclude_objects: bool,
+
+    /// Whether to show a desktop notification when background fetches finish
+    #[serde(default)]
+    pub notify_on_fetch: bool,
+
+    /// Whether to compare a detached HEAD with the remote default branch
+    #[serde(default)]
+    pub follow_detached_head: bool,
+
+    /// Whether updates only merge branches that are behind and not ahead of their upstream
+    #[serde(default)]
+    pub update_only_behind: bool,
+
+    /// How diverged branches are updated: only fast-forwarded (skipped), rebased or merged
+    #[serde(default)]
+    pub update_strategy: UpdateStrategy,
+
+    /// Remote statuses, as cached by the previous run, of repositories not fetched at startup
+    #[serde(default)]
+    pub skip_fetch_statuses: Vec<String>,
+
+    /// Whether to skip looking for untracked files when reading the status of repositories
+    #[serde(default)]
+    pub skip_untracked: bool,
+
+    /// Whether to list repositories with unpushed commits when quitting
+    #[serde(default)]
+    pub remind_unpushed: bool,
+
+    /// Whether to render below the prompt instead of on the alternate screen, leaving the
+    /// last frame in the scrollback
+    #[serde(default)]
+    pub inline: bool,
+
+    /// Whether Enter selects a repository without quitting, Alt+Enter selecting and quitting
+    #[serde(default)]
+    pub keep_open: bool,
+
+    /// Height of the list in lines when rendering inline
+    #[serde(default = "default_inline_height")]
+    pub inline_height: u16,
+
+    /// Whether to draw every other row with a different background
+    #[serde(default)]
+    pub striped_rows: bool,
+
+    /// How repositories are ordered in the list
+    #[serde(default)]
+    pub sort_mode: SortMode,
+
+    /// Whether missing repositories are sorted among the existing ones instead of after them
+    #[serde(default)]
+    pub sort_missing_inline: bool,
+
+    /// Which part of the repository names too long for their column is replaced by "…"
+    #[serde(default)]
+    pub truncate: TruncateStyle,
+
+    /// Show the full path of repositories instead of "parent/repo" in the list
+    #[serde(default)]
+    pub full_paths: bool,
+
+    /// Frames of the progress spinner, one character each
+    #[serde(default = "default_spinner")]
+    pub spinner: String,
+
+    /// Delay between two frames of the progress animation, in milliseconds
+    #[serde(default = "default_animation_interval_ms")]
+    pub animation_interval_ms: u64,
+
+    /// Maximum number of repositories cloned at the same time by batch clones
+    #[serde(default = "default_max_concurrent_clones")]
+    pub max_concurrent_clones: usize,
+
+    /// Maximum number of repositories read or fetched at the same time (defaults to the
+    /// number of CPUs)
+    #[serde(default)]
+    pub max_concurrent: Option<usize>,
+
+    /// Fetch a few repositories in the background after this many seconds without input, and
+    /// as often while the TUI stays idle (disabled by default)
+    #[serde(default)]
+    pub idle_fetch_secs: Option<u64>,
+
+    /// Maximum number of repositories fetched by each background fetch while idle
+    #[serde(default = "default_idle_fetch_batch")]
+    pub idle_fetch_batch: usize,
+
+    /// Path prefixes of slow (e.g. network) filesystems, whose repositories are only read on demand
+    #[serde(default)]
+    pub slow_paths: Vec<PathBuf>,
+
+    /// Shell command run in each repository after it is cloned (e.g. `direnv allow`)
+    #[serde(default)]
+    pub post_clone_hook: Option<String>,
+
+    /// Directory where archived repositories are bundled (defaults to the config directory)
+    #[serde(default)]
+    pub archive_dir: Option<PathBuf>,
+
+    /// Repositories listed first whatever the sort order, relative to the root path like the
+    /// cache (absolute for repositories outside of it)
+    #[serde(default)]
+    pub pinned: Vec<PathBuf>,
+
+    /// SSH private key used to clone and fetch from a host (e.g. "git.example.com")
+    #[serde(default)]
+    pub ssh_identities: BTreeMap<String, PathBuf>,
+
+    /// Which statuses count as "needs attention"
+    #[serde(default)]
+    pub attention: AttentionSettings,
+}
+
+/// How updates bring a branch up to date with its upstream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UpdateStrategy {
+    /// Only fast-forward, leaving diverged branches untouched
+    #[default]
+    FfOnly,
+    /// Rebase the local commits of diverged branches on their upstream
+    Rebase,
+    /// Merge the upstream into diverged branches
+    Merge,
+}
+
+impl UpdateStrategy {
+    /// Get the next strategy in the cycle
+    pub fn next(self) -> Self {
+        match self {
+            UpdateStrategy::FfOnly => UpdateStrategy::Rebase,
+            UpdateStrategy::Rebase => UpdateStrategy::Merge,
+            UpdateStrategy::Merge => UpdateStrategy::FfOnly,
+        }
+    }
+
+    /// Get the name of the strategy, as written in the configuration file
+    pub fn name(self) -> &'static str {
+        match self {
+            UpdateStrategy::FfOnly => "ff-only",
+            UpdateStrategy::Rebase => "rebase",
+            UpdateStrategy::Merge => "merge",
+        }
+    }
+}
+
+/// Where an ellipsis replaces the text that does not fit in a column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TruncateStyle {
+    /// Keep the end of the text (e.g. "…d/git-repos")
+    Start,
+    /// Keep both ends of the text (e.g. "narnau…repos")
+    Middle,
+    /// Keep the beginning of the text (e.g. "narnaud/git…")
+    #[default]
+    End,
+}
+
+fn default_fetch_by_default() -> bool {
+    true
+}
+
+fn default_spinner() -> String {
+    "⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏".to_string()
+}
+
+fn default_animation_interval_ms() -> u64 {
+    100
+}
+
+fn default_inline_height() -> u16 {
+    20
+}
+
+fn default_max_concurrent_clones() -> usize {
+    4
+}
+
+fn default_idle_fetch_batch() -> usize {
+    2
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            root_path: None,
+            update_by_default: false,
+            fetch_by_default: default_fetch_by_default(),
+            include_hidden: false,
+            show_disk_usage: false,
+            disk_usage_exclude_objects: false,
+            notify_on_fetch: false,
+            follow_detached_head: false,
+            update_only_behind: false,
+            update_strategy: UpdateStrategy::default(),
+            skip_fetch_statuses: Vec::new(),
+            remind_unpushed: false,
+            skip_untracked: false,
+            inline: false,
+            keep_open: false,
+            inline_height: default_inline_height(),
+            striped_rows: false,
+            sort_mode: SortMode::default(),
+            sort_missing_inline: false,
+            truncate: TruncateStyle::default(),
+            full_paths: false,
+            spinner: default_spinner(),
+            animation_interval_ms: default_animation_interval_ms(),
+            max_concurrent_clones: default_max_concurrent_clones(),
+            max_concurrent: None,
+            idle_fetch_secs: None,
+            idle_fetch_batch: default_idle_fetch_batch(),
+            slow_paths: Vec::new(),
+            archive_dir: None,
+            post_clone_hook: None,
+            pinned: Vec::new(),
+            ssh_identities: BTreeMap::new(),
+            attention: AttentionSettings::default(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +321,12 @@ pub struct CachedRepo {
     pub path: PathBuf,
     /// Remote URL (origin)
     pub remote: Option<String>,
+    /// Last known remote status, shown at startup for repositories on slow paths
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_status: Option<String>,
+    /// Last known working tree status, shown at startup for repositories on slow paths
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
 }
 
 impl Settings {
@@ -33,6 +340,12 @@ impl Settings {
 
         let contents = fs::read_to_string(&config_path)?;
         let settings: Settings = toml::from_str(&contents)?;
+        if settings.spinner.is_empty() {
+            return Err(color_eyre::eyre::eyre!(
+                "`spinner` in {} needs at least one character",
+                config_path.display()
+            ));
+        }
 
         Ok(settings)
     }
@@ -47,7 +360,7 @@ impl Settings {
         }
 
         let contents = toml::to_string_pretty(self)?;
-        fs::write(&config_path, contents)?;
+        write_atomic(&config_path, contents.as_bytes())?;
 
         Ok(())
     }
@@ -60,8 +373,43 @@ impl Settings {
         Ok(config_dir.join("git-repos").join("config.toml"))
     }
 
+    /// Get the maximum number of repositories read or fetched at the same time
+    pub fn max_concurrent(&self) -> usize {
+        self.max_concurrent
+            .unwrap_or_else(|| {
+                std::thread::available_parallelism()
+                    .map(|count| count.get())
+                    .unwrap_or(4)
+            })
+            .max(1)
+    }
+
+    /// Check if a repository is on a slow path, where its status is only read on demand
+    pub fn is_slow_path(&self, path: &Path) -> bool {
+        let path = strip_unc_pathbuf(path);
+        self.slow_paths
+            .iter()
+            .any(|prefix| path.starts_with(prefix))
+    }
+
+    /// Get the directory where archived repositories are bundled
+    pub fn archive_dir(&self) -> Result<PathBuf> {
+        if let Some(archive_dir) = &self.archive_dir {
+            return Ok(archive_dir.clone());
+        }
+        let config_dir = dirs::config_dir()
+            .ok_or_else(|| color_eyre::eyre::eyre!("Could not determine config directory"))?;
+
+        Ok(config_dir.join("git-repos").join("archive"))
+    }
+
     /// Set the root path and save
     pub fn set_root_path(&mut self, path: PathBuf) -> Result<()> {
+        // Keep the legacy cache with the root it was written for
+        if let Some(old_root) = &self.root_path {
+            migrate_legacy_cache(old_root)?;
+        }
+
         // Remove the \\?\ prefix that Windows canonicalize adds
         let cleaned_path = strip_unc_pathbuf(path.as_path());
         self.root_path = Some(cleaned_path);
@@ -73,14 +421,168 @@ impl Settings {
         self.update_by_default = enabled;
         self.save()
     }
+
+    /// Set whether to fetch at startup by default and save
+    pub fn set_fetch(&mut self, enabled: bool) -> Result<()> {
+        self.fetch_by_default = enabled;
+        self.save()
+    }
+
+    /// Set the sort mode and save
+    pub fn set_sort_mode(&mut self, sort_mode: SortMode) -> Result<()> {
+        self.sort_mode = sort_mode;
+        self.save()
+    }
+
+    /// Set how diverged branches are updated and save
+    pub fn set_update_strategy(&mut self, update_strategy: UpdateStrategy) -> Result<()> {
+        self.update_strategy = update_strategy;
+        self.save()
+    }
+
+    /// Pin or unpin a repository and save
+    pub fn set_pinned(&mut self, path: &Path, pinned: bool) -> Result<()> {
+        let path = strip_unc_pathbuf(path);
+        let entry = self.pinned_entry(&path);
+        // Older versions stored absolute paths under the root too
+        self.pinned
+            .retain(|pinned_path| *pinned_path != entry && *pinned_path != path);
+        if pinned {
+            self.pinned.push(entry);
+            self.pinned.sort();
+        }
+        self.save()
+    }
+
+    /// Get the entry of `pinned` for a repository: its path relative to the root path, or its
+    /// absolute path outside of it
+    fn pinned_entry(&self, path: &Path) -> PathBuf {
+        match &self.root_path {
+            Some(root) => path.strip_prefix(root).unwrap_or(path).to_path_buf(),
+            None => path.to_path_buf(),
+        }
+    }
+
+    /// Get the absolute paths of the pinned repositories
+    pub fn pinned_paths(&self) -> Vec<PathBuf> {
+        self.pinned
+            .iter()
+            .map(|path| match &self.root_path {
+                Some(root) => root.join(path),
+                None => path.clone(),
+            })
+            .collect()
+    }
+
+    /// Set whether to notify when fetches finish and save
+    pub fn set_notify(&mut self, enabled: bool) -> Result<()> {
+        self.notify_on_fetch = enabled;
+        self.save()
+    }
+
+    /// Set the SSH private key used for a host and save, checking that the key exists
+    pub fn set_ssh_identity(&mut self, host: &str, identity_file: &Path) -> Result<()> {
+        if !identity_file.is_file() {
+            return Err(color_eyre::eyre::eyre!(
+                "Identity file not found: {}",
+                identity_file.display()
+            ));
+        }
+        let identity_file = strip_unc_pathbuf(&identity_file.canonicalize()?);
+        self.ssh_identities
+            .insert(host.to_lowercase(), identity_file);
+        self.save()
+    }
+
+    /// Set whether to show disk usage and save
+    pub fn set_disk_usage(&mut self, enabled: bool) -> Result<()> {
+        self.show_disk_usage = enabled;
+        self.save()
+    }
+}
+
+/// Get the path to the legacy cache file, shared by all root directories
+fn legacy_cache_file_path() -> Result<PathBuf> {
+    let config_dir = dirs::config_dir()
+        .ok_or_else(|| color_eyre::eyre::eyre!("Could not determine config directory"))?;
+
+    Ok(config_dir.join("git-repos").join("repos.yaml"))
 }
 
-/// Save repository cache to YAML file
-pub fn save_repo_cache(_root: &Path, repos: &[CachedRepo]) -> Result<()> {
+/// Get the path to the log file written with `--verbose`
+pub fn log_file_path() -> Result<PathBuf> {
     let config_dir = dirs::config_dir()
         .ok_or_else(|| color_eyre::eyre::eyre!("Could not determine config directory"))?;
 
-    let cache_path = config_dir.join("git-repos").join("repos.yaml");
+    Ok(config_dir.join("git-repos").join("git-repos.log"))
+}
+
+/// Get the name of the cache file of a root directory
+///
+/// Each root has its own cache, named after a stable hash of the root path.
+fn cache_file_name(root: &Path) -> String {
+    // FNV-1a, which unlike `DefaultHasher` is stable across Rust versions
+    let root = strip_unc_pathbuf(root);
+    let hash = root
+        .to_string_lossy()
+        .bytes()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        });
+
+    format!("repos-{:016x}.yaml", hash)
+}
+
+/// Get the path to the cache file of a root directory, in the cache directory
+/// (`~/.cache` on Linux, `%LOCALAPPDATA%` on Windows)
+fn cache_file_path(root: &Path) -> Result<PathBuf> {
+    let cache_dir = dirs::cache_dir()
+        .ok_or_else(|| color_eyre::eyre::eyre!("Could not determine cache directory"))?;
+
+    Ok(cache_dir.join("git-repos").join(cache_file_name(root)))
+}
+
+/// Get the path where the cache file of a root directory was stored, next to the settings
+fn config_cache_file_path(root: &Path) -> Result<PathBuf> {
+    let config_dir = dirs::config_dir()
+        .ok_or_else(|| color_eyre::eyre::eyre!("Could not determine config directory"))?;
+
+    Ok(config_dir.join("git-repos").join(cache_file_name(root)))
+}
+
+/// Move the cache file of `root` from an older location to the cache directory, if there
+/// is one: the cache file in the configuration directory, or else the legacy cache file
+///
+/// The legacy cache was written for the root configured at the time, so this must be
+/// called before the configured root changes.
+pub fn migrate_legacy_cache(root: &Path) -> Result<()> {
+    let cache_path = cache_file_path(root)?;
+    if cache_path.exists() {
+        return Ok(());
+    }
+
+    for old_path in [config_cache_file_path(root)?, legacy_cache_file_path()?] {
+        if old_path.exists() {
+            if let Some(parent) = cache_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            // The cache and configuration directories may be on different file systems
+            if fs::rename(&old_path, &cache_path).is_err() {
+                fs::copy(&old_path, &cache_path)?;
+                fs::remove_file(&old_path)?;
+            }
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Save repository cache of a root directory to YAML file
+pub fn save_repo_cache(root: &Path, repos: &[CachedRepo]) -> Result<()> {
+    // A cache left in an older location would be picked up again if not moved first
+    migrate_legacy_cache(root)?;
+    let cache_path = cache_file_path(root)?;
 
     // Create parent directory if it doesn't exist
     if let Some(parent) = cache_path.parent() {
@@ -88,17 +590,46 @@ pub fn save_repo_cache(_root: &Path, repos: &[CachedRepo]) -> Result<()> {
     }
 
     let yaml = yaml_serde::to_string(repos)?;
-    fs::write(&cache_path, yaml)?;
+    write_atomic(&cache_path, yaml.as_bytes())
+}
 
+/// Write a file through a temporary file renamed over it
+///
+/// The rename replaces the file at once, so a crash while writing leaves the previous
+/// content intact instead of a truncated file. The temporary file is named after the process
+/// and a counter, so concurrent writers never write to the same one, and it is synced before
+/// the rename so the new content is on disk when it replaces the file.
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let mut temp_name = path.as_os_str().to_owned();
+    temp_name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let temp_path = PathBuf::from(temp_name);
+
+    let written = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp_path, path));
+    if let Err(err) = written {
+        let _ = fs::remove_file(&temp_path);
+        return Err(err.into());
+    }
     Ok(())
 }
 
-/// Load repository cache from YAML file
-pub fn load_repo_cache() -> Result<Vec<CachedRepo>> {
-    let config_dir = dirs::config_dir()
-        .ok_or_else(|| color_eyre::eyre::eyre!("Could not determine config directory"))?;
+/// Load repository cache of a root directory from YAML file
+pub fn load_repo_cache(root: &Path) -> Result<Vec<CachedRepo>> {
+    migrate_legacy_cache(root)?;
 
-    let cache_path = config_dir.join("git-repos").join("repos.yaml");
+    let cache_path = cache_file_path(root)?;
 
     if !cache_path.exists() {
         return Ok(Vec::new());
@@ -110,25 +641,146 @@ pub fn load_repo_cache() -> Result<Vec<CachedRepo>> {
     Ok(repos)
 }
 
-/// Remove a repository from the cache by its relative path
-pub fn remove_from_cache(relative_path: &Path) -> Result<()> {
-    let mut cached_repos = load_repo_cache()?;
+/// What `normalize_repo_cache` fixed in a cache
+#[derive(Debug, Default)]
+pub struct CacheRepair {
+    /// Entries dropped because their path is empty or not relative to the root
+    pub invalid: usize,
+    /// Entries dropped because an earlier one has the same path
+    pub duplicates: usize,
+    /// Whether the entries were not sorted by path
+    pub unsorted: bool,
+}
+
+impl CacheRepair {
+    /// Check if the cache needed no fix
+    pub fn is_clean(&self) -> bool {
+        self.invalid == 0 && self.duplicates == 0 && !self.unsorted
+    }
+}
+
+/// Drop the cache entries with an invalid path, remove duplicates and sort by path
+///
+/// A duplicate without a remote URL gives its place to the first one with a URL.
+pub fn normalize_repo_cache(repos: &mut Vec<CachedRepo>) -> CacheRepair {
+    let mut repair = CacheRepair::default();
+
+    let count = repos.len();
+    repos.retain(|repo| {
+        !repo.path.as_os_str().is_empty()
+            && repo
+                .path
+                .components()
+                .all(|component| matches!(component, std::path::Component::Normal(_)))
+    });
+    repair.invalid = count - repos.len();
+
+    repair.unsorted = !repos.is_sorted_by(|a, b| a.path <= b.path);
+    // Stable, so the first of the duplicates stays first
+    repos.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let count = repos.len();
+    repos.dedup_by(|duplicate, kept| {
+        if duplicate.path != kept.path {
+            return false;
+        }
+        if kept.remote.is_none() {
+            kept.remote = duplicate.remote.take();
+        }
+        true
+    });
+    repair.duplicates = count - repos.len();
+
+    repair
+}
+
+/// Repair the cache file of a root directory, rewriting it only if something was fixed
+pub fn repair_repo_cache(root: &Path) -> Result<(CacheRepair, usize)> {
+    migrate_legacy_cache(root)?;
+    let cache_path = cache_file_path(root)?;
+    if !cache_path.exists() {
+        return Err(color_eyre::eyre::eyre!(
+            "No cache for {}, it is written when quitting after scanning the root",
+            root.display()
+        ));
+    }
+
+    let mut repos = load_repo_cache(root).map_err(|err| {
+        color_eyre::eyre::eyre!("Could not read {}: {}", cache_path.display(), err)
+    })?;
+    let repair = normalize_repo_cache(&mut repos);
+    if !repair.is_clean() {
+        save_repo_cache(root, &repos)?;
+    }
+    Ok((repair, repos.len()))
+}
+
+/// Remove a repository from the cache of a root directory by its relative path
+pub fn remove_from_cache(root: &Path, relative_path: &Path) -> Result<()> {
+    let mut cached_repos = load_repo_cache(root)?;
 
     // Remove the repo with matching path
     cached_repos.retain(|repo| repo.path != relative_path);
 
     // Save updated cache
-    let config_dir = dirs::config_dir()
-        .ok_or_else(|| color_eyre::eyre::eyre!("Could not determine config directory"))?;
-
-    let cache_path = config_dir.join("git-repos").join("repos.yaml");
+    save_repo_cache(root, &cached_repos)
+}
 
-    if let Some(parent) = cache_path.parent() {
-        fs::create_dir_all(parent)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_atomic_replaces_the_file_at_once() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let files = || {
+            let mut files: Vec<_> = fs::read_dir(dir.path())
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name())
+                .collect();
+            files.sort();
+            files
+        };
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "old").unwrap();
+
+        write_atomic(&path, b"new").unwrap();
+        write_atomic(&path, b"newer").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "newer");
+        assert_eq!(files(), ["config.toml"]);
+
+        // A failed rename keeps the previous content, without leaving the temporary file behind
+        let busy = dir.path().join("busy");
+        fs::create_dir_all(busy.join("file")).unwrap();
+        assert!(write_atomic(&busy, b"lost").is_err());
+        assert!(busy.join("file").is_dir());
+        assert_eq!(files(), ["busy", "config.toml"]);
     }
 
-    let yaml = yaml_serde::to_string(&cached_repos)?;
-    fs::write(&cache_path, yaml)?;
-
-    Ok(())
+    #[test]
+    fn pins_are_stored_relative_to_the_root() {
+        let settings = Settings {
+            root_path: Some(PathBuf::from("/repos")),
+            pinned: vec![
+                PathBuf::from("/elsewhere/tool"),
+                PathBuf::from("work/project"),
+            ],
+            ..Settings::default()
+        };
+        assert_eq!(
+            settings.pinned_entry(Path::new("/repos/work/project")),
+            PathBuf::from("work/project")
+        );
+        assert_eq!(
+            settings.pinned_entry(Path::new("/elsewhere/tool")),
+            PathBuf::from("/elsewhere/tool")
+        );
+        assert_eq!(
+            settings.pinned_paths(),
+            [
+                PathBuf::from("/elsewhere/tool"),
+                PathBuf::from("/repos/work/project")
+            ]
+        );
+    }
 }
@@ -1,8 +1,12 @@
+use crate::config::RepoSettings;
+use crate::git_repo::{CORRUPT, GitRepo, Worktree};
 use color_eyre::Result;
 use crossterm::event::{Event, EventStream, KeyCode, KeyEventKind, KeyModifiers};
 use futures::{FutureExt, StreamExt};
 use std::path::PathBuf;
-use tokio::sync::mpsc;
+use std::sync::Arc;
+use tokio::sync::{Semaphore, mpsc};
+use tracing::{debug, warn};
 
 /// Message for async git data updates
 pub enum GitDataUpdate {
@@ -10,10 +14,117 @@ pub enum GitDataUpdate {
     Status(usize, String),
     FetchProgress(usize),
     FetchComplete(usize),
-    CloneProgress(usize),
-    CloneComplete(usize),
+    CloneProgress(PathBuf),
+    CloneComplete(PathBuf, Result<PathBuf, String>), // expected path, actual path or error
+    CloneCancelled(PathBuf),
+    PostCloneHookFailed(PathBuf, String), // expected path, error message
+    SubmoduleProgress(usize),
+    SubmoduleComplete(usize, Result<(), String>),
+    ArchiveProgress(usize),
+    ArchiveComplete(usize, Result<PathBuf, String>), // index, bundle path or error message
     DeleteProgress(usize),
-    DeleteComplete(usize), // index
+    DeleteComplete(usize, Result<(), String>), // index, error message on failure
+    IncomingCommits(usize, Vec<String>),
+    Size(usize, u64),
+    Upstream(usize, String),
+    LatestTag(usize, String),
+    Identity(usize, String),
+    Corruption(usize, String), // index, error of git reading a corrupt repository
+    TagsFetched(usize, Result<String, String>), // index, latest tag or error message
+    BranchCreated(usize, Result<String, String>), // index, new branch or error message
+    Committed(usize, Result<(), String>),
+    HeadCommit(usize, Option<String>), // index, SHA of HEAD or None without commits
+    UpstreamCandidates(usize, Vec<String>),
+    UpstreamSet(usize, Result<String, String>), // index, upstream or error message
+    UpstreamUnset(usize, Result<(), String>),
+    DefaultBranch(usize, Result<String, String>), // index, default branch or error message
+    MergedBranches(usize, Vec<String>),
+    Worktrees(usize, Vec<Worktree>), // index, worktree paths and branches
+    BranchesPruned(usize, Vec<String>, Vec<String>), // index, deleted branches, error messages
+    Diverged(usize),                 // fast-forward skipped, the branch needs a manual rebase
+    UpdateFailed(usize, String),     // index, error message of a fetch, rebase or merge
+    FetchFailed(usize, String),      // index, error message of a background fetch
+    PreviewFailed(usize, String),    // index, error message of the fetch before a preview
+    ChangedFiles(PathBuf, Vec<String>), // repository path, files with local changes
+    ChangesDiscarded(PathBuf, Result<(), String>),
+    RepoFound(Box<GitRepo>),    // repository discovered by the startup scan
+    ScanComplete(Vec<PathBuf>), // directories skipped by the scan (permission denied)
+    FullStatus(usize, Result<Vec<String>, String>), // index, `git status` lines or error message
+}
+
+/// Read remote and working tree status of a repository and send them as updates
+///
+/// Returns the remote status so callers can decide whether to fetch.
+pub async fn load_status(
+    tx: &mpsc::UnboundedSender<GitDataUpdate>,
+    idx: usize,
+    path: PathBuf,
+) -> String {
+    let remote_status = load_remote_status(tx, idx, path.clone()).await;
+
+    let status = tokio::task::spawn_blocking(move || crate::git_repo::GitRepo::read_status(&path))
+        .await
+        .unwrap_or_else(|_| "error".to_string());
+
+    let _ = tx.send(GitDataUpdate::Status(idx, status));
+
+    remote_status
+}
+
+/// Read only the remote status of a repository and send it as an update
+///
+/// For fetches and upstream changes, which leave the working tree untouched: reading its
+/// status again would only add git processes.
+pub async fn load_remote_status(
+    tx: &mpsc::UnboundedSender<GitDataUpdate>,
+    idx: usize,
+    path: PathBuf,
+) -> String {
+    let remote_status =
+        tokio::task::spawn_blocking(move || crate::git_repo::GitRepo::read_remote_status(&path))
+            .await
+            .unwrap_or_else(|_| "error".to_string());
+
+    let _ = tx.send(GitDataUpdate::RemoteStatus(idx, remote_status.clone()));
+
+    remote_status
+}
+
+/// Fetch a repository, optionally fast-forwarding it, then send its new remote status
+pub async fn fetch_repo(
+    tx: mpsc::UnboundedSender<GitDataUpdate>,
+    idx: usize,
+    path: PathBuf,
+    update: bool,
+) {
+    let _ = tx.send(GitDataUpdate::FetchProgress(idx));
+    debug!(path = %path.display(), update, "fetching");
+
+    let fetch_result = tokio::task::spawn_blocking({
+        let path = path.clone();
+        move || crate::git_repo::GitRepo::fetch(&path, update)
+    })
+    .await;
+
+    match &fetch_result {
+        Ok(Ok(true)) => debug!(path = %path.display(), "fetched"),
+        Ok(Ok(false)) => warn!(path = %path.display(), "fetched, diverged from upstream"),
+        Ok(Err(err)) => {
+            warn!(path = %path.display(), "fetch failed: {}", err);
+            let _ = tx.send(GitDataUpdate::FetchFailed(idx, err.to_string()));
+        }
+        Err(err) => warn!(path = %path.display(), "fetch task failed: {}", err),
+    }
+
+    if let Ok(result) = fetch_result {
+        // Re-read remote status after fetch
+        load_remote_status(&tx, idx, path).await;
+        if matches!(result, Ok(false)) {
+            let _ = tx.send(GitDataUpdate::Diverged(idx));
+        }
+    }
+
+    let _ = tx.send(GitDataUpdate::FetchComplete(idx));
 }
 
 /// Terminal event types
@@ -24,93 +135,109 @@ pub enum TerminalEvent {
 
 /// Event handler with channel for git updates
 pub struct EventHandler {
-    terminal_events: EventStream,
+    /// Started on the first read, so the handler can be created without a terminal
+    terminal_events: Option<EventStream>,
     git_rx: mpsc::UnboundedReceiver<GitDataUpdate>,
     git_tx: mpsc::UnboundedSender<GitDataUpdate>,
+    fetch_repos: bool,
+    update_local: bool,
+    skip_fetch_statuses: Arc<Vec<String>>,
+    read_limiter: Arc<Semaphore>,
+    fetch_limiter: Arc<Semaphore>,
 }
 
 impl EventHandler {
-    /// Create a new event handler and spawn git data loading tasks
-    pub fn new<F>(repo_count: usize, get_path: F, fetch_repos: bool, update_local: bool) -> Self
-    where
-        F: Fn(usize) -> PathBuf + Send + 'static,
-    {
-        let (tx, git_rx) = mpsc::unbounded_channel();
-
-        // Spawn background tasks to load git data
-        for idx in 0..repo_count {
-            let path = get_path(idx);
-            let tx_clone = tx.clone();
-            let should_fetch = fetch_repos;
-            let should_update = update_local;
-
-            tokio::spawn(async move {
-                // Load both remote status and working tree status
-                let remote_status = tokio::task::spawn_blocking({
-                    let path = path.clone();
-                    move || crate::git_repo::GitRepo::read_remote_status(&path)
-                })
-                .await
-                .unwrap_or_else(|_| "error".to_string());
-
-                let status = tokio::task::spawn_blocking({
-                    let path = path.clone();
-                    move || crate::git_repo::GitRepo::read_status(&path)
-                })
-                .await
-                .unwrap_or_else(|_| "error".to_string());
-
-                let _ = tx_clone.send(GitDataUpdate::RemoteStatus(idx, remote_status.clone()));
-                let _ = tx_clone.send(GitDataUpdate::Status(idx, status));
-
-                // If fetch is enabled and repo has remote, fetch it
-                if should_fetch && remote_status != "local-only" && remote_status != "error" {
-                    let _ = tx_clone.send(GitDataUpdate::FetchProgress(idx));
-
-                    let fetch_result = tokio::task::spawn_blocking({
-                        let path = path.clone();
-                        move || crate::git_repo::GitRepo::fetch(&path, should_update)
-                    })
-                    .await;
-
-                    if fetch_result.is_ok() {
-                        // Re-read remote status after fetch
-                        let new_remote_status = tokio::task::spawn_blocking(move || {
-                            crate::git_repo::GitRepo::read_remote_status(&path)
-                        })
-                        .await
-                        .unwrap_or_else(|_| "error".to_string());
-
-                        let _ = tx_clone.send(GitDataUpdate::RemoteStatus(idx, new_remote_status));
-                    }
-
-                    let _ = tx_clone.send(GitDataUpdate::FetchComplete(idx));
-                }
-            });
-        }
-        let tx_clone = tx.clone();
+    /// Create a new event handler, loading the repositories given to `load_repo`
+    ///
+    /// Repositories whose cached remote status is in `skip_fetch_statuses` are not fetched.
+    /// At most `max_concurrent` repositories are read, and as many fetched, at the same time.
+    pub fn new(
+        fetch_repos: bool,
+        update_local: bool,
+        skip_fetch_statuses: Vec<String>,
+        max_concurrent: usize,
+    ) -> Self {
+        let (git_tx, git_rx) = mpsc::unbounded_channel();
 
         Self {
-            terminal_events: EventStream::new(),
+            terminal_events: None,
             git_rx,
-            git_tx: tx_clone,
+            git_tx,
+            fetch_repos,
+            update_local,
+            skip_fetch_statuses: Arc::new(skip_fetch_statuses),
+            read_limiter: Arc::new(Semaphore::new(max_concurrent)),
+            fetch_limiter: Arc::new(Semaphore::new(max_concurrent)),
         }
     }
 
+    /// Read the statuses of a repository, then fetch it if enabled
+    ///
+    /// `cached_status` is the remote status cached by the previous run. Reads wait for a
+    /// permit in the order they were requested, so large roots don't flood the blocking
+    /// thread pool.
+    pub fn load_repo(&self, idx: usize, path: PathBuf, cached_status: Option<String>) {
+        let tx = self.git_tx.clone();
+        let read_limiter = self.read_limiter.clone();
+        let fetch_limiter = self.fetch_limiter.clone();
+        let skip_fetch_statuses = self.skip_fetch_statuses.clone();
+        let (fetch_repos, update_local) = (self.fetch_repos, self.update_local);
+
+        tokio::spawn(async move {
+            let remote_status = {
+                let Ok(_permit) = read_limiter.acquire().await else {
+                    return;
+                };
+                // Load both remote status and working tree status
+                load_status(&tx, idx, path.clone()).await
+            };
+
+            // Settings of the repository take precedence over the command line
+            let overrides = RepoSettings::load(&path);
+            let should_fetch = overrides.no_fetch.map_or(fetch_repos, |skip| !skip)
+                && !cached_status.is_some_and(|status| skip_fetch_statuses.contains(&status));
+            let should_update = overrides.update.unwrap_or(update_local);
+
+            // If fetch is enabled and repo has remote, fetch it without holding the read
+            // permit: fetches wait on the network, not on the local machine, and are
+            // limited separately
+            if should_fetch && !matches!(remote_status.as_str(), "local-only" | "error" | CORRUPT) {
+                let Ok(_permit) = fetch_limiter.acquire().await else {
+                    return;
+                };
+                fetch_repo(tx, idx, path, should_update).await;
+            }
+        });
+    }
+
+    /// Drop the terminal event stream, a fresh one starts on the next read
+    ///
+    /// The stream reads from the terminal in a background thread; it must be dropped
+    /// before handing the terminal to a child process, or it would steal its input.
+    pub fn reset_terminal_events(&mut self) {
+        self.terminal_events = None;
+    }
+
     /// Get a clone of the git update sender
     pub fn git_tx(&self) -> mpsc::UnboundedSender<GitDataUpdate> {
         self.git_tx.clone()
     }
 
+    /// Get a git update that is already waiting, without blocking
+    pub fn try_next_git_update(&mut self) -> Option<GitDataUpdate> {
+        self.git_rx.try_recv().ok()
+    }
+
     /// Get next event (terminal or git update)
     pub async fn next(&mut self) -> Result<Option<TerminalEvent>> {
+        let terminal_events = self.terminal_events.get_or_insert_with(EventStream::new);
         tokio::select! {
             // Check for git updates
             Some(update) = self.git_rx.recv() => {
                 Ok(Some(TerminalEvent::GitUpdate(update)))
             }
             // Check for terminal events
-            Some(event) = self.terminal_events.next().fuse() => {
+            Some(event) = terminal_events.next().fuse() => {
                 match event? {
                     Event::Key(key) if key.kind == KeyEventKind::Press => {
                         Ok(Some(TerminalEvent::Key(key.code, key.modifiers)))
@@ -122,3 +249,43 @@ impl EventHandler {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::process::Command;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn every_repository_loads_with_a_single_permit() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        let count = 5;
+        let mut handler = EventHandler::new(false, false, Vec::new(), 1);
+        for idx in 0..count {
+            let path = dir.path().join(format!("repo{}", idx));
+            let status = Command::new("git")
+                .args(["init", "--quiet"])
+                .arg(&path)
+                .status()
+                .expect("Failed to run git");
+            assert!(status.success());
+            handler.load_repo(idx, path, None);
+        }
+
+        let (mut remote_loaded, mut status_loaded) = (HashSet::new(), HashSet::new());
+        while remote_loaded.len() < count || status_loaded.len() < count {
+            let update =
+                tokio::time::timeout(std::time::Duration::from_secs(30), handler.git_rx.recv())
+                    .await
+                    .expect("Timed out waiting for the statuses")
+                    .expect("Channel closed");
+            match update {
+                GitDataUpdate::RemoteStatus(idx, _) => remote_loaded.insert(idx),
+                GitDataUpdate::Status(idx, _) => status_loaded.insert(idx),
+                _ => false,
+            };
+        }
+        assert_eq!(remote_loaded, (0..count).collect());
+        assert_eq!(status_loaded, (0..count).collect());
+    }
+}
@@ -1,18 +1,62 @@
+use crate::config::UpdateStrategy;
 use color_eyre::Result;
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{debug, warn};
 use walkdir::WalkDir;
 
+/// Whether detached HEADs are compared with the remote default branch
+static FOLLOW_DETACHED_HEAD: AtomicBool = AtomicBool::new(false);
+
+/// Whether repositories are displayed with their full path instead of "parent/repo"
+static FULL_PATHS: AtomicBool = AtomicBool::new(false);
+
+/// Whether scans only look at the immediate children of the scanned directory
+static FLAT_SCAN: AtomicBool = AtomicBool::new(false);
+
+/// Whether `read_status` skips looking for untracked files
+static SKIP_UNTRACKED: AtomicBool = AtomicBool::new(false);
+
+/// Marker added to the status when untracked files are not counted
+pub const NO_UNTRACKED: &str = "no-untracked";
+
+/// Status of a repository whose `.git` exists but that git can't read
+pub const CORRUPT: &str = "corrupt";
+
+/// Whether fast-forwards are only attempted on branches strictly behind their upstream
+static UPDATE_ONLY_BEHIND: AtomicBool = AtomicBool::new(false);
+
+/// How `fast_forward` updates branches that diverged from their upstream
+static UPDATE_STRATEGY: RwLock<UpdateStrategy> = RwLock::new(UpdateStrategy::FfOnly);
+
+/// SSH private key to use for each remote host, from the settings
+static SSH_IDENTITIES: RwLock<BTreeMap<String, PathBuf>> = RwLock::new(BTreeMap::new());
+
+/// Path of a worktree and its branch
+pub type Worktree = (PathBuf, String);
+
 /// Represents a Git repository with its path
 #[derive(Debug, Clone)]
 pub struct GitRepo {
     path: PathBuf,
     branch: String,
     remote_status: Option<String>,
+    ahead: Option<u32>,
+    behind: Option<u32>,
     status: Option<String>,
     missing: bool,
     remote_url: Option<String>,
+    size: Option<u64>,
+    upstream: Option<String>,
+    default_branch: Option<String>,
+    latest_tag: Option<String>,
+    identity: Option<String>,
+    corruption: Option<String>,
+    diverged: bool,
 }
 
 impl GitRepo {
@@ -20,14 +64,24 @@ impl GitRepo {
     pub fn new(path: PathBuf) -> Self {
         let branch = Self::read_branch(&path);
         let remote_url = Self::read_remote_url(&path);
+        let default_branch = Self::read_default_branch(&path);
 
         Self {
             path,
             branch,
             remote_status: None,
+            ahead: None,
+            behind: None,
             status: None,
             missing: false,
             remote_url,
+            size: None,
+            upstream: None,
+            default_branch,
+            latest_tag: None,
+            identity: None,
+            corruption: None,
+            diverged: false,
         }
     }
 
@@ -37,9 +91,18 @@ impl GitRepo {
             path,
             branch: String::new(),
             remote_status: None,
+            ahead: None,
+            behind: None,
             status: None,
             missing: true,
             remote_url,
+            size: None,
+            upstream: None,
+            default_branch: None,
+            latest_tag: None,
+            identity: None,
+            corruption: None,
+            diverged: false,
         }
     }
 
@@ -48,14 +111,40 @@ impl GitRepo {
         self.missing
     }
 
+    /// Update the path after the repository was moved
+    pub fn set_path(&mut self, path: PathBuf) {
+        self.path = path;
+    }
+
     /// Mark this repository as missing (deleted)
     pub fn set_missing(&mut self) {
         self.missing = true;
     }
 
+    /// Update the current branch name
+    pub fn set_branch(&mut self, branch: String) {
+        self.branch = branch;
+    }
+
     /// Update the remote status
     pub fn set_remote_status(&mut self, remote_status: String) {
+        (self.ahead, self.behind) = parse_ahead_behind(&remote_status).unzip();
         self.remote_status = Some(remote_status);
+        // Once the branch is back in line with its upstream, it can be fast-forwarded again
+        let still_diverged = self.is_ahead() && self.is_behind();
+        if !still_diverged {
+            self.diverged = false;
+        }
+    }
+
+    /// Mark this repository as not fast-forwardable because it diverged from its upstream
+    pub fn set_diverged(&mut self) {
+        self.diverged = true;
+    }
+
+    /// Check if a fast-forward was skipped because the branch diverged from its upstream
+    pub fn is_diverged(&self) -> bool {
+        self.diverged
     }
 
     /// Update the working tree status
@@ -63,6 +152,93 @@ impl GitRepo {
         self.status = Some(status);
     }
 
+    /// Update the on-disk size
+    pub fn set_size(&mut self, size: u64) {
+        self.size = Some(size);
+    }
+
+    /// Get the on-disk size, if it has been computed
+    pub fn size(&self) -> Option<u64> {
+        self.size
+    }
+
+    /// Forget the remote and working tree status so they show as loading again
+    pub fn reset_status(&mut self) {
+        self.remote_status = None;
+        self.ahead = None;
+        self.behind = None;
+        self.status = None;
+        self.upstream = None;
+        self.latest_tag = None;
+        self.identity = None;
+        self.corruption = None;
+    }
+
+    /// Get the remote and working tree statuses, if they were read
+    pub fn loaded_statuses(&self) -> (Option<&str>, Option<&str>) {
+        (self.remote_status.as_deref(), self.status.as_deref())
+    }
+
+    /// Update the upstream branch name
+    pub fn set_upstream(&mut self, upstream: String) {
+        self.upstream = Some(upstream);
+    }
+
+    /// Get the upstream branch name (e.g. "origin/main"), if it has been read
+    pub fn upstream(&self) -> Option<&str> {
+        self.upstream.as_deref()
+    }
+
+    /// Update the most recent tag reachable from HEAD
+    pub fn set_latest_tag(&mut self, latest_tag: String) {
+        self.latest_tag = Some(latest_tag);
+    }
+
+    /// Get the most recent tag reachable from HEAD ("none" without tags), if it has been read
+    pub fn latest_tag(&self) -> Option<&str> {
+        self.latest_tag.as_deref()
+    }
+
+    /// Update the identity used for commits
+    pub fn set_identity(&mut self, identity: String) {
+        self.identity = Some(identity);
+    }
+
+    /// Get the identity used for commits ("none" when unset), if it has been read
+    pub fn identity(&self) -> Option<&str> {
+        self.identity.as_deref()
+    }
+
+    /// Update the error git gives when reading a corrupt repository
+    pub fn set_corruption(&mut self, corruption: String) {
+        self.corruption = Some(corruption);
+    }
+
+    /// Get the error git gives when reading a corrupt repository, if it has been read
+    pub fn corruption(&self) -> Option<&str> {
+        self.corruption.as_deref()
+    }
+
+    /// Check if git can't read the repository, see `read_corruption`
+    pub fn is_corrupt(&self) -> bool {
+        self.status.as_deref() == Some(CORRUPT)
+    }
+
+    /// Update the default branch of `origin`
+    pub fn set_default_branch(&mut self, default_branch: String) {
+        self.default_branch = Some(default_branch);
+    }
+
+    /// Get the default branch of `origin` (e.g. "main"), if `origin/HEAD` is set
+    pub fn default_branch(&self) -> Option<&str> {
+        self.default_branch.as_deref()
+    }
+
+    /// Check if the current branch is the default branch of `origin`
+    pub fn is_on_default_branch(&self) -> bool {
+        self.default_branch.as_deref() == Some(self.branch.as_str())
+    }
+
     /// Check if async data is loaded
     pub fn is_loaded(&self) -> bool {
         self.remote_status.is_some() && self.status.is_some()
@@ -92,6 +268,16 @@ impl GitRepo {
         }
     }
 
+    /// Get the name shown in the repository list: the full path (without UNC prefix) when
+    /// enabled with `set_full_paths`, "parent/repo" otherwise
+    pub fn display_name(&self) -> String {
+        if FULL_PATHS.load(Ordering::Relaxed) {
+            crate::util::strip_unc_prefix(&self.path.display().to_string()).to_string()
+        } else {
+            self.display_short()
+        }
+    }
+
     /// Get the current branch name
     pub fn branch(&self) -> &str {
         &self.branch
@@ -102,18 +288,86 @@ impl GitRepo {
         self.remote_status.as_deref().unwrap_or("loading...")
     }
 
+    /// Get the number of commits behind upstream, if known
+    pub fn behind_count(&self) -> Option<u32> {
+        self.behind
+    }
+
+    /// Get the number of commits ahead of upstream, if known
+    pub fn ahead_count(&self) -> Option<u32> {
+        self.ahead
+    }
+
+    /// Check if the branch is behind its upstream
+    pub fn is_behind(&self) -> bool {
+        self.behind.is_some_and(|count| count > 0)
+    }
+
+    /// Check if the branch is ahead of its upstream
+    pub fn is_ahead(&self) -> bool {
+        self.ahead.is_some_and(|count| count > 0)
+    }
+
+    /// Check if the working tree has staged or modified tracked files
+    pub fn has_changes(&self) -> bool {
+        self.status.as_deref().is_some_and(|status| {
+            status
+                .split_whitespace()
+                .any(|part| part == "dirty" || part.ends_with('S') || part.ends_with('M'))
+        })
+    }
+
+    /// Check if the working tree is clean and no operation is in progress
+    ///
+    /// Stashed changes do not make a repository unclean.
+    pub fn is_clean(&self) -> bool {
+        self.status.as_deref().is_some_and(|status| {
+            status
+                .split_whitespace()
+                .all(|part| part == "clean" || part == "stash" || part == NO_UNTRACKED)
+        })
+    }
+
+    /// Get the operation in progress (e.g. "REBASING"), if any
+    pub fn operation(&self) -> Option<&str> {
+        self.status
+            .as_deref()?
+            .split_whitespace()
+            .next()
+            .filter(|part| part.chars().all(|c| c.is_ascii_uppercase() || c == '-'))
+    }
+
+    /// Check if the working tree has untracked files
+    pub fn has_untracked(&self) -> bool {
+        self.status
+            .as_deref()
+            .is_some_and(|status| status.split_whitespace().any(|part| part.ends_with('?')))
+    }
+
+    /// Get the host of the remote URL (e.g. "github.com"), or None for local remotes
+    pub fn remote_host(&self) -> Option<String> {
+        url_host(self.remote_url.as_deref()?)
+    }
+
+    /// Get the remote URL in a form shared by all the ways to write it, see `canonical_remote`
+    pub fn canonical_remote(&self) -> Option<String> {
+        self.remote_url.as_deref().map(canonical_remote)
+    }
+
     /// Get the working tree status
     pub fn status(&self) -> &str {
         self.status.as_deref().unwrap_or("loading...")
     }
 
-    /// Get the cached remote URL (origin)
+    /// Get the remote URL (origin) read when the repository was scanned
     pub fn get_remote_url(&self) -> Option<String> {
         self.remote_url.clone()
     }
 
     /// Clone this repository to its expected path
-    pub fn clone_repository(&self) -> Result<()> {
+    ///
+    /// Returns the path where the repository actually landed, see [`Self::locate_clone`].
+    pub fn clone_repository(&self) -> Result<PathBuf> {
         if !self.missing {
             return Err(color_eyre::eyre::eyre!("Repository already exists"));
         }
@@ -131,33 +385,92 @@ impl GitRepo {
         // Check if it's a GitHub repository
         let is_github = remote_url.contains("github.com");
 
-        let output = if is_github {
+        let mut command = if is_github {
             // Use gh repo clone for GitHub repos
-            Command::new("gh")
-                .args(["repo", "clone", remote_url, &self.path.to_string_lossy()])
-                .stdout(std::process::Stdio::null())
-                .stderr(std::process::Stdio::null())
-                .output()
+            let mut command = Command::new("gh");
+            command.args(["repo", "clone", remote_url, &self.path.to_string_lossy()]);
+            command
         } else {
             // Use git clone for non-GitHub repos
-            Command::new("git")
-                .args(["clone", remote_url, &self.path.to_string_lossy()])
-                .stdout(std::process::Stdio::null())
-                .stderr(std::process::Stdio::null())
-                .output()
-        }?;
+            let mut command = Command::new("git");
+            command.args(["clone", remote_url, &self.path.to_string_lossy()]);
+            command
+        };
+        // gh clones with git, which inherits the environment
+        use_ssh_identity(&mut command, remote_url);
+        let output = command
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .output()?;
 
         if !output.status.success() {
             return Err(color_eyre::eyre::eyre!("Failed to clone repository"));
         }
 
+        Self::locate_clone(&self.path).ok_or_else(|| {
+            color_eyre::eyre::eyre!("No repository found in {}", self.path.display())
+        })
+    }
+
+    /// Run a shell command in the repository, e.g. a setup step after cloning
+    pub fn run_hook(path: &Path, command: &str) -> Result<()> {
+        let mut shell = if cfg!(windows) {
+            let mut shell = Command::new("cmd");
+            shell.arg("/C");
+            shell
+        } else {
+            let mut shell = Command::new("sh");
+            shell.arg("-c");
+            shell
+        };
+        let output = shell
+            .arg(command)
+            .current_dir(path)
+            .stdin(std::process::Stdio::null())
+            .output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let message = stderr.lines().last().unwrap_or("").trim();
+            return Err(color_eyre::eyre::eyre!(
+                "`{}` exited with {}{}",
+                command,
+                output.status,
+                if message.is_empty() {
+                    String::new()
+                } else {
+                    format!(": {}", message)
+                }
+            ));
+        }
         Ok(())
     }
 
+    /// Find the repository created by a clone into `expected`
+    ///
+    /// Depending on the tool and how the target path is interpreted, the clone can end
+    /// up in a subdirectory named after the repository instead of `expected` itself.
+    fn locate_clone(expected: &Path) -> Option<PathBuf> {
+        if is_git_repo(expected) {
+            return Some(expected.to_path_buf());
+        }
+
+        WalkDir::new(expected)
+            .min_depth(1)
+            .max_depth(2)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .find(|entry| entry.file_type().is_dir() && is_git_repo(entry.path()))
+            .map(|entry| entry.into_path())
+    }
+
     /// Read the remote URL from git config
+    ///
+    /// The URL is read as written in the config, without applying `insteadOf` rewrites,
+    /// so the cache keeps the exact form used when the repository was set up.
     fn read_remote_url(path: &Path) -> Option<String> {
         Command::new("git")
-            .args(["remote", "get-url", "origin"])
+            .args(["config", "--get", "remote.origin.url"])
             .current_dir(path)
             .output()
             .ok()
@@ -193,12 +506,168 @@ impl GitRepo {
         "unknown".to_string()
     }
 
+    /// Read the default branch of `origin` from the `origin/HEAD` symbolic ref
+    ///
+    /// The ref is only set by `git clone` or `git remote set-head`, so it can be missing
+    /// even when the remote exists. Git resolves it from the directory shared by the
+    /// worktrees, whatever the ref storage.
+    fn read_default_branch(path: &Path) -> Option<String> {
+        let output = Self::local_command(path)
+            .args([
+                "symbolic-ref",
+                "--quiet",
+                "--short",
+                "refs/remotes/origin/HEAD",
+            ])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .strip_prefix("origin/")
+            .map(|branch| branch.to_string())
+    }
+
+    /// Ask `origin` for its default branch and store it in `origin/HEAD`
+    pub fn detect_default_branch(path: &Path) -> Result<String> {
+        let output = Self::network_command(path)
+            .args(["remote", "set-head", "origin", "--auto"])
+            .current_dir(path)
+            .output()?;
+        if !output.status.success() {
+            return Err(color_eyre::eyre::eyre!(
+                "{}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        Self::read_default_branch(path)
+            .ok_or_else(|| color_eyre::eyre::eyre!("origin/HEAD is not set"))
+    }
+
+    /// Read the name of the upstream branch, or "none" when there is no upstream
+    pub fn read_upstream(path: &Path) -> String {
+        Command::new("git")
+            .args(["rev-parse", "--abbrev-ref", "@{upstream}"])
+            .current_dir(path)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .or_else(|| {
+                Self::read_gone_upstream(path).map(|upstream| format!("{} (gone)", upstream))
+            })
+            .unwrap_or_else(|| "none".to_string())
+    }
+
+    /// Read the most recent tag reachable from HEAD, or "none" when there is no tag
+    pub fn read_latest_tag(path: &Path) -> String {
+        Command::new("git")
+            .args(["describe", "--tags", "--abbrev=0"])
+            .current_dir(path)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .unwrap_or_else(|| "none".to_string())
+    }
+
+    /// Read the effective `user.name` and `user.email`, as "Name <email>", or "none" when
+    /// neither is set
+    ///
+    /// Git resolves them for the repository, so conditional includes like `includeIf "gitdir:"`
+    /// are taken into account.
+    pub fn read_identity(path: &Path) -> String {
+        let read = |key: &str| {
+            Command::new("git")
+                .args(["config", "--get", key])
+                .current_dir(path)
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        match (read("user.name"), read("user.email")) {
+            (Some(name), Some(email)) => format!("{} <{}>", name, email),
+            (Some(name), None) => format!("{} (no email)", name),
+            (None, Some(email)) => format!("<{}>", email),
+            (None, None) => "none".to_string(),
+        }
+    }
+
+    /// Fetch all tags from `origin`
+    pub fn fetch_tags(path: &Path) -> Result<()> {
+        let output = Self::network_command(path)
+            .args(["fetch", "--tags", "origin"])
+            .current_dir(path)
+            .output()?;
+        if !output.status.success() {
+            return Err(color_eyre::eyre::eyre!(
+                "{}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Read the full SHA of the commit checked out, or None before the first commit
+    pub fn read_head_commit(path: &Path) -> Option<String> {
+        let output = Command::new("git")
+            .args(["rev-parse", "--verify", "--quiet", "HEAD"])
+            .current_dir(path)
+            .output()
+            .ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+        let commit = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (!commit.is_empty()).then_some(commit)
+    }
+
+    /// Read the committer timestamp of the last commit on HEAD, in seconds since the epoch
+    pub fn read_last_commit_time(path: &Path) -> Option<u64> {
+        let output = Command::new("git")
+            .args(["log", "-1", "--format=%ct"])
+            .current_dir(path)
+            .output()
+            .ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+        String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+    }
+
+    /// Count the commits on HEAD committed after `since` and, if given, before `until`, both
+    /// in seconds since the epoch
+    ///
+    /// A repository without commits has none in any period.
+    pub fn count_commits_between(path: &Path, since: u64, until: Option<u64>) -> usize {
+        let mut command = Command::new("git");
+        command
+            .args(["rev-list", "--count", "HEAD"])
+            .arg(format!("--since=@{}", since));
+        if let Some(until) = until {
+            command.arg(format!("--until=@{}", until));
+        }
+        let output = command.current_dir(path).output();
+
+        match output {
+            Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+                .trim()
+                .parse()
+                .unwrap_or(0),
+            _ => 0,
+        }
+    }
+
     /// Read the remote tracking status (ahead/behind)
     pub fn read_remote_status(path: &Path) -> String {
         // Check if there are any remotes configured
-        let has_remote = Command::new("git")
+        let has_remote = Self::local_command(path)
             .args(["remote"])
-            .current_dir(path)
             .output()
             .ok()
             .and_then(|output| {
@@ -207,158 +676,1173 @@ impl GitRepo {
                 } else {
                     None
                 }
-            })
-            .unwrap_or(false);
+            });
+        let Some(has_remote) = has_remote else {
+            return if Self::read_corruption(path).is_some() {
+                CORRUPT.to_string()
+            } else {
+                "local-only".to_string()
+            };
+        };
 
         if !has_remote {
             return "local-only".to_string();
         }
 
         // Get ahead/behind count
-        let output = Command::new("git")
-            .args(["rev-list", "--left-right", "--count", "HEAD...@{upstream}"])
-            .current_dir(path)
-            .output();
+        if let Some((ahead, behind)) = Self::read_ahead_behind(path, "HEAD...@{upstream}") {
+            if ahead == 0 && behind == 0 {
+                return "up-to-date".to_string();
+            }
+            return format!("↑{} ↓{}", ahead, behind);
+        }
 
-        if let Ok(output) = output
-            && output.status.success()
-        {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let parts: Vec<&str> = stdout.split_whitespace().collect();
+        // A freshly initialized or cloned empty repository has nothing to compare
+        if Self::is_head_unborn(path) {
+            return "no-commits".to_string();
+        }
 
-            if parts.len() == 2
-                && let (Ok(ahead), Ok(behind)) = (parts[0].parse::<i32>(), parts[1].parse::<i32>())
-            {
-                if ahead == 0 && behind == 0 {
-                    return "up-to-date".to_string();
-                }
-                return format!("↑{} ↓{}", ahead, behind);
-            }
+        // The remote branch of the upstream was deleted, e.g. after a branch rename
+        if Self::read_gone_upstream(path).is_some() {
+            return "upstream-gone".to_string();
+        }
+
+        // On a detached HEAD, optionally compare with the remote default branch instead
+        if FOLLOW_DETACHED_HEAD.load(Ordering::Relaxed)
+            && let Some(status) = Self::read_detached_remote_status(path)
+        {
+            return status;
         }
 
         // No tracking branch or error
         "no-tracking".to_string()
     }
 
-    /// Read the working tree status (clean/dirty)
-    pub fn read_status(path: &Path) -> String {
-        // Run git status --porcelain to check for changes
-        let output = Command::new("git")
-            .args(["status", "--porcelain"])
+    /// Read the upstream of the current branch when it is configured but its remote branch no
+    /// longer exists, which `git status -sb` marks as `[gone]`
+    fn read_gone_upstream(path: &Path) -> Option<String> {
+        let head = Command::new("git")
+            .args(["symbolic-ref", "--quiet", "HEAD"])
             .current_dir(path)
-            .output();
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        let head = String::from_utf8_lossy(&head.stdout).trim().to_string();
 
-        if let Ok(output) = output
-            && output.status.success()
-        {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            if stdout.trim().is_empty() {
-                return "clean".to_string();
-            }
+        let output = Command::new("git")
+            .args([
+                "for-each-ref",
+                "--format=%(upstream:short) %(upstream:track)",
+                &head,
+            ])
+            .current_dir(path)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        stdout
+            .trim()
+            .strip_suffix(" [gone]")
+            .map(|upstream| upstream.to_string())
+    }
 
-            // Count staged and unstaged changes
-            let mut staged = 0;
-            let mut unstaged = 0;
+    /// Check if HEAD points to a branch without any commit yet
+    fn is_head_unborn(path: &Path) -> bool {
+        Command::new("git")
+            .args(["rev-parse", "--verify", "--quiet", "HEAD"])
+            .current_dir(path)
+            .output()
+            .is_ok_and(|output| !output.status.success())
+    }
 
-            for line in stdout.lines() {
-                if line.len() >= 2 {
-                    let index_status = &line[0..1];
-                    let work_tree_status = &line[1..2];
+    /// Read the ahead/behind counts of a symmetric difference range like `HEAD...@{upstream}`
+    fn read_ahead_behind(path: &Path, range: &str) -> Option<(i32, i32)> {
+        let output = Command::new("git")
+            .args(["rev-list", "--left-right", "--count", range])
+            .current_dir(path)
+            .output()
+            .ok()?;
 
-                    if index_status != " " && index_status != "?" {
-                        staged += 1;
-                    }
-                    if work_tree_status != " " {
-                        unstaged += 1;
-                    }
-                }
-            }
+        if !output.status.success() {
+            return None;
+        }
 
-            match (staged, unstaged) {
-                (0, u) if u > 0 => format!("{}M", u),
-                (s, 0) if s > 0 => format!("{}S", s),
-                (s, u) if s > 0 && u > 0 => format!("{}S {}M", s, u),
-                _ => "dirty".to_string(),
-            }
-        } else {
-            "unknown".to_string()
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let parts: Vec<&str> = stdout.split_whitespace().collect();
+        if parts.len() != 2 {
+            return None;
         }
+        Some((parts[0].parse().ok()?, parts[1].parse().ok()?))
     }
 
-    /// Fetch from all remotes and optionally fast-forward if possible
-    pub fn fetch(path: &Path, update: bool) -> Result<()> {
-        // First, fetch from all remotes
-        let output = Command::new("git")
-            .args(["fetch", "--all", "--prune"])
+    /// Compare a detached HEAD with the default branch of `origin` (e.g. "↑0 ↓3 origin/main")
+    fn read_detached_remote_status(path: &Path) -> Option<String> {
+        // HEAD is attached to a branch if it is a symbolic ref
+        let attached = Command::new("git")
+            .args(["symbolic-ref", "-q", "HEAD"])
             .current_dir(path)
-            .output()?;
+            .output()
+            .ok()?
+            .status
+            .success();
+        if attached {
+            return None;
+        }
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(color_eyre::eyre::eyre!("git fetch failed: {}", stderr));
+        let default_branch = format!("origin/{}", Self::read_default_branch(path)?);
+
+        let (ahead, behind) = Self::read_ahead_behind(path, &format!("HEAD...{}", default_branch))?;
+        if ahead == 0 && behind == 0 {
+            return Some("up-to-date".to_string());
         }
+        Some(format!("↑{} ↓{} {}", ahead, behind, default_branch))
+    }
 
-        // Try to fast-forward merge the current branch with its upstream if requested
-        if update {
-            // This only succeeds if it's a clean fast-forward (no divergence)
-            let merge_output = Command::new("git")
-                .args(["merge", "--ff-only", "@{upstream}"])
-                .current_dir(path)
-                .output()?;
-
-            // If merge succeeded, also update submodules
-            if merge_output.status.success() {
-                let _ = Command::new("git")
-                    .args(["submodule", "update", "--init", "--recursive"])
-                    .current_dir(path)
-                    .output();
-            }
+    /// Create a git command run in a repository that never falls back to a parent repository
+    ///
+    /// When `.git` is broken, git would otherwise go up the directories and read the
+    /// repository containing the scanned directory, if any.
+    fn local_command(path: &Path) -> Command {
+        let mut command = Command::new("git");
+        command.current_dir(path);
+        if let Some(parent) = path.parent() {
+            command.env("GIT_CEILING_DIRECTORIES", parent);
         }
+        command
+    }
 
-        Ok(())
+    /// Read the error git gives for a repository whose `.git` exists but can't be read,
+    /// or None when the repository is fine
+    pub fn read_corruption(path: &Path) -> Option<String> {
+        let output = Self::local_command(path)
+            .args(["rev-parse", "--git-dir"])
+            .output()
+            .ok()?;
+        if output.status.success() {
+            return None;
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let error = stderr
+            .lines()
+            .map(|line| line.trim_start_matches("fatal: ").trim())
+            .find(|line| !line.is_empty())
+            .unwrap_or("git can't read the repository");
+        Some(error.to_string())
     }
-}
 
-/// Check if a directory is a git repository
-fn is_git_repo(path: &Path) -> bool {
-    path.join(".git").exists()
-}
+    /// Create a git command talking to the remotes of a repository, with the SSH identity
+    /// configured for its origin host
+    fn network_command(path: &Path) -> Command {
+        let mut command = Command::new("git");
+        if let Some(remote_url) = Self::read_remote_url(path) {
+            use_ssh_identity(&mut command, &remote_url);
+        }
+        command
+    }
 
-/// Scan directory recursively and find all git repositories
-pub fn find_git_repos(root: &Path) -> Vec<GitRepo> {
-    WalkDir::new(root)
-        .into_iter()
-        .filter_entry(|e| {
-            let filename = e.file_name();
+    /// Set the SSH private key used to clone and fetch from each host
+    pub fn set_ssh_identities(identities: BTreeMap<String, PathBuf>) {
+        *SSH_IDENTITIES
+            .write()
+            .unwrap_or_else(|err| err.into_inner()) = identities;
+    }
 
-            // Skip .git directories and other hidden directories
-            if filename.to_str().is_some_and(|s| s.starts_with('.')) {
-                return false;
-            }
+    /// Display full paths instead of "parent/repo" in `display_name`
+    pub fn set_full_paths(enabled: bool) {
+        FULL_PATHS.store(enabled, Ordering::Relaxed);
+    }
 
-            // Skip tmp directories
-            if filename.to_str().is_some_and(|s| s == "tmp") {
-                return false;
-            }
+    /// Only look for repositories among the immediate children of the scanned directory in
+    /// `scan_git_repos`
+    pub fn set_flat_scan(enabled: bool) {
+        FLAT_SCAN.store(enabled, Ordering::Relaxed);
+    }
 
-            // Skip if parent is a git repo (don't descend into nested repos)
-            if let Some(parent) = e.path().parent()
-                && parent != root
-                && is_git_repo(parent)
-            {
-                return false;
-            }
+    /// Check if scans only look at the immediate children of the scanned directory
+    pub fn is_flat_scan() -> bool {
+        FLAT_SCAN.load(Ordering::Relaxed)
+    }
 
-            true
-        })
-        .filter_map(|entry| entry.ok())
-        .filter(|entry| entry.file_type().is_dir() && is_git_repo(entry.path()))
-        .map(|entry| {
-            let path = entry
+    /// Enable comparing detached HEADs with the remote default branch in `read_remote_status`
+    pub fn set_follow_detached_head(enabled: bool) {
+        FOLLOW_DETACHED_HEAD.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Skip looking for untracked files in `read_status`, marking the status instead
+    pub fn set_skip_untracked(enabled: bool) {
+        SKIP_UNTRACKED.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Choose how `fast_forward` updates branches that diverged from their upstream
+    pub fn set_update_strategy(strategy: UpdateStrategy) {
+        *UPDATE_STRATEGY
+            .write()
+            .unwrap_or_else(|err| err.into_inner()) = strategy;
+    }
+
+    /// Only attempt fast-forwards in `fast_forward` when the branch is strictly behind
+    pub fn set_update_only_behind(enabled: bool) {
+        UPDATE_ONLY_BEHIND.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Read the working tree status (clean/dirty)
+    ///
+    /// The status is prefixed with the in-progress operation (e.g. "REBASING") if any,
+    /// and suffixed with "stash" when the repository has stashed changes.
+    pub fn read_status(path: &Path) -> String {
+        let Some(changes) = Self::read_changes(path) else {
+            return if Self::read_corruption(path).is_some() {
+                CORRUPT.to_string()
+            } else {
+                "unknown".to_string()
+            };
+        };
+
+        let git_dir = resolve_git_dir(path);
+        let mut parts = Vec::new();
+        if let Some(operation) = Self::read_operation(&git_dir) {
+            parts.push(operation.to_string());
+        }
+        // A clean tree is implied when an operation is shown
+        if changes != "clean" || parts.is_empty() {
+            parts.push(changes);
+        }
+        let stale_submodules = Self::read_stale_submodules(path);
+        if stale_submodules > 0 {
+            parts.push(format!("sub+{}", stale_submodules));
+        }
+        // Linked worktrees share the stash of their main repository
+        if resolve_common_dir(&git_dir)
+            .join("logs")
+            .join("refs")
+            .join("stash")
+            .exists()
+        {
+            parts.push("stash".to_string());
+        }
+        if SKIP_UNTRACKED.load(Ordering::Relaxed) {
+            parts.push(NO_UNTRACKED.to_string());
+        }
+        parts.join(" ")
+    }
+
+    /// Detect a merge, rebase or other operation left in progress in the git directory
+    fn read_operation(git_dir: &Path) -> Option<&'static str> {
+        const MARKERS: [(&str, &str); 6] = [
+            ("rebase-merge", "REBASING"),
+            ("rebase-apply", "REBASING"),
+            ("MERGE_HEAD", "MERGING"),
+            ("CHERRY_PICK_HEAD", "CHERRY-PICKING"),
+            ("REVERT_HEAD", "REVERTING"),
+            ("BISECT_LOG", "BISECTING"),
+        ];
+
+        MARKERS
+            .iter()
+            .find(|(marker, _)| git_dir.join(marker).exists())
+            .map(|(_, operation)| *operation)
+    }
+
+    /// Count submodules checked out at a different commit than the one recorded
+    /// in the superproject
+    fn read_stale_submodules(path: &Path) -> usize {
+        // Avoid spawning git for the common case of a repository without submodules
+        if !path.join(".gitmodules").exists() {
+            return 0;
+        }
+
+        Command::new("git")
+            .args(["submodule", "status", "--recursive"])
+            .current_dir(path)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| {
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .filter(|line| line.starts_with('+'))
+                    .count()
+            })
+            .unwrap_or(0)
+    }
+
+    /// Read the staged/modified/untracked counts, or "clean"
+    fn read_changes(path: &Path) -> Option<String> {
+        // Porcelain v2 keeps the format stable; optional locks would block the user's own
+        // git commands. Without `-uno`, the repository's config (fsmonitor, sparse
+        // checkout, status.showUntrackedFiles) decides how untracked files are found.
+        let mut command = Self::local_command(path);
+        command.args(["--no-optional-locks", "status", "--porcelain=v2"]);
+        if SKIP_UNTRACKED.load(Ordering::Relaxed) {
+            command.arg("-uno");
+        }
+        let output = command.output().ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if stdout.trim().is_empty() {
+            return Some("clean".to_string());
+        }
+
+        // Count staged, unstaged and untracked changes
+        let mut staged = 0;
+        let mut unstaged = 0;
+        let mut untracked = 0;
+
+        for line in stdout.lines() {
+            let mut fields = line.split(' ');
+            match fields.next() {
+                Some("?") => untracked += 1,
+                // Changed ("1"), renamed or copied ("2") and unmerged ("u") entries,
+                // with "." for an unchanged side
+                Some("1" | "2" | "u") => {
+                    let Some(xy) = fields.next() else {
+                        continue;
+                    };
+                    let mut sides = xy.chars();
+                    if sides.next().is_some_and(|side| side != '.') {
+                        staged += 1;
+                    }
+                    if sides.next().is_some_and(|side| side != '.') {
+                        unstaged += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut parts = Vec::new();
+        if staged > 0 {
+            parts.push(format!("{}S", staged));
+        }
+        if unstaged > 0 {
+            parts.push(format!("{}M", unstaged));
+        }
+        if untracked > 0 {
+            parts.push(format!("{}?", untracked));
+        }
+
+        if parts.is_empty() {
+            Some("dirty".to_string())
+        } else {
+            Some(parts.join(" "))
+        }
+    }
+
+    /// Compute the on-disk size of a repository, optionally skipping `.git/objects`
+    pub fn read_disk_usage(path: &Path, exclude_objects: bool) -> u64 {
+        let objects_path = path.join(".git").join("objects");
+
+        WalkDir::new(path)
+            .into_iter()
+            .filter_entry(|e| !(exclude_objects && e.path() == objects_path))
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .filter_map(|entry| entry.metadata().ok())
+            .map(|metadata| metadata.len())
+            .sum()
+    }
+
+    /// Fetch from all remotes and optionally fast-forward if possible
+    ///
+    /// Returns false when the fast-forward was skipped because the branch diverged
+    /// from its upstream.
+    pub fn fetch(path: &Path, update: bool) -> Result<bool> {
+        // First, fetch from all remotes
+        let output = Self::network_command(path)
+            .args(["fetch", "--all", "--prune"])
+            .current_dir(path)
+            .output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(color_eyre::eyre::eyre!("git fetch failed: {}", stderr));
+        }
+
+        // Try to fast-forward merge the current branch with its upstream if requested
+        if update {
+            return Self::fast_forward(path);
+        }
+
+        Ok(true)
+    }
+
+    /// Check what a fetch from all remotes would change, without fetching (`--dry-run`)
+    ///
+    /// Returns the remote-tracking refs that would be updated, each followed by "(new)",
+    /// "(deleted)" or "(forced)" when it is not a plain fast-forward.
+    pub fn fetch_dry_run(path: &Path) -> Result<Vec<String>> {
+        let output = Self::network_command(path)
+            .args(["fetch", "--all", "--prune", "--dry-run"])
+            .current_dir(path)
+            .output()?;
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() {
+            return Err(color_eyre::eyre::eyre!(
+                "git fetch failed: {}",
+                stderr.trim()
+            ));
+        }
+
+        // Updates are reported on stderr, e.g. "   1a2b3c4..5d6e7f8  main -> origin/main"
+        Ok(stderr
+            .lines()
+            .filter_map(|line| {
+                let (summary, target) = line.split_once(" -> ")?;
+                let target = target.split_whitespace().next()?;
+                let kind = if summary.contains("[new") {
+                    " (new)"
+                } else if summary.contains("[deleted]") {
+                    " (deleted)"
+                } else if summary.trim_start().starts_with('+') {
+                    " (forced)"
+                } else {
+                    ""
+                };
+                Some(format!("{}{}", target, kind))
+            })
+            .collect())
+    }
+
+    /// Save all refs of a repository, with their history, to a bundle file
+    pub fn create_bundle(path: &Path, bundle_path: &Path) -> Result<()> {
+        if let Some(parent) = bundle_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let output = Command::new("git")
+            .arg("bundle")
+            .arg("create")
+            .arg(bundle_path)
+            .arg("--all")
+            .current_dir(path)
+            .output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(color_eyre::eyre::eyre!("{}", stderr.trim()));
+        }
+
+        Ok(())
+    }
+
+    /// List the changed and untracked files, as shown by `git status --short`
+    pub fn read_changed_files(path: &Path) -> Vec<String> {
+        let output = Command::new("git")
+            .args(["status", "--porcelain", "--untracked-files=all"])
+            .current_dir(path)
+            .output();
+
+        match output {
+            Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(|line| line.to_string())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Read the long form of `git status`, followed by `git diff --stat` against HEAD
+    ///
+    /// The diff is left out when there is nothing to show or no commit to compare with.
+    pub fn read_full_status(path: &Path) -> Result<Vec<String>> {
+        let output = Command::new("git")
+            .args(["status", "--long"])
+            .current_dir(path)
+            .output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(color_eyre::eyre::eyre!("{}", stderr.trim()));
+        }
+        // Tabs indent the file names, but are not expanded when rendered
+        let mut lines: Vec<String> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| line.replace('\t', "    "))
+            .collect();
+
+        let diff = Command::new("git")
+            .args(["diff", "HEAD", "--stat"])
+            .current_dir(path)
+            .output();
+        if let Ok(diff) = diff
+            && diff.status.success()
+            && !diff.stdout.is_empty()
+        {
+            lines.push(String::new());
+            lines.extend(
+                String::from_utf8_lossy(&diff.stdout)
+                    .lines()
+                    .map(|line| line.to_string()),
+            );
+        }
+        Ok(lines)
+    }
+
+    /// Throw away all local changes: reset tracked files to HEAD and remove untracked files
+    pub fn discard_changes(path: &Path) -> Result<()> {
+        for args in [["reset", "--hard"].as_slice(), ["clean", "-fd"].as_slice()] {
+            let output = Command::new("git").args(args).current_dir(path).output()?;
+
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                return Err(color_eyre::eyre::eyre!("{}", stderr.trim()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Delete the working copy of a repository from disk
+    pub fn delete(path: &Path) -> Result<()> {
+        fs::remove_dir_all(path)?;
+        Ok(())
+    }
+
+    /// Move the working copy of a repository to a new path, creating parent directories
+    pub fn move_to(path: &Path, target: &Path) -> Result<()> {
+        if target.exists() {
+            return Err(color_eyre::eyre::eyre!(
+                "{} already exists",
+                target.display()
+            ));
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::rename(path, target).map_err(|err| {
+            if err.kind() == std::io::ErrorKind::CrossesDevices {
+                color_eyre::eyre::eyre!("cannot move a repository to another filesystem")
+            } else {
+                err.into()
+            }
+        })
+    }
+
+    /// Create a new branch from HEAD and switch to it
+    pub fn create_branch(path: &Path, name: &str) -> Result<()> {
+        let output = Command::new("git")
+            .args(["checkout", "-b", name])
+            .current_dir(path)
+            .output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(color_eyre::eyre::eyre!("{}", stderr.trim()));
+        }
+
+        Ok(())
+    }
+
+    /// Stage all changes, untracked files included, and commit them with `message`
+    pub fn commit_all(path: &Path, message: &str) -> Result<()> {
+        let output = Command::new("git")
+            .args(["add", "-A"])
+            .current_dir(path)
+            .output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(color_eyre::eyre::eyre!("{}", stderr.trim()));
+        }
+
+        let output = Command::new("git")
+            .args(["commit", "-m", message])
+            .current_dir(path)
+            .stdin(std::process::Stdio::null())
+            .output()?;
+        if !output.status.success() {
+            // "nothing to commit" is printed on stdout, hook failures on stderr
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let message = stderr
+                .lines()
+                .chain(stdout.lines())
+                .map(str::trim)
+                .rfind(|line| !line.is_empty())
+                .unwrap_or("git commit failed");
+            return Err(color_eyre::eyre::eyre!("{}", message));
+        }
+
+        Ok(())
+    }
+
+    /// Set the upstream of the current branch (e.g. "origin/main")
+    pub fn track_upstream(path: &Path, upstream: &str) -> Result<()> {
+        let output = Command::new("git")
+            .args(["branch", &format!("--set-upstream-to={}", upstream)])
+            .current_dir(path)
+            .output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(color_eyre::eyre::eyre!("{}", stderr.trim()));
+        }
+
+        Ok(())
+    }
+
+    /// Remove the upstream of the current branch
+    pub fn untrack_upstream(path: &Path) -> Result<()> {
+        let output = Command::new("git")
+            .args(["branch", "--unset-upstream"])
+            .current_dir(path)
+            .output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(color_eyre::eyre::eyre!("{}", stderr.trim()));
+        }
+
+        Ok(())
+    }
+
+    /// Check if a remote-tracking branch (e.g. "origin/main") exists
+    pub fn remote_branch_exists(path: &Path, name: &str) -> bool {
+        Command::new("git")
+            .args([
+                "rev-parse",
+                "--verify",
+                "--quiet",
+                &format!("refs/remotes/{}", name),
+            ])
+            .current_dir(path)
+            .output()
+            .is_ok_and(|output| output.status.success())
+    }
+
+    /// Read the names of all remote-tracking branches, without the symbolic `HEAD`s
+    pub fn read_remote_branches(path: &Path) -> Vec<String> {
+        let output = Command::new("git")
+            .args(["branch", "--remotes", "--format=%(refname:short)"])
+            .current_dir(path)
+            .output();
+
+        match output {
+            Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter(|line| line.contains('/') && !line.ends_with("/HEAD"))
+                .map(|line| line.to_string())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Read the local branches merged into the remote default branch, except the default
+    /// branch itself and `current`
+    pub fn read_merged_branches(path: &Path, default_branch: &str, current: &str) -> Vec<String> {
+        let output = Command::new("git")
+            .args([
+                "branch",
+                "--merged",
+                &format!("origin/{}", default_branch),
+                "--format=%(refname:short)",
+            ])
+            .current_dir(path)
+            .output();
+
+        match output {
+            // A detached HEAD is listed as "(HEAD detached at ...)"
+            Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter(|line| {
+                    !line.is_empty()
+                        && !line.starts_with('(')
+                        && *line != current
+                        && *line != default_branch
+                })
+                .map(|line| line.to_string())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Delete a local branch, refused by git if it is not fully merged
+    pub fn delete_branch(path: &Path, name: &str) -> Result<()> {
+        let output = Command::new("git")
+            .args(["branch", "-d", name])
+            .current_dir(path)
+            .output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(color_eyre::eyre::eyre!("{}", stderr.trim()));
+        }
+
+        Ok(())
+    }
+
+    /// Read the worktrees of a repository (`git worktree list --porcelain`), main one first,
+    /// with their branch ("detached" or "bare" when they have none)
+    pub fn read_worktrees(path: &Path) -> Vec<Worktree> {
+        let output = Command::new("git")
+            .args(["worktree", "list", "--porcelain"])
+            .current_dir(path)
+            .output();
+
+        let stdout = match output {
+            Ok(output) if output.status.success() => output.stdout,
+            _ => return Vec::new(),
+        };
+
+        // Worktrees are blocks of "key value" lines separated by an empty line
+        let mut worktrees: Vec<Worktree> = Vec::new();
+        for line in String::from_utf8_lossy(&stdout).lines() {
+            if let Some(worktree) = line.strip_prefix("worktree ") {
+                worktrees.push((PathBuf::from(worktree), String::new()));
+            } else if let Some((_, branch)) = worktrees.last_mut() {
+                if let Some(name) = line.strip_prefix("branch ") {
+                    *branch = name.strip_prefix("refs/heads/").unwrap_or(name).to_string();
+                } else if line == "detached" || line == "bare" {
+                    *branch = line.to_string();
+                }
+            }
+        }
+        worktrees
+    }
+
+    /// Fast-forward the current branch to its upstream, without fetching
+    ///
+    /// Returns false, without touching the branch, if it diverged from its upstream and the
+    /// update strategy is fast-forward only. With the rebase and merge strategies, a diverged
+    /// branch is rebased or merged instead; on conflicts, it is aborted and an error lists
+    /// the conflicting files.
+    pub fn fast_forward(path: &Path) -> Result<bool> {
+        let strategy = *UPDATE_STRATEGY
+            .read()
+            .unwrap_or_else(|err| err.into_inner());
+        let ahead_behind = Self::read_ahead_behind(path, "HEAD...@{upstream}");
+        let diverged = matches!(ahead_behind, Some((ahead, behind)) if ahead > 0 && behind > 0);
+
+        // A diverged branch needs a manual rebase or merge
+        if diverged && strategy == UpdateStrategy::FfOnly {
+            return Ok(false);
+        }
+
+        // Skip the merge, and the submodule update after it, when there is nothing to merge
+        if UPDATE_ONLY_BEHIND.load(Ordering::Relaxed)
+            && !matches!(ahead_behind, Some((_, behind)) if behind > 0)
+        {
+            return Ok(true);
+        }
+
+        if diverged {
+            Self::integrate_upstream(path, strategy)?;
+            let _ = Self::update_submodules(path);
+            return Ok(true);
+        }
+
+        // This only succeeds if it's a clean fast-forward (no divergence)
+        let merge_output = Command::new("git")
+            .args(["merge", "--ff-only", "@{upstream}"])
+            .current_dir(path)
+            .output()?;
+
+        // If merge succeeded, also update submodules
+        if merge_output.status.success() {
+            let _ = Self::update_submodules(path);
+        }
+
+        Ok(true)
+    }
+
+    /// Rebase the current branch on its upstream, or merge the upstream into it
+    ///
+    /// On conflicts, the rebase or merge is aborted so the repository is left as it was.
+    fn integrate_upstream(path: &Path, strategy: UpdateStrategy) -> Result<()> {
+        let (operation, args): (&str, &[&str]) = match strategy {
+            UpdateStrategy::FfOnly => return Ok(()),
+            UpdateStrategy::Rebase => ("rebase", &["rebase", "@{upstream}"]),
+            UpdateStrategy::Merge => ("merge", &["merge", "--no-edit", "@{upstream}"]),
+        };
+        let output = Command::new("git").args(args).current_dir(path).output()?;
+        if output.status.success() {
+            return Ok(());
+        }
+
+        let conflicts = Command::new("git")
+            .args(["diff", "--name-only", "--diff-filter=U"])
+            .current_dir(path)
+            .output()
+            .map(|output| {
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        // Fails harmlessly when git refused to start (e.g. unstaged changes)
+        let _ = Command::new("git")
+            .args([operation, "--abort"])
+            .current_dir(path)
+            .output();
+
+        if conflicts.is_empty() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            // Progress lines end with a carriage return, look for the error itself first
+            let lines: Vec<&str> = stderr
+                .split(['\n', '\r'])
+                .chain(stdout.lines())
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .collect();
+            let message = lines
+                .iter()
+                .find_map(|line| {
+                    line.strip_prefix("fatal: ")
+                        .or_else(|| line.strip_prefix("error: "))
+                })
+                .or_else(|| lines.first().copied())
+                .unwrap_or("unknown error");
+            return Err(color_eyre::eyre::eyre!("{} failed: {}", operation, message));
+        }
+        Err(color_eyre::eyre::eyre!(
+            "{} aborted, conflicts in {}",
+            operation,
+            conflicts.join(", ")
+        ))
+    }
+
+    /// Check out the commits recorded in the superproject in all submodules
+    pub fn update_submodules(path: &Path) -> Result<()> {
+        let output = Command::new("git")
+            .args(["submodule", "update", "--init", "--recursive"])
+            .current_dir(path)
+            .output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(color_eyre::eyre::eyre!("{}", stderr.trim()));
+        }
+
+        Ok(())
+    }
+
+    /// Read the one-line summaries of commits on the upstream that are not in HEAD
+    pub fn read_incoming_commits(path: &Path) -> Vec<String> {
+        let output = Command::new("git")
+            .args(["log", "HEAD..@{upstream}", "--oneline"])
+            .current_dir(path)
+            .output();
+
+        match output {
+            Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(|line| line.to_string())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Read the commit counts of a remote status such as "↑2 ↓3", or "↑0 ↓3 origin/main" for a
+/// detached HEAD, as (ahead, behind)
+fn parse_ahead_behind(remote_status: &str) -> Option<(u32, u32)> {
+    let mut parts = remote_status.split_whitespace();
+    let ahead = parts.next()?.strip_prefix('↑')?.parse().ok()?;
+    let behind = parts.next()?.strip_prefix('↓')?.parse().ok()?;
+    Some((ahead, behind))
+}
+
+/// Resolve the git directory of a repository, following `.git` files used by worktrees
+/// and submodules
+fn resolve_git_dir(path: &Path) -> PathBuf {
+    let dot_git = path.join(".git");
+    if dot_git.is_file()
+        && let Ok(content) = fs::read_to_string(&dot_git)
+        && let Some(git_dir) = content.trim().strip_prefix("gitdir: ")
+    {
+        return path.join(git_dir);
+    }
+    dot_git
+}
+
+/// Resolve the directory shared by all the worktrees of a repository, from the `commondir`
+/// file of a linked worktree git directory
+fn resolve_common_dir(git_dir: &Path) -> PathBuf {
+    match fs::read_to_string(git_dir.join("commondir")) {
+        Ok(content) => git_dir.join(content.trim()),
+        Err(_) => git_dir.to_path_buf(),
+    }
+}
+
+/// Check if a directory is a git repository
+fn is_git_repo(path: &Path) -> bool {
+    path.join(".git").exists()
+}
+
+/// Check if a directory, or one of its subdirectories up to `max_depth` levels down, is a
+/// git repository, without reading the repositories found
+pub fn contains_git_repo(root: &Path, max_depth: usize) -> bool {
+    WalkDir::new(root)
+        .max_depth(max_depth)
+        .into_iter()
+        .filter_entry(|e| e.file_name() != ".git")
+        .filter_map(|entry| entry.ok())
+        .any(|entry| entry.file_type().is_dir() && is_git_repo(entry.path()))
+}
+
+/// Get the host of a remote URL, or None for a local path
+///
+/// Handles both URLs ("https://github.com/user/repo") and scp-like addresses
+/// ("git@github.com:user/repo").
+fn url_host(remote_url: &str) -> Option<String> {
+    let authority = match remote_url.split_once("://") {
+        Some(("file", _)) => return None,
+        Some((_, rest)) => rest.split('/').next()?,
+        None => {
+            let (authority, _) = remote_url.split_once(':')?;
+            // A Windows drive letter, or a relative path containing a colon
+            if authority.len() == 1 || authority.contains('/') || authority.contains('\\') {
+                return None;
+            }
+            authority
+        }
+    };
+
+    let host = authority.rsplit('@').next()?;
+    let host = host.split(':').next()?.to_lowercase();
+    (!host.is_empty()).then_some(host)
+}
+
+/// Normalize a remote URL to "host/path", so SSH, HTTPS and scp-like addresses of the same
+/// repository compare equal (e.g. "git@github.com:user/repo.git" and
+/// "https://github.com/user/repo" both give "github.com/user/repo")
+///
+/// Local remotes keep their path, without the trailing ".git" and slashes.
+pub fn canonical_remote(remote_url: &str) -> String {
+    let trim = |path: &str| {
+        let path = path.trim_end_matches('/');
+        path.strip_suffix(".git").unwrap_or(path).to_string()
+    };
+
+    let Some(host) = url_host(remote_url) else {
+        return trim(remote_url.strip_prefix("file://").unwrap_or(remote_url));
+    };
+    let path = match remote_url.split_once("://") {
+        Some((_, rest)) => rest.split_once('/').map_or("", |(_, path)| path),
+        None => remote_url.split_once(':').map_or("", |(_, path)| path),
+    };
+    format!("{}/{}", host, trim(path.trim_start_matches(['/', '~'])))
+}
+
+/// Make an ssh-based git command use the identity configured for the host of `remote_url`
+fn use_ssh_identity(command: &mut Command, remote_url: &str) {
+    let Some(host) = url_host(remote_url) else {
+        return;
+    };
+    let identities = SSH_IDENTITIES.read().unwrap_or_else(|err| err.into_inner());
+    if let Some(identity_file) = identities.get(&host) {
+        // Only offer this key, not the ones of the agent
+        command.env(
+            "GIT_SSH_COMMAND",
+            format!(
+                "ssh -i {} -o IdentitiesOnly=yes",
+                crate::util::shell_quote(&identity_file.to_string_lossy())
+            ),
+        );
+    }
+}
+
+/// Scan directory recursively and find all git repositories
+///
+/// Hidden directories are skipped unless `include_hidden` is set; `.git` always is.
+pub fn find_git_repos(root: &Path, include_hidden: bool) -> Vec<GitRepo> {
+    scan_git_repos(root, include_hidden).0
+}
+
+/// Scan directory recursively like `find_git_repos`, also returning the directories that
+/// could not be read because of their permissions
+pub fn scan_git_repos(root: &Path, include_hidden: bool) -> (Vec<GitRepo>, Vec<PathBuf>) {
+    let mut repos = Vec::new();
+    let denied = walk_git_repos(root, include_hidden, |repo| repos.push(repo));
+    (repos, denied)
+}
+
+/// Scan directory recursively like `scan_git_repos`, handing each repository to `found` as
+/// soon as it is discovered
+///
+/// Returns the directories that could not be read because of their permissions.
+pub fn walk_git_repos(
+    root: &Path,
+    include_hidden: bool,
+    mut found: impl FnMut(GitRepo),
+) -> Vec<PathBuf> {
+    if GitRepo::is_flat_scan() {
+        return walk_flat(root, include_hidden, found);
+    }
+
+    let started = std::time::Instant::now();
+    let mut denied = Vec::new();
+    let mut count = 0;
+    WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| {
+            let filename = e.file_name();
+
+            // Skip .git directories
+            if filename == ".git" {
+                return false;
+            }
+
+            // Skip other hidden directories, but never the scanned directory itself
+            if !include_hidden
+                && e.depth() > 0
+                && filename.to_str().is_some_and(|s| s.starts_with('.'))
+            {
+                return false;
+            }
+
+            // Skip tmp directories
+            if filename.to_str().is_some_and(|s| s == "tmp") {
+                return false;
+            }
+
+            // Skip if parent is a git repo (don't descend into nested repos)
+            if let Some(parent) = e.path().parent()
+                && parent != root
+                && is_git_repo(parent)
+            {
+                return false;
+            }
+
+            true
+        })
+        .filter_map(|entry| match entry {
+            Ok(entry) => Some(entry),
+            Err(err) => {
+                if err.io_error().map(|err| err.kind())
+                    == Some(std::io::ErrorKind::PermissionDenied)
+                    && let Some(path) = err.path()
+                {
+                    warn!(path = %path.display(), "skipped directory, permission denied");
+                    denied.push(path.to_path_buf());
+                }
+                None
+            }
+        })
+        .filter(|entry| entry.file_type().is_dir() && is_git_repo(entry.path()))
+        .for_each(|entry| {
+            let path = entry
                 .path()
                 .canonicalize()
                 .unwrap_or_else(|_| entry.path().to_path_buf());
-            GitRepo::new(path)
+            count += 1;
+            found(GitRepo::new(path));
+        });
+
+    debug!(
+        root = %root.display(),
+        count,
+        elapsed = ?started.elapsed(),
+        "scanned for repositories"
+    );
+    denied
+}
+
+/// Find the repositories among the immediate children of `root`, without recursing
+fn walk_flat(root: &Path, include_hidden: bool, mut found: impl FnMut(GitRepo)) -> Vec<PathBuf> {
+    let entries = match fs::read_dir(root) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::PermissionDenied => {
+            warn!(path = %root.display(), "skipped directory, permission denied");
+            return vec![root.to_path_buf()];
+        }
+        Err(_) => return Vec::new(),
+    };
+
+    let mut count = 0;
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+        .filter(|entry| {
+            let filename = entry.file_name();
+            let filename = filename.to_string_lossy();
+            filename != ".git"
+                && filename != "tmp"
+                && (include_hidden || !filename.starts_with('.'))
         })
-        .collect()
+        .filter(|entry| is_git_repo(&entry.path()))
+        .for_each(|entry| {
+            let path = entry.path();
+            count += 1;
+            found(GitRepo::new(path.canonicalize().unwrap_or(path)));
+        });
+
+    debug!(
+        root = %root.display(),
+        count,
+        "scanned immediate children for repositories"
+    );
+    Vec::new()
+}
+
+/// Describe the directories skipped by `scan_git_repos`, if any
+pub fn skipped_dirs_message(skipped: &[PathBuf]) -> Option<String> {
+    match skipped.len() {
+        0 => None,
+        1 => Some("1 directory skipped (permission denied)".to_string()),
+        count => Some(format!("{} directories skipped (permission denied)", count)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git_init(path: &Path) {
+        let status = Command::new("git")
+            .args(["init", "--quiet"])
+            .arg(path)
+            .status()
+            .expect("Failed to run git");
+        assert!(status.success());
+    }
+
+    #[test]
+    fn clone_is_found_where_it_landed() {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+
+        // `git clone <url> <path>` clones into the path itself
+        let expected = dir.path().join("direct");
+        git_init(&expected);
+        assert_eq!(GitRepo::locate_clone(&expected), Some(expected));
+
+        // A tool can also take the path as the parent directory of the repository
+        let expected = dir.path().join("nested");
+        git_init(&expected.join("project"));
+        assert_eq!(
+            GitRepo::locate_clone(&expected),
+            Some(expected.join("project"))
+        );
+
+        let expected = dir.path().join("empty");
+        fs::create_dir(&expected).expect("Failed to create directory");
+        assert_eq!(GitRepo::locate_clone(&expected), None);
+    }
+
+    #[test]
+    fn ssh_identity_path_is_quoted() {
+        GitRepo::set_ssh_identities(BTreeMap::from([(
+            "example.com".to_string(),
+            PathBuf::from("/keys/it's mine"),
+        )]));
+        let mut command = Command::new("git");
+        use_ssh_identity(&mut command, "git@example.com:user/repo.git");
+
+        let ssh_command = command
+            .get_envs()
+            .find(|(key, _)| *key == "GIT_SSH_COMMAND")
+            .and_then(|(_, value)| value)
+            .expect("GIT_SSH_COMMAND is not set");
+        assert_eq!(
+            ssh_command,
+            r"ssh -i '/keys/it'\''s mine' -o IdentitiesOnly=yes"
+        );
+    }
 }
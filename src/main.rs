@@ -1,19 +1,24 @@
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
 use color_eyre::Result;
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing_subscriber::EnvFilter;
 
 mod app;
 mod cache;
 mod config;
 mod event;
 mod git_repo;
+mod serve;
 mod ui;
 mod util;
 
-use app::App;
-use cache::{load_repos_with_cache, save_repos_to_cache};
+use app::{App, FilterMode, SortMode};
+use cache::{cache_root, load_repos_with_cache, save_repos_to_cache};
 use config::Settings;
-use util::strip_unc_prefix;
+use util::{format_size, parse_duration, strip_unc_pathbuf, strip_unc_prefix};
 
 /// CLI tool for managing git repositories
 #[derive(Parser, Debug)]
@@ -23,17 +28,53 @@ struct Args {
     #[command(subcommand)]
     command: Option<Command>,
 
-    /// Path to scan for git repositories (defaults to current directory or configured root)
-    path: Option<PathBuf>,
+    /// Paths to scan for git repositories (defaults to current directory or configured root)
+    paths: Vec<PathBuf>,
 
     /// Skip automatic fetching of repositories with remotes
     #[arg(long)]
     no_fetch: bool,
 
+    /// Fetch repositories with remotes at startup, even when disabled in the configuration
+    #[arg(long, conflicts_with = "no_fetch")]
+    fetch: bool,
+
+    /// Also scan hidden directories (like ~/.config) for repositories
+    #[arg(long, global = true)]
+    include_hidden: bool,
+
+    /// Only look for repositories directly inside the scanned directories, without recursing
+    #[arg(long, global = true)]
+    flat: bool,
+
+    /// Disable colors (also disabled when the NO_COLOR environment variable is set)
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Log git operations to git-repos.log in the config directory (filter with RUST_LOG)
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    /// Never write the log file, even when RUST_LOG is set
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
     /// Update local branches with fast-forward merge after fetch
     #[arg(short, long)]
     update: bool,
 
+    /// Render below the prompt instead of full screen, keeping the terminal scrollback
+    #[arg(long)]
+    inline: bool,
+
+    /// Select repositories with Enter without quitting (Alt+Enter selects and quits)
+    #[arg(long)]
+    keep_open: bool,
+
+    /// Maximum number of repositories read or fetched at the same time
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    max_concurrent: Option<u64>,
+
     /// Write selected repository path to this file on exit (for shell integration)
     #[arg(long, value_name = "PATH")]
     cwd_file: Option<PathBuf>,
@@ -46,6 +87,117 @@ enum Command {
         #[command(subcommand)]
         setting: SetCommand,
     },
+    /// Print the shell function that changes directory to the selected repository
+    Init {
+        /// Shell to generate the function for
+        shell: Shell,
+    },
+    /// Print the completion script of the command line for a shell
+    Completions {
+        /// Shell to generate the completions for
+        shell: Shell,
+    },
+    /// Print a table of the repositories and their status, or only their paths when piped
+    List {
+        /// Path to scan for git repositories (defaults to current directory or configured root)
+        path: Option<PathBuf>,
+
+        /// Only list repositories with a commit more recent than this (e.g. 12h, 7d, 2w)
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+        newer_than: Option<Duration>,
+
+        /// Only list repositories whose last commit is older than this (e.g. 12h, 7d, 2w)
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+        older_than: Option<Duration>,
+
+        /// Only print the paths, as when the output is not a terminal
+        #[arg(long)]
+        plain: bool,
+    },
+    /// Fetch all the repositories with a remote and print their new remote status
+    Fetch {
+        /// Path to scan for git repositories (defaults to current directory or configured root)
+        path: Option<PathBuf>,
+
+        /// Only report which repositories would receive updates, without fetching
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Count the commits of each repository over a period, e.g. for timesheets
+    ///
+    /// Only the repositories with commits on HEAD in the period are listed.
+    Report {
+        /// Path to scan for git repositories (defaults to current directory or configured root)
+        path: Option<PathBuf>,
+
+        /// Count the commits more recent than this (e.g. 12h, 7d, 2w)
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+        since: Duration,
+
+        /// Only count the commits older than this (e.g. 12h, 7d, 2w)
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+        until: Option<Duration>,
+
+        /// Print a JSON array instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// List the repositories cloned more than once, grouped by remote
+    ///
+    /// Remote URLs are compared without their scheme, user and ".git" suffix, so SSH and
+    /// HTTPS clones of the same repository are found.
+    Duplicates {
+        /// Paths to scan for git repositories (defaults to current directory or configured root)
+        paths: Vec<PathBuf>,
+    },
+    /// Print the branch, remote status and status of a single repository
+    Status {
+        /// Path of the repository
+        path: PathBuf,
+
+        /// Print a JSON object instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// Answer commands read from stdin with line-delimited JSON, for editor plugins
+    ///
+    /// Commands: `scan [path]`, `status <path>` and `fetch <path>`.
+    Serve,
+    /// Manage the cache of repositories of a root directory
+    Cache {
+        #[command(subcommand)]
+        action: CacheCommand,
+    },
+    /// Delete the repositories whose path relative to the scanned directory matches a glob
+    Drop {
+        /// Glob matched against the relative path of each repository (e.g. "archive/*")
+        pattern: String,
+
+        /// Path to scan for git repositories (defaults to current directory or configured root)
+        path: Option<PathBuf>,
+
+        /// Actually delete the repositories instead of listing what would be removed
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+/// Shells supported by the `init` and `completions` subcommands
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+#[derive(Subcommand, Debug)]
+enum CacheCommand {
+    /// Drop invalid and duplicate entries from the cache and sort it
+    Repair {
+        /// Root directory of the cache (defaults to the configured root or current directory)
+        path: Option<PathBuf>,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -60,6 +212,33 @@ enum SetCommand {
         /// Enable or disable auto-update (true or false)
         enabled: String,
     },
+    /// Fetch repositories with remotes at startup by default
+    Fetch {
+        /// Enable or disable the startup fetch (true or false)
+        enabled: String,
+    },
+    /// Set the default sort order of the repository list
+    Sort {
+        /// Sort mode to use
+        mode: SortMode,
+    },
+    /// Show a desktop notification when background fetches finish
+    Notify {
+        /// Enable or disable notifications (true or false)
+        enabled: String,
+    },
+    /// Show the on-disk size of each repository
+    DiskUsage {
+        /// Enable or disable the size column (true or false)
+        enabled: String,
+    },
+    /// Use an SSH private key to clone and fetch from a host
+    SshIdentity {
+        /// Host of the remote URLs (e.g. git.example.com)
+        host: String,
+        /// Path of the private key
+        identity_file: PathBuf,
+    },
 }
 
 fn handle_set_root(path: PathBuf) -> Result<()> {
@@ -74,13 +253,23 @@ fn handle_set_root(path: PathBuf) -> Result<()> {
         canonical_path.display().to_string()
     };
     println!("Root path set to: {}", display_path);
+
+    // Still saved, the repositories may be cloned there later
+    if !git_repo::contains_git_repo(&canonical_path, 3) {
+        eprintln!("Warning: no git repositories found under {}", display_path);
+    }
     Ok(())
 }
 
+fn parse_bool(value: &str) -> Result<bool> {
+    value
+        .to_lowercase()
+        .parse::<bool>()
+        .map_err(|_| color_eyre::eyre::eyre!("Invalid value '{}'. Use 'true' or 'false'", value))
+}
+
 fn handle_set_update(enabled: String) -> Result<()> {
-    let enabled_bool = enabled.to_lowercase().parse::<bool>().map_err(|_| {
-        color_eyre::eyre::eyre!("Invalid value '{}'. Use 'true' or 'false'", enabled)
-    })?;
+    let enabled_bool = parse_bool(&enabled)?;
 
     let mut settings = Settings::load()?;
     settings.set_update(enabled_bool)?;
@@ -88,6 +277,555 @@ fn handle_set_update(enabled: String) -> Result<()> {
     Ok(())
 }
 
+fn handle_set_fetch(enabled: String) -> Result<()> {
+    let enabled_bool = parse_bool(&enabled)?;
+
+    let mut settings = Settings::load()?;
+    settings.set_fetch(enabled_bool)?;
+    println!("Startup fetch set to: {}", enabled_bool);
+    Ok(())
+}
+
+fn handle_set_sort(mode: SortMode) -> Result<()> {
+    let mut settings = Settings::load()?;
+    settings.set_sort_mode(mode)?;
+    println!("Sort mode set to: {}", mode.display_name());
+    Ok(())
+}
+
+fn handle_set_notify(enabled: String) -> Result<()> {
+    let enabled_bool = parse_bool(&enabled)?;
+
+    let mut settings = Settings::load()?;
+    settings.set_notify(enabled_bool)?;
+    println!("Fetch notifications set to: {}", enabled_bool);
+    Ok(())
+}
+
+fn handle_set_disk_usage(enabled: String) -> Result<()> {
+    let enabled_bool = parse_bool(&enabled)?;
+
+    let mut settings = Settings::load()?;
+    settings.set_disk_usage(enabled_bool)?;
+    println!("Disk usage column set to: {}", enabled_bool);
+    Ok(())
+}
+
+fn handle_set_ssh_identity(host: &str, identity_file: &Path) -> Result<()> {
+    let mut settings = Settings::load()?;
+    settings.set_ssh_identity(host, identity_file)?;
+    println!(
+        "SSH identity for {} set to: {}",
+        host,
+        settings.ssh_identities[&host.to_lowercase()].display()
+    );
+    Ok(())
+}
+
+/// Warn on stderr about the directories a scan could not read, so repositories inside them
+/// are not silently missing
+fn warn_skipped_dirs(skipped: &[PathBuf]) {
+    if let Some(message) = git_repo::skipped_dirs_message(skipped) {
+        eprintln!("Warning: {}:", message);
+        for path in skipped {
+            eprintln!("  {}", strip_unc_prefix(&path.display().to_string()));
+        }
+    }
+}
+
+/// List the repositories, as a table with or without colors, or as paths when `table` is None
+fn handle_list(
+    path: Option<PathBuf>,
+    newer_than: Option<Duration>,
+    older_than: Option<Duration>,
+    include_hidden: bool,
+    table: Option<bool>,
+) -> Result<()> {
+    let settings = Settings::load()?;
+    apply_git_settings(&settings);
+    let scan_path = determine_scan_path(path, &settings)?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+    let include_hidden = include_hidden || settings.include_hidden;
+    let (mut repos, skipped) = git_repo::scan_git_repos(&scan_path, include_hidden);
+    warn_skipped_dirs(&skipped);
+    repos.sort_by_key(|repo| repo.path().to_path_buf());
+
+    repos.retain(|repo| {
+        if newer_than.is_none() && older_than.is_none() {
+            return true;
+        }
+        // Repositories without any commit never match a recency filter
+        let Some(last_commit) = git_repo::GitRepo::read_last_commit_time(repo.path()) else {
+            return false;
+        };
+        let age = now.saturating_sub(last_commit);
+        newer_than.is_none_or(|limit| age <= limit.as_secs())
+            && older_than.is_none_or(|limit| age >= limit.as_secs())
+    });
+
+    if let Some(color_enabled) = table {
+        print_repo_table(&mut repos, settings.max_concurrent(), color_enabled);
+        return Ok(());
+    }
+
+    for repo in repos {
+        let path = repo.path().display().to_string();
+        println!("{}", strip_unc_prefix(&path));
+    }
+    Ok(())
+}
+
+/// Format a number of repositories, e.g. "1 repository" or "2 repositories"
+fn repositories(count: usize) -> String {
+    match count {
+        1 => "1 repository".to_string(),
+        count => format!("{} repositories", count),
+    }
+}
+
+fn handle_cache_repair(path: Option<PathBuf>) -> Result<()> {
+    let settings = Settings::load()?;
+    let root = determine_scan_path(path, &settings)?;
+    let (repair, count) = config::repair_repo_cache(&root)?;
+
+    let root = root.display().to_string();
+    let root = strip_unc_prefix(&root);
+    if repair.is_clean() {
+        println!("Cache of {} is fine ({})", root, repositories(count));
+        return Ok(());
+    }
+    let entries = |count: usize| match count {
+        1 => "1 entry".to_string(),
+        count => format!("{} entries", count),
+    };
+    if repair.invalid > 0 {
+        println!("Dropped {} with an invalid path", entries(repair.invalid));
+    }
+    if repair.duplicates > 0 {
+        println!(
+            "Dropped {} duplicating an earlier one",
+            entries(repair.duplicates)
+        );
+    }
+    if repair.unsorted {
+        println!("Sorted the entries by path");
+    }
+    println!("Cache of {} rewritten ({})", root, repositories(count));
+    Ok(())
+}
+
+/// Print the groups of repositories sharing the same remote
+fn handle_duplicates(paths: Vec<PathBuf>, include_hidden: bool) -> Result<()> {
+    let settings = Settings::load()?;
+    apply_git_settings(&settings);
+    let scan_paths = determine_scan_paths(paths, &settings)?;
+    let include_hidden = include_hidden || settings.include_hidden;
+
+    let mut by_remote: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+    for scan_path in &scan_paths {
+        let (repos, skipped) = git_repo::scan_git_repos(scan_path, include_hidden);
+        warn_skipped_dirs(&skipped);
+        for repo in repos {
+            if let Some(remote) = repo.canonical_remote() {
+                let paths = by_remote.entry(remote).or_default();
+                let path = strip_unc_pathbuf(repo.path());
+                // Scan paths may overlap
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+    }
+
+    let duplicates: Vec<_> = by_remote
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .collect();
+    if duplicates.is_empty() {
+        println!("No repository is cloned more than once");
+        return Ok(());
+    }
+    for (remote, mut paths) in duplicates {
+        paths.sort();
+        println!("{}", remote);
+        for path in paths {
+            println!("  {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// Apply the settings that change how repositories are read, so that every command
+/// reports the same statuses
+fn apply_git_settings(settings: &Settings) {
+    git_repo::GitRepo::set_follow_detached_head(settings.follow_detached_head);
+    git_repo::GitRepo::set_update_only_behind(settings.update_only_behind);
+    git_repo::GitRepo::set_update_strategy(settings.update_strategy);
+    git_repo::GitRepo::set_skip_untracked(settings.skip_untracked);
+    git_repo::GitRepo::set_ssh_identities(settings.ssh_identities.clone());
+    git_repo::GitRepo::set_full_paths(settings.full_paths);
+}
+
+/// Fetch the repositories with a remote, or report what a fetch would change with `dry_run`
+fn handle_fetch(path: Option<PathBuf>, dry_run: bool, include_hidden: bool) -> Result<()> {
+    let settings = Settings::load()?;
+    apply_git_settings(&settings);
+    let scan_path = determine_scan_path(path, &settings)?;
+
+    let include_hidden = include_hidden || settings.include_hidden;
+    let (mut repos, skipped) = git_repo::scan_git_repos(&scan_path, include_hidden);
+    warn_skipped_dirs(&skipped);
+    repos.retain(|repo| repo.get_remote_url().is_some());
+    repos.sort_by_key(|repo| repo.path().to_path_buf());
+
+    // One line per repository, filled in parallel as fetches are network bound
+    let mut reports = vec![(false, String::new()); repos.len()];
+    let chunk_size = repos.len().div_ceil(settings.max_concurrent()).max(1);
+    std::thread::scope(|scope| {
+        for (chunk, reports) in repos.chunks(chunk_size).zip(reports.chunks_mut(chunk_size)) {
+            scope.spawn(move || {
+                for (repo, report) in chunk.iter().zip(reports) {
+                    *report = if dry_run {
+                        match git_repo::GitRepo::fetch_dry_run(repo.path()) {
+                            Ok(refs) if refs.is_empty() => (false, "up to date".to_string()),
+                            Ok(refs) => (true, format!("would update {}", refs.join(", "))),
+                            Err(err) => (false, err.to_string()),
+                        }
+                    } else {
+                        match git_repo::GitRepo::fetch(repo.path(), false) {
+                            Ok(_) => (true, git_repo::GitRepo::read_remote_status(repo.path())),
+                            Err(err) => (false, err.to_string().trim().to_string()),
+                        }
+                    };
+                }
+            });
+        }
+    });
+
+    for (repo, (_, report)) in repos.iter().zip(&reports) {
+        println!("{}: {}", repo.display_short(), report);
+    }
+    if dry_run {
+        let updated = reports.iter().filter(|(updated, _)| *updated).count();
+        println!(
+            "{} of {} would receive updates",
+            updated,
+            repositories(repos.len())
+        );
+    }
+    Ok(())
+}
+
+/// Print the number of commits made in each repository over a period
+fn handle_report(
+    path: Option<PathBuf>,
+    since: Duration,
+    until: Option<Duration>,
+    json: bool,
+    include_hidden: bool,
+) -> Result<()> {
+    let settings = Settings::load()?;
+    apply_git_settings(&settings);
+    let scan_path = determine_scan_path(path, &settings)?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let since = now.saturating_sub(since.as_secs());
+    let until = until.map(|until| now.saturating_sub(until.as_secs()));
+
+    let include_hidden = include_hidden || settings.include_hidden;
+    let (mut repos, skipped) = git_repo::scan_git_repos(&scan_path, include_hidden);
+    warn_skipped_dirs(&skipped);
+    repos.sort_by_key(|repo| repo.path().to_path_buf());
+
+    // One `git rev-list` per repository, run in parallel on large directories
+    let mut counts = vec![0; repos.len()];
+    let chunk_size = repos.len().div_ceil(settings.max_concurrent()).max(1);
+    std::thread::scope(|scope| {
+        for (chunk, counts) in repos.chunks(chunk_size).zip(counts.chunks_mut(chunk_size)) {
+            scope.spawn(move || {
+                for (repo, count) in chunk.iter().zip(counts) {
+                    *count = git_repo::GitRepo::count_commits_between(repo.path(), since, until);
+                }
+            });
+        }
+    });
+    let active: Vec<_> = repos
+        .iter()
+        .zip(counts)
+        .filter(|(_, count)| *count > 0)
+        .collect();
+
+    if json {
+        let report: Vec<_> = active
+            .iter()
+            .map(|(repo, count)| {
+                let path = repo.path().display().to_string();
+                serde_json::json!({
+                    "path": strip_unc_prefix(&path),
+                    "commits": count,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::Value::Array(report));
+        return Ok(());
+    }
+
+    if active.is_empty() {
+        println!("No commits in this period");
+        return Ok(());
+    }
+    let names: Vec<String> = active.iter().map(|(repo, _)| repo.display_name()).collect();
+    let width = names
+        .iter()
+        .map(|name| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (name, (_, count)) in names.iter().zip(&active) {
+        println!("{:width$}  {}", name, count, width = width);
+    }
+    let total: usize = active.iter().map(|(_, count)| count).sum();
+    let commits = if total == 1 { "commit" } else { "commits" };
+    println!("{} {} in {}", total, commits, repositories(active.len()));
+    Ok(())
+}
+
+/// Print the status of one repository, without scanning
+fn handle_status(path: &Path, json: bool, color_enabled: bool) -> Result<()> {
+    if !path.join(".git").exists() {
+        return Err(color_eyre::eyre::eyre!(
+            "Not a git repository: {}",
+            path.display()
+        ));
+    }
+    let settings = Settings::load()?;
+    apply_git_settings(&settings);
+
+    let mut repo = git_repo::GitRepo::new(std::path::absolute(path)?);
+    repo.set_remote_status(git_repo::GitRepo::read_remote_status(repo.path()));
+    repo.set_status(git_repo::GitRepo::read_status(repo.path()));
+    if repo.is_corrupt()
+        && let Some(corruption) = git_repo::GitRepo::read_corruption(repo.path())
+    {
+        repo.set_corruption(corruption);
+    }
+
+    let repo_path = repo.path().display().to_string();
+    if json {
+        let mut status = serde_json::json!({
+            "path": strip_unc_prefix(&repo_path),
+            "branch": repo.branch(),
+            "remote_status": repo.remote_status(),
+            "status": repo.status(),
+        });
+        if let Some(corruption) = repo.corruption() {
+            status["error"] = corruption.into();
+        }
+        println!("{}", status);
+        return Ok(());
+    }
+
+    let colored = |(text, color): (String, ratatui::style::Color)| {
+        if color_enabled && std::io::stdout().is_terminal() {
+            format!("\x1b[{}m{}\x1b[0m", ansi_color(color), text)
+        } else {
+            text
+        }
+    };
+    println!("Path:          {}", strip_unc_prefix(&repo_path));
+    println!("Branch:        {}", repo.branch());
+    println!("Remote Status: {}", colored(ui::remote_status_text(&repo)));
+    println!("Status:        {}", colored(ui::status_text(&repo)));
+    if let Some(corruption) = repo.corruption() {
+        println!("Error:         {}", corruption);
+    }
+    Ok(())
+}
+
+/// Print the repositories in aligned columns, colored like the interactive list
+fn print_repo_table(repos: &mut [git_repo::GitRepo], max_concurrent: usize, color_enabled: bool) {
+    // Read the statuses in parallel, the slowest part on large directories
+    let chunk_size = repos.len().div_ceil(max_concurrent).max(1);
+    std::thread::scope(|scope| {
+        for chunk in repos.chunks_mut(chunk_size) {
+            scope.spawn(move || {
+                for repo in chunk {
+                    repo.set_remote_status(git_repo::GitRepo::read_remote_status(repo.path()));
+                    repo.set_status(git_repo::GitRepo::read_status(repo.path()));
+                }
+            });
+        }
+    });
+
+    let header = ["Repository", "Branch", "Remote Status", "Status"]
+        .map(|title| (title.to_string(), Some(ratatui::style::Color::LightBlue)));
+    let rows: Vec<[(String, Option<ratatui::style::Color>); 4]> = repos
+        .iter()
+        .map(|repo| {
+            let (remote_text, remote_color) = ui::remote_status_text(repo);
+            let (status_text, status_color) = ui::status_text(repo);
+            [
+                (repo.display_name(), None),
+                (repo.branch().to_string(), ui::branch_color(repo)),
+                (remote_text, Some(remote_color)),
+                (status_text, Some(status_color)),
+            ]
+        })
+        .collect();
+
+    let mut widths = [0; 4];
+    for row in std::iter::once(&header).chain(&rows) {
+        for (width, (text, _)) in widths.iter_mut().zip(row) {
+            *width = (*width).max(text.chars().count());
+        }
+    }
+
+    for row in std::iter::once(&header).chain(&rows) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|((text, color), width)| {
+                let padded = format!("{:width$}", text, width = width);
+                match color {
+                    Some(color) if color_enabled => {
+                        format!("\x1b[{}m{}\x1b[0m", ansi_color(*color), padded)
+                    }
+                    _ => padded,
+                }
+            })
+            .collect();
+        println!("{}", cells.join("  ").trim_end());
+    }
+}
+
+/// Get the ANSI escape code of one of the colors used by the interactive list
+fn ansi_color(color: ratatui::style::Color) -> &'static str {
+    use ratatui::style::Color;
+    match color {
+        Color::Red => "31",
+        Color::Green => "32",
+        Color::Yellow => "33",
+        Color::Cyan => "36",
+        Color::DarkGray => "90",
+        Color::LightBlue => "94",
+        _ => "37",
+    }
+}
+
+fn handle_drop(
+    pattern: &str,
+    path: Option<PathBuf>,
+    force: bool,
+    include_hidden: bool,
+) -> Result<()> {
+    let settings = Settings::load()?;
+    apply_git_settings(&settings);
+    let scan_path = determine_scan_path(path, &settings)?;
+    // Compared with the repository paths, which are stripped too
+    let scan_root = strip_unc_pathbuf(&scan_path);
+    let pattern = glob::Pattern::new(pattern)?;
+    let options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+
+    let include_hidden = include_hidden || settings.include_hidden;
+    let (mut repos, is_root, skipped) =
+        load_repos_with_cache(&scan_path, settings.root_path.as_deref(), include_hidden);
+    warn_skipped_dirs(&skipped);
+    repos.sort_by_key(|repo| repo.path().to_path_buf());
+
+    let mut reclaimed = 0;
+    let mut dropped = 0;
+    for repo in &mut repos {
+        if repo.is_missing() {
+            continue;
+        }
+        let cleaned_path = strip_unc_pathbuf(repo.path());
+        let Ok(relative_path) = cleaned_path.strip_prefix(&scan_root) else {
+            continue;
+        };
+        let relative_path = relative_path.to_string_lossy().replace('\\', "/");
+        if !pattern.matches_with(&relative_path, options) {
+            continue;
+        }
+
+        let size = git_repo::GitRepo::read_disk_usage(repo.path(), false);
+        if force {
+            if let Err(err) = git_repo::GitRepo::delete(repo.path()) {
+                eprintln!("Failed to delete {}: {}", relative_path, err);
+                continue;
+            }
+            repo.set_missing();
+            println!("Deleted {} ({})", relative_path, format_size(size));
+        } else {
+            println!("Would delete {} ({})", relative_path, format_size(size));
+        }
+        reclaimed += size;
+        dropped += 1;
+    }
+
+    if dropped == 0 {
+        println!("No repository matches the pattern");
+    } else if force {
+        // Keep the deleted repositories in the cache so they can be cloned again
+        if is_root && let Some(root) = &settings.root_path {
+            save_repos_to_cache(&repos, root)?;
+        }
+        println!(
+            "Deleted {}, reclaimed {}",
+            repositories(dropped),
+            format_size(reclaimed)
+        );
+    } else {
+        println!(
+            "Would delete {}, reclaiming {} (use --force to delete)",
+            repositories(dropped),
+            format_size(reclaimed)
+        );
+    }
+    Ok(())
+}
+
+/// Get the shell function wrapping git-repos with `--cwd-file`
+fn shell_init(shell: Shell) -> &'static str {
+    match shell {
+        Shell::Bash | Shell::Zsh => {
+            r#"gr() {
+    local tmp dir
+    tmp="$(mktemp)"
+    command git-repos --cwd-file "$tmp" "$@"
+    dir="$(cat "$tmp")"
+    rm -f "$tmp"
+    if [ -n "$dir" ]; then
+        cd "$dir" || return
+    fi
+}
+"#
+        }
+        Shell::Fish => {
+            r#"function gr
+    set -l tmp (mktemp)
+    command git-repos --cwd-file $tmp $argv
+    if test -s $tmp
+        cd (cat $tmp)
+    end
+    rm -f $tmp
+end
+"#
+        }
+        Shell::Powershell => {
+            r#"function gr {
+    $tmp = [System.IO.Path]::GetTempFileName()
+    git-repos --cwd-file $tmp $args
+    $path = Get-Content $tmp -Raw
+    if ($path) { Set-Location $path }
+    Remove-Item $tmp -ErrorAction SilentlyContinue
+}
+"#
+        }
+    }
+}
+
 fn determine_scan_path(args_path: Option<PathBuf>, settings: &Settings) -> Result<PathBuf> {
     if let Some(path) = args_path {
         Ok(path.canonicalize()?)
@@ -98,11 +836,110 @@ fn determine_scan_path(args_path: Option<PathBuf>, settings: &Settings) -> Resul
     }
 }
 
+/// Read the filter mode to start with from the GIT_REPOS_FILTER environment variable
+fn initial_filter_mode() -> Option<FilterMode> {
+    let name = std::env::var("GIT_REPOS_FILTER").ok()?;
+    if name.is_empty() {
+        return None;
+    }
+    let filter_mode = FilterMode::from_name(&name);
+    if filter_mode.is_none() {
+        let names: Vec<&str> = FilterMode::ALL
+            .iter()
+            .map(|mode| mode.display_name())
+            .collect();
+        eprintln!(
+            "Warning: unknown GIT_REPOS_FILTER '{}', showing all repositories (use one of: {})",
+            name,
+            names.join(", ")
+        );
+    }
+    filter_mode
+}
+
+/// Remind of the repositories with commits not pushed to their upstream
+fn print_unpushed_reminder<'a>(repos: impl IntoIterator<Item = &'a git_repo::GitRepo>) {
+    let unpushed: Vec<String> = repos
+        .into_iter()
+        .filter(|repo| !repo.is_missing() && repo.is_ahead())
+        .map(|repo| repo.display_short())
+        .collect();
+
+    match unpushed.len() {
+        0 => {}
+        1 => eprintln!("1 repo has unpushed commits: {}", unpushed[0]),
+        count => eprintln!(
+            "{} repos have unpushed commits: {}",
+            count,
+            unpushed.join(", ")
+        ),
+    }
+}
+
+/// Write logs to a file, since the terminal belongs to the TUI
+///
+/// Logging is enabled by `--verbose` or by setting `RUST_LOG`, which also selects what
+/// is logged.
+fn init_logging(verbose: bool, quiet: bool) -> Result<()> {
+    if quiet {
+        return Ok(());
+    }
+    let filter = match std::env::var("RUST_LOG") {
+        Ok(filter) if !filter.is_empty() => EnvFilter::new(filter),
+        _ if verbose => EnvFilter::new("git_repos=debug"),
+        _ => return Ok(()),
+    };
+
+    let log_path = config::log_file_path()?;
+    if let Some(parent) = log_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let log_file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)?;
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::sync::Mutex::new(log_file))
+        .with_ansi(false)
+        .init();
+    Ok(())
+}
+
+/// Determine the paths to scan, without duplicates
+fn determine_scan_paths(args_paths: Vec<PathBuf>, settings: &Settings) -> Result<Vec<PathBuf>> {
+    if args_paths.is_empty() {
+        return Ok(vec![determine_scan_path(None, settings)?]);
+    }
+
+    let mut scan_paths: Vec<PathBuf> = Vec::new();
+    for path in args_paths {
+        let path = path.canonicalize()?;
+        if !scan_paths.contains(&path) {
+            scan_paths.push(path);
+        }
+    }
+    Ok(scan_paths)
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    color_eyre::install()?;
-
     let args = Args::parse();
+    git_repo::GitRepo::set_flat_scan(args.flat);
+
+    init_logging(args.verbose, args.quiet)?;
+
+    // See https://no-color.org: any non-empty value disables colors
+    let color_enabled =
+        !args.no_color && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty());
+    if color_enabled {
+        color_eyre::install()?;
+    } else {
+        color_eyre::config::HookBuilder::default()
+            .theme(color_eyre::config::Theme::new())
+            .install()?;
+    }
 
     // Handle subcommands
     if let Some(command) = args.command {
@@ -110,39 +947,135 @@ async fn main() -> Result<()> {
             Command::Set { setting } => match setting {
                 SetCommand::Root { path } => handle_set_root(path),
                 SetCommand::Update { enabled } => handle_set_update(enabled),
+                SetCommand::Fetch { enabled } => handle_set_fetch(enabled),
+                SetCommand::Sort { mode } => handle_set_sort(mode),
+                SetCommand::Notify { enabled } => handle_set_notify(enabled),
+                SetCommand::DiskUsage { enabled } => handle_set_disk_usage(enabled),
+                SetCommand::SshIdentity {
+                    host,
+                    identity_file,
+                } => handle_set_ssh_identity(&host, &identity_file),
             },
+            Command::Init { shell } => {
+                print!("{}", shell_init(shell));
+                Ok(())
+            }
+            Command::Completions { shell } => {
+                let shell = match shell {
+                    Shell::Bash => clap_complete::Shell::Bash,
+                    Shell::Zsh => clap_complete::Shell::Zsh,
+                    Shell::Fish => clap_complete::Shell::Fish,
+                    Shell::Powershell => clap_complete::Shell::PowerShell,
+                };
+                clap_complete::generate(
+                    shell,
+                    &mut Args::command(),
+                    "git-repos",
+                    &mut std::io::stdout(),
+                );
+                Ok(())
+            }
+            Command::List {
+                path,
+                newer_than,
+                older_than,
+                plain,
+            } => {
+                let table = !plain && std::io::stdout().is_terminal();
+                handle_list(
+                    path,
+                    newer_than,
+                    older_than,
+                    args.include_hidden,
+                    table.then_some(color_enabled),
+                )
+            }
+            Command::Status { path, json } => handle_status(&path, json, color_enabled),
+            Command::Serve => {
+                let settings = Settings::load()?;
+                apply_git_settings(&settings);
+                let scan_path = determine_scan_path(None, &settings)?;
+                serve::run(&scan_path, args.include_hidden || settings.include_hidden)
+            }
+            Command::Cache {
+                action: CacheCommand::Repair { path },
+            } => handle_cache_repair(path),
+            Command::Fetch { path, dry_run } => handle_fetch(path, dry_run, args.include_hidden),
+            Command::Duplicates { paths } => handle_duplicates(paths, args.include_hidden),
+            Command::Report {
+                path,
+                since,
+                until,
+                json,
+            } => handle_report(path, since, until, json, args.include_hidden),
+            Command::Drop {
+                pattern,
+                path,
+                force,
+            } => handle_drop(&pattern, path, force, args.include_hidden),
         };
     }
 
-    // Load settings
-    let settings = Settings::load()?;
+    // Load settings, the command line taking precedence
+    let mut settings = Settings::load()?;
+    if let Some(max_concurrent) = args.max_concurrent {
+        settings.max_concurrent = Some(max_concurrent as usize);
+    }
+    settings.inline |= args.inline;
+    settings.keep_open |= args.keep_open;
+    apply_git_settings(&settings);
 
-    // Determine scan path and load repositories
-    let scan_path = determine_scan_path(args.path, &settings)?;
-    let (repos, is_root) = load_repos_with_cache(&scan_path, settings.root_path.as_deref());
+    // Determine scan paths, the repositories are loaded while the TUI runs
+    let scan_paths = determine_scan_paths(args.paths, &settings)?;
+    let include_hidden = args.include_hidden || settings.include_hidden;
+    let cache_root = cache_root(&scan_paths, settings.root_path.as_deref()).map(Path::to_path_buf);
+    let is_root = cache_root.is_some();
     let update_enabled = args.update || settings.update_by_default;
+    let fetch_enabled = args.fetch || (!args.no_fetch && settings.fetch_by_default);
 
     // Run the TUI
     let root_for_app = is_root.then(|| settings.root_path.clone()).flatten();
     let mut app = App::new_with_root(
-        repos,
-        &scan_path,
-        !args.no_fetch,
+        Vec::new(),
+        &scan_paths,
+        fetch_enabled,
         update_enabled,
         root_for_app,
         args.cwd_file.is_some(),
+        settings.clone(),
     );
+    app.set_color_enabled(color_enabled);
+    if let Some(filter_mode) = initial_filter_mode() {
+        app.set_filter_mode(filter_mode);
+    }
+    app.scan(scan_paths, cache_root, include_hidden);
     app.run().await?;
 
-    // Save cache if we were scanning root directory
-    if is_root && let Some(root_path) = &settings.root_path {
+    // Save cache if we were scanning root directory, unless quitting before the scan was
+    // over: the repositories not found yet would be dropped from it
+    if is_root
+        && !app.is_scanning()
+        && let Some(root_path) = &settings.root_path
+    {
         save_repos_to_cache(app.repos(), root_path)?;
     }
 
+    if settings.remind_unpushed {
+        print_unpushed_reminder(app.repos());
+    }
+
     // If a repository was selected and --cwd-file is set, write to the file
     if let (Some(repo_path), Some(cwd_file)) = (app.selected_repo, args.cwd_file) {
         let cleaned = strip_unc_prefix(&repo_path);
-        std::fs::write(cwd_file, cleaned)?;
+        // The TUI is gone already: print the path rather than failing without it
+        if let Err(err) = std::fs::write(&cwd_file, cleaned) {
+            eprintln!(
+                "Could not write the selected repository to {}: {}",
+                cwd_file.display(),
+                err
+            );
+            println!("{}", cleaned);
+        }
     }
 
     Ok(())
@@ -0,0 +1,142 @@
+use crate::git_repo::{GitRepo, find_git_repos};
+use crate::util::strip_unc_prefix;
+use color_eyre::Result;
+use serde::Serialize;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// A repository found by the `scan` command
+#[derive(Serialize)]
+struct RepoInfo {
+    path: String,
+    branch: String,
+    remote_url: Option<String>,
+}
+
+/// Response to a command, written as one line of JSON
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Response {
+    Scan {
+        repos: Vec<RepoInfo>,
+    },
+    Status {
+        path: String,
+        branch: String,
+        remote_status: String,
+        status: String,
+    },
+    Fetch {
+        path: String,
+        remote_status: String,
+    },
+    Error {
+        error: String,
+    },
+}
+
+/// Answer commands read from stdin, one per line, with one line of JSON each on stdout
+///
+/// Supported commands are `scan [path]`, `status <path>` and `fetch <path>`.
+pub fn run(default_path: &Path, include_hidden: bool) -> Result<()> {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout().lock();
+
+    for line in stdin.lock().lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let (command, argument) = match line.split_once(char::is_whitespace) {
+            Some((command, argument)) => (command, Some(argument.trim())),
+            None => (line, None),
+        };
+        let response = match (command, argument) {
+            ("scan", path) => scan(path.map_or(default_path, Path::new), include_hidden),
+            ("status", Some(path)) => status(Path::new(path)),
+            ("fetch", Some(path)) => fetch(Path::new(path)),
+            ("status" | "fetch", None) => Response::Error {
+                error: format!("Missing path: {} <path>", command),
+            },
+            _ => Response::Error {
+                error: format!("Unknown command '{}'. Use scan, status or fetch", command),
+            },
+        };
+
+        serde_json::to_writer(&mut stdout, &response)?;
+        writeln!(stdout)?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
+
+/// Convert a path to the string sent to clients
+fn path_string(path: &Path) -> String {
+    strip_unc_prefix(&path.display().to_string()).to_string()
+}
+
+/// Check that a path given by a client is a repository
+fn repo_path(path: &Path) -> Result<PathBuf, Response> {
+    if path.join(".git").exists() {
+        Ok(path.to_path_buf())
+    } else {
+        Err(Response::Error {
+            error: format!("Not a git repository: {}", path.display()),
+        })
+    }
+}
+
+fn scan(path: &Path, include_hidden: bool) -> Response {
+    if !path.is_dir() {
+        return Response::Error {
+            error: format!("Not a directory: {}", path.display()),
+        };
+    }
+
+    let mut repos = find_git_repos(path, include_hidden);
+    repos.sort_by_key(|repo| repo.path().to_path_buf());
+    Response::Scan {
+        repos: repos
+            .iter()
+            .map(|repo| RepoInfo {
+                path: path_string(repo.path()),
+                branch: repo.branch().to_string(),
+                remote_url: repo.get_remote_url(),
+            })
+            .collect(),
+    }
+}
+
+fn status(path: &Path) -> Response {
+    let path = match repo_path(path) {
+        Ok(path) => path,
+        Err(response) => return response,
+    };
+
+    let repo = GitRepo::new(path);
+    Response::Status {
+        path: path_string(repo.path()),
+        branch: repo.branch().to_string(),
+        remote_status: GitRepo::read_remote_status(repo.path()),
+        status: GitRepo::read_status(repo.path()),
+    }
+}
+
+fn fetch(path: &Path) -> Response {
+    let path = match repo_path(path) {
+        Ok(path) => path,
+        Err(response) => return response,
+    };
+
+    if let Err(err) = GitRepo::fetch(&path, false) {
+        return Response::Error {
+            error: err.to_string(),
+        };
+    }
+    Response::Fetch {
+        path: path_string(&path),
+        remote_status: GitRepo::read_remote_status(&path),
+    }
+}
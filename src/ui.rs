@@ -1,96 +1,343 @@
-use crate::app::App;
+use crate::app::{App, FilterMode, NOT_LOADED, TableRow};
+use crate::config::TruncateStyle;
+use crate::git_repo::{CORRUPT, GitRepo};
+use crate::util::format_size;
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Layout, Rect},
     style::{Color, Modifier, Style, Stylize},
     text::{Line, Span},
-    widgets::{Block, Borders, Cell, Row, StatefulWidget, Table, Widget},
+    widgets::{
+        Block, Borders, Cell, Clear, List, ListItem, ListState, Row, StatefulWidget, Table,
+        TableState, Widget,
+    },
 };
+use unicode_width::UnicodeWidthChar;
 
 /// Widget implementation for App
 impl Widget for &mut App {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let chunks = Layout::vertical([
             Constraint::Min(1),    // Main table
+            Constraint::Length(6), // Detail pane
             Constraint::Length(1), // Status bar
         ])
         .split(area);
 
         self.render_table(chunks[0], buf);
-        self.render_status_bar(chunks[1], buf);
+        self.render_details(chunks[1], buf);
+        self.render_status_bar(chunks[2], buf);
+
+        if self.is_incoming_mode() {
+            self.render_incoming_popup(chunks[0], buf);
+        }
+        if self.is_error_log_mode() {
+            self.render_error_log_popup(chunks[0], buf);
+        }
+        if self.is_full_status_mode() {
+            self.render_full_status_popup(chunks[0], buf);
+        }
+        if self.is_upstream_picker_mode() {
+            self.render_upstream_picker(chunks[0], buf);
+        }
+        if self.is_prune_picker_mode() {
+            self.render_prune_picker(chunks[0], buf);
+        }
+        if self.is_worktree_picker_mode() {
+            self.render_worktree_picker(chunks[0], buf);
+        }
+        if self.is_discard_mode() {
+            self.render_discard_popup(chunks[0], buf);
+        }
+        if self.is_update_all_mode() {
+            self.render_update_all_popup(chunks[0], buf);
+        }
+
+        if !self.color_enabled() {
+            remove_colors(area, buf);
+        }
+    }
+}
+
+/// Get the text and color of the remote status of a repository
+pub fn remote_status_text(repo: &GitRepo) -> (String, Color) {
+    let remote_status = repo.remote_status();
+    match remote_status {
+        "loading..." => (format!("⟳ {}", remote_status), Color::DarkGray),
+        NOT_LOADED => (remote_status.to_string(), Color::DarkGray),
+        CORRUPT => (remote_status.to_string(), Color::Magenta),
+        _ if repo.is_diverged() => (format!("{} diverged", remote_status), Color::Red),
+        "local-only" | "upstream-gone" => (remote_status.to_string(), Color::Red),
+        "up-to-date" => (remote_status.to_string(), Color::Green),
+        "no-tracking" | "no-commits" => (remote_status.to_string(), Color::Yellow),
+        _ if repo.ahead_count().is_some() => (remote_status.to_string(), Color::Cyan),
+        _ => (remote_status.to_string(), Color::White),
+    }
+}
+
+/// Get the text and color of the working tree status of a repository
+pub fn status_text(repo: &GitRepo) -> (String, Color) {
+    let status = repo.status();
+    match status {
+        "loading..." => (format!("⟳ {}", status), Color::DarkGray),
+        "unknown" | NOT_LOADED => (status.to_string(), Color::DarkGray),
+        CORRUPT => (status.to_string(), Color::Magenta),
+        _ if repo.operation().is_some() => (status.to_string(), Color::Red),
+        _ if repo.is_clean() => (status.to_string(), Color::Green),
+        _ => (status.to_string(), Color::Yellow),
+    }
+}
+
+/// Get a single glyph summing up both statuses of a repository, and its color
+///
+/// The most pressing state wins: ⚠ for what needs a manual fix (an operation in progress,
+/// a diverged branch, a missing or gone upstream, a corrupt repository), then ● for local
+/// changes, ↓ behind, ↑ ahead, and ✓ when clean and in sync.
+pub fn status_glyph(repo: &GitRepo) -> (&'static str, Color) {
+    let remote_status = repo.remote_status();
+    let status = repo.status();
+    if [remote_status, status].contains(&"loading...") {
+        return ("⟳", Color::DarkGray);
+    }
+    if matches!(status, "unknown" | NOT_LOADED) || remote_status == NOT_LOADED {
+        return ("?", Color::DarkGray);
+    }
+    let needs_fix = repo.operation().is_some()
+        || repo.is_diverged()
+        || matches!(
+            remote_status,
+            "no-tracking" | "upstream-gone" | "error" | CORRUPT
+        )
+        || status == CORRUPT;
+    if needs_fix {
+        ("⚠", Color::Red)
+    } else if !repo.is_clean() {
+        ("●", Color::Yellow)
+    } else if repo.is_behind() {
+        ("↓", Color::Cyan)
+    } else if repo.is_ahead() {
+        ("↑", Color::Cyan)
+    } else {
+        ("✓", Color::Green)
+    }
+}
+
+/// Get the color of the branch of a repository, if highlighted
+pub fn branch_color(repo: &GitRepo) -> Option<Color> {
+    // Without origin/HEAD the default branch is unknown, press 'h' to detect it
+    repo.is_on_default_branch().then_some(Color::Green)
+}
+
+/// Shorten a text to fit in `width` columns, replacing the part cut out by "…"
+fn truncate(text: &str, width: usize, style: TruncateStyle) -> String {
+    let char_width = |c: char| c.width().unwrap_or(0);
+    if text.chars().map(char_width).sum::<usize>() <= width {
+        return text.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+
+    // Keep characters from the start, or the end, as long as they fit in the budget
+    let keep = |chars: &mut dyn Iterator<Item = char>, budget: usize| {
+        let mut used = 0;
+        chars
+            .take_while(|&c| {
+                used += char_width(c);
+                used <= budget
+            })
+            .collect::<Vec<char>>()
+    };
+    let start = |budget: usize| {
+        keep(&mut text.chars(), budget)
+            .into_iter()
+            .collect::<String>()
+    };
+    let end = |budget: usize| {
+        keep(&mut text.chars().rev(), budget)
+            .into_iter()
+            .rev()
+            .collect::<String>()
+    };
+
+    // One column goes to the ellipsis
+    let budget = width - 1;
+    match style {
+        TruncateStyle::Start => format!("…{}", end(budget)),
+        TruncateStyle::Middle => format!("{}…{}", start(budget.div_ceil(2)), end(budget / 2)),
+        TruncateStyle::End => format!("{}…", start(budget)),
+    }
+}
+
+/// Split text into spans, highlighting the first case-insensitive match of a lowercase query
+fn highlight_match<'a>(text: &'a str, query: Option<&str>, style: Style) -> Line<'a> {
+    let highlight = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    let lower = text.to_lowercase();
+    let Some(start) = query.and_then(|query| lower.find(query)) else {
+        return Line::from(Span::styled(text, style));
+    };
+    // Lowercasing can change byte lengths outside of ASCII: highlight all of it then
+    let end = start + query.map_or(0, str::len);
+    if lower.len() != text.len() || !text.is_char_boundary(start) || !text.is_char_boundary(end) {
+        return Line::from(Span::styled(text, highlight));
+    }
+
+    Line::from(vec![
+        Span::styled(&text[..start], style),
+        Span::styled(&text[start..end], highlight),
+        Span::styled(&text[end..], style),
+    ])
+}
+
+/// Reset the colors of every cell, keeping text modifiers
+///
+/// Highlighted cells are shown reversed, so the selection stays visible without colors.
+fn remove_colors(area: Rect, buf: &mut Buffer) {
+    for position in area.positions() {
+        let cell = &mut buf[position];
+        if cell.bg != Color::Reset {
+            cell.modifier.insert(Modifier::REVERSED);
+        }
+        cell.fg = Color::Reset;
+        cell.bg = Color::Reset;
     }
 }
 
 impl App {
     /// Render the repository table
     fn render_table(&mut self, area: Rect, buf: &mut Buffer) {
-        let header = Row::new(vec!["Repository", "Branch", "Remote Status", "Status"]).style(
+        let show_disk_usage = self.show_disk_usage();
+        let compact_status = self.is_compact_status();
+
+        let status_headers: &[&str] = if compact_status {
+            &["Status"]
+        } else {
+            &["Remote Status", "Status"]
+        };
+        let mut header_cells: Vec<String> = ["Repository", "Branch"]
+            .iter()
+            .chain(status_headers)
+            .map(|title| title.to_string())
+            .collect();
+        if show_disk_usage {
+            header_cells.push("Size".to_string());
+        }
+        let arrow = if self.sort_descending { " ▼" } else { " ▲" };
+        // Both statuses are sorted under the glyph column
+        let sorted_column = if compact_status {
+            self.sort_mode.column().min(2)
+        } else {
+            self.sort_mode.column()
+        };
+        header_cells[sorted_column].push_str(arrow);
+        let header = Row::new(header_cells).style(
             Style::default()
                 .fg(Color::LightBlue)
                 .add_modifier(Modifier::BOLD),
         );
 
-        let filtered_indices = self.filtered_repos();
-        let selected_idx = self.table_state.selected();
+        let widths = if compact_status && show_disk_usage {
+            vec![
+                Constraint::Percentage(45),
+                Constraint::Percentage(30),
+                Constraint::Length(8),
+                Constraint::Percentage(15),
+            ]
+        } else if compact_status {
+            vec![
+                Constraint::Percentage(55),
+                Constraint::Percentage(35),
+                Constraint::Length(8),
+            ]
+        } else if show_disk_usage {
+            vec![
+                Constraint::Percentage(30),
+                Constraint::Percentage(20),
+                Constraint::Percentage(20),
+                Constraint::Percentage(15),
+                Constraint::Percentage(15),
+            ]
+        } else {
+            vec![
+                Constraint::Percentage(30),
+                Constraint::Percentage(25),
+                Constraint::Percentage(25),
+                Constraint::Percentage(20),
+            ]
+        };
 
-        let rows: Vec<Row> = self
-            .repos
-            .iter()
-            .enumerate()
-            .filter(|(idx, _)| filtered_indices.contains(idx))
-            .map(|(idx, repo)| {
-                // If repo is missing, render everything in gray (or white if selected)
-                if repo.is_missing() {
-                    let is_selected = selected_idx == Some(idx);
-                    let color = if is_selected {
-                        Color::White
-                    } else {
-                        Color::DarkGray
-                    };
+        // Width left for the names once the borders and the highlight symbol are drawn
+        let name_width = Layout::horizontal(widths.clone())
+            .spacing(1)
+            .split(Rect::new(0, 0, area.width.saturating_sub(2 + 2), 1))[0]
+            .width as usize;
+        let truncate_style = self.truncate_style();
 
-                    return Row::new(vec![
-                        Cell::from(repo.display_short()).fg(color),
-                        Cell::from("").fg(color),
-                        Cell::from("missing").fg(color),
-                        Cell::from("").fg(color),
-                    ]);
-                }
+        let table_rows = self.table_rows();
+        let selected_idx = self.table_state.selected();
+        // Without colors, a background would be rendered as a selection
+        let striped_rows = self.striped_rows() && self.color_enabled();
+        let mut repo_position = 0;
+        let branch_query = self.branch_query();
 
-                let remote_status = repo.remote_status();
-                let (remote_text, remote_color) = match remote_status {
-                    "loading..." => (format!("⟳ {}", remote_status), Color::DarkGray),
-                    "local-only" => (remote_status.to_string(), Color::Red),
-                    "up-to-date" => (remote_status.to_string(), Color::Green),
-                    "no-tracking" => (remote_status.to_string(), Color::Yellow),
-                    _ if remote_status.contains('↑') || remote_status.contains('↓') => {
-                        (remote_status.to_string(), Color::Cyan)
+        let rows: Vec<Row> = table_rows
+            .iter()
+            .map(|row| {
+                let idx = match row {
+                    TableRow::Group {
+                        host,
+                        count,
+                        collapsed,
+                    } => {
+                        let marker = if *collapsed { "▶" } else { "▼" };
+                        return Row::new(vec![Cell::from(format!(
+                            "{} {} ({})",
+                            marker, host, count
+                        ))])
+                        .style(
+                            Style::default()
+                                .fg(Color::LightBlue)
+                                .add_modifier(Modifier::BOLD),
+                        );
                     }
-                    _ => (remote_status.to_string(), Color::White),
+                    TableRow::Repo(idx) => *idx,
                 };
+                let repo = &self.repos[idx];
 
-                let status = repo.status();
-                let (status_text, status_color) = match status {
-                    "loading..." => (format!("⟳ {}", status), Color::DarkGray),
-                    "clean" => (status.to_string(), Color::Green),
-                    "unknown" => (status.to_string(), Color::DarkGray),
-                    _ => (status.to_string(), Color::Yellow),
-                };
-
-                Row::new(vec![
-                    Cell::from(repo.display_short()),
-                    Cell::from(repo.branch()),
-                    Cell::from(remote_text).fg(remote_color),
-                    Cell::from(status_text).fg(status_color),
-                ])
+                // Darker than the DarkGray used for the selection and missing repositories
+                let stripe = striped_rows && repo_position % 2 == 1;
+                repo_position += 1;
+                let row = self.repo_row(
+                    repo,
+                    selected_idx == Some(idx),
+                    branch_query.as_deref(),
+                    name_width,
+                    truncate_style,
+                );
+                if stripe {
+                    row.style(Style::default().bg(Color::Indexed(235)))
+                } else {
+                    row
+                }
             })
             .collect();
 
-        let widths = [
-            Constraint::Percentage(30),
-            Constraint::Percentage(25),
-            Constraint::Percentage(25),
-            Constraint::Percentage(20),
-        ];
+        let mut filter_spans = Vec::new();
+        for mode in FilterMode::ALL {
+            if !filter_spans.is_empty() {
+                filter_spans.push(Span::raw(" - "));
+            }
+            let style = if self.filter_mode == mode {
+                Style::default()
+                    .fg(Color::LightBlue)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            filter_spans.push(Span::styled(mode.display_name(), style));
+        }
 
         let table = Table::new(rows, widths)
             .header(header)
@@ -101,54 +348,11 @@ impl App {
                             .bold()
                             .light_blue(),
                     )
-                    .title_bottom(
-                        Line::from(vec![
-                            if self.filter_mode == crate::app::FilterMode::All {
-                                Span::styled(
-                                    "All",
-                                    Style::default()
-                                        .fg(Color::LightBlue)
-                                        .add_modifier(Modifier::BOLD),
-                                )
-                            } else {
-                                Span::styled("All", Style::default().fg(Color::White))
-                            },
-                            Span::raw(" - "),
-                            if self.filter_mode == crate::app::FilterMode::NoUpstream {
-                                Span::styled(
-                                    "No Upstream",
-                                    Style::default()
-                                        .fg(Color::LightBlue)
-                                        .add_modifier(Modifier::BOLD),
-                                )
-                            } else {
-                                Span::styled("No Upstream", Style::default().fg(Color::White))
-                            },
-                            Span::raw(" - "),
-                            if self.filter_mode == crate::app::FilterMode::Behind {
-                                Span::styled(
-                                    "Behind",
-                                    Style::default()
-                                        .fg(Color::LightBlue)
-                                        .add_modifier(Modifier::BOLD),
-                                )
-                            } else {
-                                Span::styled("Behind", Style::default().fg(Color::White))
-                            },
-                            Span::raw(" - "),
-                            if self.filter_mode == crate::app::FilterMode::Modified {
-                                Span::styled(
-                                    "Modified",
-                                    Style::default()
-                                        .fg(Color::LightBlue)
-                                        .add_modifier(Modifier::BOLD),
-                                )
-                            } else {
-                                Span::styled("Modified", Style::default().fg(Color::White))
-                            },
-                        ])
-                        .right_aligned(),
+                    .title(
+                        Line::from(format!("Sort: {}", self.sort_mode.display_name()))
+                            .right_aligned(),
                     )
+                    .title_bottom(Line::from(filter_spans).right_aligned())
                     .borders(Borders::ALL)
                     .border_type(ratatui::widgets::BorderType::Rounded)
                     .border_style(Style::default().fg(Color::White))
@@ -161,11 +365,654 @@ impl App {
             )
             .highlight_symbol("> ");
 
-        StatefulWidget::render(table, area, buf, &mut self.table_state);
+        // The table state tracks repository indices, the widget needs the row in display order
+        let mut row_state = TableState::default()
+            .with_offset(self.table_state.offset())
+            .with_selected(self.selected_row());
+        StatefulWidget::render(table, area, buf, &mut row_state);
+        *self.table_state.offset_mut() = row_state.offset();
+
+        // Nothing found yet is not nothing found
+        if self.repos().next().is_none() && !self.is_scanning() {
+            self.render_empty_message(area, buf);
+        }
+    }
+
+    /// Build the table row of a repository, highlighting the part of the branch matching
+    /// the search
+    fn repo_row<'a>(
+        &self,
+        repo: &'a GitRepo,
+        is_selected: bool,
+        branch_query: Option<&str>,
+        name_width: usize,
+        truncate_style: TruncateStyle,
+    ) -> Row<'a> {
+        let show_disk_usage = self.show_disk_usage();
+        let compact_status = self.is_compact_status();
+        let name = if self.is_pinned(repo) {
+            let name = truncate(
+                &repo.display_name(),
+                name_width.saturating_sub(2),
+                truncate_style,
+            );
+            format!("★ {}", name)
+        } else {
+            truncate(&repo.display_name(), name_width, truncate_style)
+        };
+
+        // If repo is missing, render everything in gray (or white if selected)
+        if repo.is_missing() {
+            let color = if is_selected {
+                Color::White
+            } else {
+                Color::DarkGray
+            };
+
+            // Without a cached remote URL, the repository cannot be cloned back
+            let missing_text = match (compact_status, repo.get_remote_url().is_some()) {
+                (true, _) => "✗",
+                (false, true) => "missing",
+                (false, false) => "missing (no remote)",
+            };
+            let missing_cell = if repo.get_remote_url().is_some() || is_selected {
+                Cell::from(missing_text).fg(color)
+            } else {
+                Cell::from(missing_text).fg(Color::Red)
+            };
+            let mut cells = vec![
+                Cell::from(name).fg(color),
+                Cell::from("").fg(color),
+                missing_cell,
+            ];
+            if !compact_status {
+                cells.push(Cell::from("").fg(color));
+            }
+            if show_disk_usage {
+                cells.push(Cell::from("").fg(color));
+            }
+            return Row::new(cells);
+        }
+
+        let branch_style =
+            branch_color(repo).map_or(Style::default(), |color| Style::default().fg(color));
+        let branch_cell = Cell::from(highlight_match(repo.branch(), branch_query, branch_style));
+
+        let mut cells = vec![Cell::from(name), branch_cell];
+        if compact_status {
+            let (glyph, glyph_color) = status_glyph(repo);
+            cells.push(Cell::from(glyph).fg(glyph_color));
+        } else {
+            let (remote_text, remote_color) = remote_status_text(repo);
+            let (status_text, status_color) = status_text(repo);
+            cells.push(Cell::from(remote_text).fg(remote_color));
+            cells.push(Cell::from(status_text).fg(status_color));
+        }
+        if show_disk_usage {
+            let size_cell = match repo.size() {
+                Some(size) => Cell::from(format_size(size)),
+                None => Cell::from("⟳").fg(Color::DarkGray),
+            };
+            cells.push(size_cell);
+        }
+        Row::new(cells)
+    }
+
+    /// Render an explanation in the middle of the table when the scan found nothing
+    fn render_empty_message(&self, area: Rect, buf: &mut Buffer) {
+        let [message_area] = Layout::vertical([Constraint::Length(2)])
+            .flex(ratatui::layout::Flex::Center)
+            .areas(area);
+
+        let message = vec![
+            Line::from(format!(
+                "No git repositories found under {}",
+                self.scan_path
+            ))
+            .centered(),
+            Line::from(Span::styled(
+                "Scan another directory with `git-repos <path>` or set a default with `git-repos set root <path>`",
+                Style::default().fg(Color::DarkGray),
+            ))
+            .centered(),
+        ];
+        ratatui::widgets::Paragraph::new(message).render(message_area, buf);
+    }
+
+    /// Render the detail pane for the selected repository
+    fn render_details(&self, area: Rect, buf: &mut Buffer) {
+        let label = Style::default().fg(Color::LightBlue);
+        let lines = match self.current_repo() {
+            Some(repo) if repo.is_missing() => vec![
+                Line::from(vec![
+                    Span::styled("Path: ", label),
+                    Span::raw(repo.path().display().to_string()),
+                ]),
+                Line::from(vec![
+                    Span::styled("Remote: ", label),
+                    Span::raw(repo.get_remote_url().unwrap_or_else(|| "none".to_string())),
+                ]),
+            ],
+            // The other details can't be read, show why instead
+            Some(repo) if repo.is_corrupt() => vec![
+                Line::from(vec![
+                    Span::styled("Path: ", label),
+                    Span::raw(repo.path().display().to_string()),
+                ]),
+                Line::from(vec![
+                    Span::styled("Error: ", label),
+                    match repo.corruption() {
+                        Some(error) => {
+                            Span::styled(error.to_string(), Style::default().fg(Color::Magenta))
+                        }
+                        None => Span::styled("⟳ loading...", Style::default().fg(Color::DarkGray)),
+                    },
+                ]),
+            ],
+            Some(repo) => vec![
+                Line::from(vec![
+                    Span::styled("Path: ", label),
+                    Span::raw(repo.path().display().to_string()),
+                ]),
+                Line::from(vec![
+                    Span::styled("Upstream: ", label),
+                    match repo.upstream() {
+                        Some(upstream) => Span::raw(upstream.to_string()),
+                        None => Span::styled("⟳ loading...", Style::default().fg(Color::DarkGray)),
+                    },
+                ]),
+                Line::from(vec![
+                    Span::styled("Latest tag: ", label),
+                    match repo.latest_tag() {
+                        Some(latest_tag) => Span::raw(latest_tag.to_string()),
+                        None => Span::styled("⟳ loading...", Style::default().fg(Color::DarkGray)),
+                    },
+                ]),
+                Line::from(vec![
+                    Span::styled("Identity: ", label),
+                    match repo.identity() {
+                        // Commits would fail, or use a guessed identity
+                        Some("none") => Span::styled("none", Style::default().fg(Color::Red)),
+                        Some(identity) => Span::raw(identity.to_string()),
+                        None => Span::styled("⟳ loading...", Style::default().fg(Color::DarkGray)),
+                    },
+                ]),
+            ],
+            None => Vec::new(),
+        };
+
+        ratatui::widgets::Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(ratatui::widgets::BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::White)),
+            )
+            .render(area, buf);
+    }
+
+    /// Render the incoming commits preview popup, centered over the table
+    fn render_incoming_popup(&self, area: Rect, buf: &mut Buffer) {
+        let Some((repo_name, commits)) = self.incoming_preview() else {
+            return;
+        };
+
+        let popup_area = popup_area(area, commits.len());
+
+        let items: Vec<ListItem> = commits
+            .iter()
+            .map(|commit| match commit.split_once(' ') {
+                Some((sha, summary)) => ListItem::new(Line::from(vec![
+                    Span::styled(sha, Style::default().fg(Color::Yellow)),
+                    Span::raw(" "),
+                    Span::raw(summary),
+                ])),
+                None => ListItem::new(commit.as_str()),
+            })
+            .collect();
+
+        let list = List::new(items).block(
+            Block::default()
+                .title(
+                    format!("Incoming commits - {} ({})", repo_name, commits.len())
+                        .bold()
+                        .light_blue(),
+                )
+                .borders(Borders::ALL)
+                .border_type(ratatui::widgets::BorderType::Rounded)
+                .border_style(Style::default().fg(Color::White)),
+        );
+
+        Clear.render(popup_area, buf);
+        Widget::render(list, popup_area, buf);
+    }
+
+    /// Render the full `git status` of a repository, centered over the table
+    fn render_full_status_popup(&mut self, area: Rect, buf: &mut Buffer) {
+        let Some((repo_name, lines, scroll)) = self.full_status() else {
+            return;
+        };
+
+        let popup_area = popup_area(area, lines.len());
+
+        // Stop scrolling once the last line is in view
+        let visible_rows = popup_area.height.saturating_sub(2) as usize;
+        *scroll = (*scroll).min(lines.len().saturating_sub(visible_rows));
+
+        let text: Vec<Line> = lines.iter().map(|line| Line::from(line.as_str())).collect();
+        let paragraph = ratatui::widgets::Paragraph::new(text)
+            .scroll((*scroll as u16, 0))
+            .block(
+                Block::default()
+                    .title(format!("git status - {}", repo_name).bold().light_blue())
+                    .borders(Borders::ALL)
+                    .border_type(ratatui::widgets::BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::White)),
+            );
+
+        Clear.render(popup_area, buf);
+        paragraph.render(popup_area, buf);
+    }
+
+    /// Render the failures of background operations, centered over the table
+    fn render_error_log_popup(&self, area: Rect, buf: &mut Buffer) {
+        let errors = self.error_log();
+        let popup_area = popup_area(area, errors.len());
+
+        let items: Vec<ListItem> = errors
+            .iter()
+            .map(|error| match error.split_once(": ") {
+                Some((name, message)) => ListItem::new(Line::from(vec![
+                    Span::styled(name, Style::default().fg(Color::Yellow)),
+                    Span::raw(": "),
+                    Span::styled(message, Style::default().fg(Color::Red)),
+                ])),
+                None => ListItem::new(error.as_str()),
+            })
+            .collect();
+
+        // Keep the most recent failures in view when they don't all fit
+        let visible_rows = popup_area.height.saturating_sub(2) as usize;
+        let mut list_state = ListState::default();
+        if let Some(last) = items.len().checked_sub(1) {
+            *list_state.offset_mut() = (last + 1).saturating_sub(visible_rows);
+        }
+
+        let list = List::new(items).block(
+            Block::default()
+                .title(
+                    format!("Failed operations ({})", errors.len())
+                        .bold()
+                        .light_blue(),
+                )
+                .borders(Borders::ALL)
+                .border_type(ratatui::widgets::BorderType::Rounded)
+                .border_style(Style::default().fg(Color::White)),
+        );
+
+        Clear.render(popup_area, buf);
+        StatefulWidget::render(list, popup_area, buf, &mut list_state);
+    }
+
+    /// Render the files whose changes would be discarded, centered over the table
+    fn render_discard_popup(&self, area: Rect, buf: &mut Buffer) {
+        let Some((repo_name, files)) = self.discard_confirmation() else {
+            return;
+        };
+
+        let popup_area = popup_area(area, files.len());
+
+        let items: Vec<ListItem> = files
+            .iter()
+            .map(|file| {
+                // Porcelain lines are a two-letter status, a space and the path
+                let (status, path) = file.split_at(file.len().min(2));
+                ListItem::new(Line::from(vec![
+                    Span::styled(status, Style::default().fg(Color::Red)),
+                    Span::raw(path),
+                ]))
+            })
+            .collect();
+
+        let list = List::new(items).block(
+            Block::default()
+                .title(
+                    format!("Discard changes - {} ({} files)", repo_name, files.len())
+                        .bold()
+                        .red(),
+                )
+                .borders(Borders::ALL)
+                .border_type(ratatui::widgets::BorderType::Rounded)
+                .border_style(Style::default().fg(Color::Red)),
+        );
+
+        Clear.render(popup_area, buf);
+        Widget::render(list, popup_area, buf);
+    }
+
+    /// Render what updating all displayed repositories will do, centered over the table
+    fn render_update_all_popup(&self, area: Rect, buf: &mut Buffer) {
+        let Some((count, behind, local_only, up_to_date)) = self.update_all_summary() else {
+            return;
+        };
+
+        let plural = |count: usize| {
+            if count == 1 {
+                "repository"
+            } else {
+                "repositories"
+            }
+        };
+        let mut lines = vec![
+            Line::from(format!(
+                "{} {} will be fetched, and fast-forwarded when possible",
+                count,
+                plural(count)
+            )),
+            Line::from(vec![
+                Span::styled(format!("{}", behind), Style::default().fg(Color::Cyan)),
+                Span::raw(" behind their upstream"),
+            ]),
+        ];
+        if local_only > 0 {
+            lines.push(Line::from(vec![
+                Span::styled(format!("{}", local_only), Style::default().fg(Color::Red)),
+                Span::raw(format!(" local-only {} skipped", plural(local_only))),
+            ]));
+        }
+        if up_to_date > 0 {
+            lines.push(Line::from(vec![
+                Span::styled(format!("{}", up_to_date), Style::default().fg(Color::Green)),
+                Span::raw(format!(" up-to-date {} skipped", plural(up_to_date))),
+            ]));
+        }
+
+        let popup_area = popup_area(area, lines.len());
+        let paragraph = ratatui::widgets::Paragraph::new(lines).block(
+            Block::default()
+                .title("Update all".bold().light_blue())
+                .borders(Borders::ALL)
+                .border_type(ratatui::widgets::BorderType::Rounded)
+                .border_style(Style::default().fg(Color::White)),
+        );
+
+        Clear.render(popup_area, buf);
+        paragraph.render(popup_area, buf);
+    }
+
+    /// Render the remote branch picker used to choose an upstream, centered over the table
+    fn render_upstream_picker(&mut self, area: Rect, buf: &mut Buffer) {
+        let Some((repo_name, candidates, list_state)) = self.upstream_picker() else {
+            return;
+        };
+
+        let popup_area = popup_area(area, candidates.len());
+        let items: Vec<ListItem> = candidates
+            .iter()
+            .map(|candidate| ListItem::new(candidate.as_str()))
+            .collect();
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(
+                        format!("Track remote branch - {}", repo_name)
+                            .bold()
+                            .light_blue(),
+                    )
+                    .title_bottom(Line::from("Enter: select | Esc: cancel").right_aligned())
+                    .borders(Borders::ALL)
+                    .border_type(ratatui::widgets::BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::White)),
+            )
+            .highlight_style(
+                Style::default()
+                    .bg(Color::DarkGray)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("> ");
+
+        Clear.render(popup_area, buf);
+        StatefulWidget::render(list, popup_area, buf, list_state);
+    }
+
+    /// Render the merged branches picker used to choose the ones to delete, centered over
+    /// the table
+    fn render_prune_picker(&mut self, area: Rect, buf: &mut Buffer) {
+        let Some((repo_name, branches, list_state)) = self.prune_picker() else {
+            return;
+        };
+
+        let popup_area = popup_area(area, branches.len());
+        let items: Vec<ListItem> = branches
+            .iter()
+            .map(|(name, checked)| {
+                let mark = if *checked { "[x]" } else { "[ ]" };
+                ListItem::new(format!("{} {}", mark, name))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(
+                        format!("Delete merged branches - {}", repo_name)
+                            .bold()
+                            .light_blue(),
+                    )
+                    .title_bottom(
+                        Line::from("Space: toggle | a: all | Enter: delete | Esc: cancel")
+                            .right_aligned(),
+                    )
+                    .borders(Borders::ALL)
+                    .border_type(ratatui::widgets::BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::White)),
+            )
+            .highlight_style(
+                Style::default()
+                    .bg(Color::DarkGray)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("> ");
+
+        Clear.render(popup_area, buf);
+        StatefulWidget::render(list, popup_area, buf, list_state);
+    }
+
+    /// Render the worktree picker used to choose where to change directory, centered over
+    /// the table
+    fn render_worktree_picker(&mut self, area: Rect, buf: &mut Buffer) {
+        let Some((repo_name, worktrees, list_state)) = self.worktree_picker() else {
+            return;
+        };
+
+        let popup_area = popup_area(area, worktrees.len());
+        let items: Vec<ListItem> = worktrees
+            .iter()
+            .map(|(path, branch)| {
+                ListItem::new(Line::from(vec![
+                    Span::raw(path.display().to_string()),
+                    Span::raw(" "),
+                    Span::styled(branch.as_str(), Style::default().fg(Color::DarkGray)),
+                ]))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(format!("Worktrees - {}", repo_name).bold().light_blue())
+                    .title_bottom(
+                        Line::from("Enter: change directory | Esc: cancel").right_aligned(),
+                    )
+                    .borders(Borders::ALL)
+                    .border_type(ratatui::widgets::BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::White)),
+            )
+            .highlight_style(
+                Style::default()
+                    .bg(Color::DarkGray)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("> ");
+
+        Clear.render(popup_area, buf);
+        StatefulWidget::render(list, popup_area, buf, list_state);
     }
 
     /// Render the status bar
     fn render_status_bar(&self, area: Rect, buf: &mut Buffer) {
+        // While typing a branch name, show only the branch prompt
+        if let Some((repo_name, name)) = self.branch_input() {
+            Line::from(vec![
+                Span::styled("New branch in ", Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    repo_name,
+                    Style::default()
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(": ", Style::default().fg(Color::Yellow)),
+                Span::styled(name, Style::default().fg(Color::White)),
+            ])
+            .render(area, buf);
+            return;
+        }
+
+        // While typing a commit message, show only the commit prompt
+        if let Some((repo_name, message)) = self.commit_input() {
+            Line::from(vec![
+                Span::styled("Commit all changes in ", Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    repo_name,
+                    Style::default()
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(" with message: ", Style::default().fg(Color::Yellow)),
+                Span::styled(message, Style::default().fg(Color::White)),
+            ])
+            .render(area, buf);
+            return;
+        }
+
+        // While typing the new path of a repository, show only the move prompt
+        if let Some((repo_name, target)) = self.move_input() {
+            Line::from(vec![
+                Span::styled("Move ", Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    repo_name,
+                    Style::default()
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(" to: ", Style::default().fg(Color::Yellow)),
+                Span::styled(target, Style::default().fg(Color::White)),
+            ])
+            .render(area, buf);
+            return;
+        }
+
+        if self.is_full_status_mode() {
+            Line::from(vec![
+                Span::styled("Full status", Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    " | Scroll: ↑/↓ or j/k | Close: Esc",
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ])
+            .render(area, buf);
+            return;
+        }
+
+        if self.is_error_log_mode() {
+            Line::from(vec![
+                Span::styled("Failed operations", Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    " | Close: Esc | Clear: c",
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ])
+            .render(area, buf);
+            return;
+        }
+
+        // While changed files are listed, ask whether to discard them
+        if let Some((repo_name, files)) = self.discard_confirmation() {
+            Line::from(vec![
+                Span::styled("Discard ", Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    format!("{} changed files", files.len()),
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(" in ", Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    repo_name,
+                    Style::default()
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    "? This cannot be undone ",
+                    Style::default().fg(Color::Yellow),
+                ),
+                Span::styled(
+                    "[y/n]",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+            ])
+            .render(area, buf);
+            return;
+        }
+
+        // Before updating all displayed repositories, ask for confirmation
+        if let Some((count, ..)) = self.update_all_summary() {
+            Line::from(vec![
+                Span::styled("Update ", Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    if count == 1 {
+                        "1 repository".to_string()
+                    } else {
+                        format!("{} repositories", count)
+                    },
+                    Style::default()
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled("? ", Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    "[y/n]",
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+            ])
+            .render(area, buf);
+            return;
+        }
+
+        // In incoming preview mode, ask whether to fast-forward
+        if let Some((repo_name, _)) = self.incoming_preview() {
+            let confirm_text = Line::from(vec![
+                Span::styled("Fast-forward ", Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    repo_name,
+                    Style::default()
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled("? ", Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    "[y/n]",
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+            ]);
+            confirm_text.render(area, buf);
+            return;
+        }
+
         // In confirmation mode, show confirmation prompt
         if self.is_confirmation_mode() {
             if let Some(repo_name) = self.confirmation_repo_name() {
@@ -190,18 +1037,46 @@ impl App {
 
         // In search mode, show only the search prompt
         if self.is_search_mode() {
-            let search_text = Line::from(vec![
-                Span::styled("Search: ", Style::default().fg(Color::Yellow)),
+            let prompt = if self.is_regex_search() {
+                "Regex: "
+            } else {
+                "Search: "
+            };
+            let mut spans = vec![
+                Span::styled(prompt, Style::default().fg(Color::Yellow)),
                 Span::styled(self.search_query(), Style::default().fg(Color::White)),
-            ]);
-            search_text.render(area, buf);
+            ];
+            if self.is_search_regex_invalid() {
+                spans.push(Span::styled(
+                    " (invalid regex)",
+                    Style::default().fg(Color::Red),
+                ));
+            }
+            spans.push(Span::styled(
+                " | Toggle regex: Ctrl-R",
+                Style::default().fg(Color::DarkGray),
+            ));
+            Line::from(spans).render(area, buf);
+            return;
+        }
+
+        // After a batch operation, show its result until the next key press
+        if let Some(message) = self.status_message() {
+            Line::from(vec![
+                Span::styled(message, Style::default().fg(Color::Cyan)),
+                Span::styled(
+                    " | Press any key to continue",
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ])
+            .render(area, buf);
             return;
         }
 
         let filtered_count = self.filtered_repos().len();
-        let total_count = self.repos.len();
+        let total_count = self.repos().count();
 
-        let repo_count = if filtered_count == total_count {
+        let mut repo_count = if filtered_count == total_count {
             if total_count == 1 {
                 "Found 1 repository".to_string()
             } else {
@@ -210,20 +1085,52 @@ impl App {
         } else {
             format!("Showing {} of {} repositories", filtered_count, total_count)
         };
+        let (needs_attention, filtered_size) = self.filtered_summary();
+        if needs_attention > 0 {
+            repo_count = format!("{}, {} need attention", repo_count, needs_attention);
+        }
+        if self.show_disk_usage() {
+            repo_count = format!("{}, {} on disk", repo_count, format_size(filtered_size));
+        }
+        let (ahead, behind, out_of_sync) = self.sync_totals();
+        let repo_count = if out_of_sync == 0 {
+            repo_count
+        } else if out_of_sync == 1 {
+            format!("{} | Σ ↑{} ↓{} in 1 repo", repo_count, ahead, behind)
+        } else {
+            format!(
+                "{} | Σ ↑{} ↓{} across {} repos",
+                repo_count, ahead, behind, out_of_sync
+            )
+        };
+
+        // What Enter does depends on the shell integration and on `keep_open`
+        let enter_hint = self
+            .enter_hint()
+            .map(|hint| Span::styled(format!(" | {}", hint), Style::default().fg(Color::White)));
 
         let status_text = if !self.search_query().is_empty() {
             // Show search at the bottom left when a search filter is active
-            let search_display = format!("Search: {} (press / to edit)", self.search_query());
+            let search_display = if self.is_search_regex_invalid() {
+                format!(
+                    "Regex: {} (invalid regex, press / to edit)",
+                    self.search_query()
+                )
+            } else if self.is_regex_search() {
+                format!("Regex: {} (press / to edit)", self.search_query())
+            } else {
+                format!("Search: {} (press / to edit)", self.search_query())
+            };
 
-            if !self.fetching_repos.is_empty()
-                || !self.cloning_repos.is_empty()
-                || !self.deleting_repos.is_empty()
-            {
-                let spinner_chars = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
-                let spinner = spinner_chars[self.fetch_animation_frame % spinner_chars.len()];
+            if self.is_busy() {
+                let spinner = self.spinner();
 
                 let mut progress_parts = Vec::new();
 
+                if self.is_scanning() {
+                    progress_parts.push(format!("{} Scanning…", spinner));
+                }
+
                 if !self.fetching_repos.is_empty() {
                     let fetch_text = if self.fetching_repos.len() == 1 {
                         format!("{} Fetching 1 repo", spinner)
@@ -240,6 +1147,32 @@ impl App {
                         format!("{} Cloning {} repos", spinner, self.cloning_repos.len())
                     };
                     progress_parts.push(clone_text);
+                    if let Some((done, total)) = self.clone_batch_progress() {
+                        progress_parts.push(format!("{}/{} done", done, total));
+                        progress_parts.push("Esc to cancel".to_string());
+                    }
+                }
+
+                if !self.updating_submodules.is_empty() {
+                    let submodule_text = if self.updating_submodules.len() == 1 {
+                        format!("{} Updating submodules in 1 repo", spinner)
+                    } else {
+                        format!(
+                            "{} Updating submodules in {} repos",
+                            spinner,
+                            self.updating_submodules.len()
+                        )
+                    };
+                    progress_parts.push(submodule_text);
+                }
+
+                if !self.archiving_repos.is_empty() {
+                    let archive_text = if self.archiving_repos.len() == 1 {
+                        format!("{} Archiving 1 repo", spinner)
+                    } else {
+                        format!("{} Archiving {} repos", spinner, self.archiving_repos.len())
+                    };
+                    progress_parts.push(archive_text);
                 }
 
                 if !self.deleting_repos.is_empty() {
@@ -275,16 +1208,16 @@ impl App {
                     ),
                 ])
             }
-        } else if !self.fetching_repos.is_empty()
-            || !self.cloning_repos.is_empty()
-            || !self.deleting_repos.is_empty()
-        {
+        } else if self.is_busy() {
             // Show fetch/clone/delete progress with animation
-            let spinner_chars = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
-            let spinner = spinner_chars[self.fetch_animation_frame % spinner_chars.len()];
+            let spinner = self.spinner();
 
             let mut progress_parts = Vec::new();
 
+            if self.is_scanning() {
+                progress_parts.push(format!("{} Scanning…", spinner));
+            }
+
             if !self.fetching_repos.is_empty() {
                 let fetch_text = if self.fetching_repos.len() == 1 {
                     format!("{} Fetching 1 repo", spinner)
@@ -301,6 +1234,32 @@ impl App {
                     format!("{} Cloning {} repos", spinner, self.cloning_repos.len())
                 };
                 progress_parts.push(clone_text);
+                if let Some((done, total)) = self.clone_batch_progress() {
+                    progress_parts.push(format!("{}/{} done", done, total));
+                    progress_parts.push("Esc to cancel".to_string());
+                }
+            }
+
+            if !self.updating_submodules.is_empty() {
+                let submodule_text = if self.updating_submodules.len() == 1 {
+                    format!("{} Updating submodules in 1 repo", spinner)
+                } else {
+                    format!(
+                        "{} Updating submodules in {} repos",
+                        spinner,
+                        self.updating_submodules.len()
+                    )
+                };
+                progress_parts.push(submodule_text);
+            }
+
+            if !self.archiving_repos.is_empty() {
+                let archive_text = if self.archiving_repos.len() == 1 {
+                    format!("{} Archiving 1 repo", spinner)
+                } else {
+                    format!("{} Archiving {} repos", spinner, self.archiving_repos.len())
+                };
+                progress_parts.push(archive_text);
             }
 
             if !self.deleting_repos.is_empty() {
@@ -314,25 +1273,39 @@ impl App {
 
             let progress_text = progress_parts.join(", ");
 
-            Line::from(vec![
+            let mut spans = vec![
                 Span::styled(repo_count, Style::default().fg(Color::Cyan)),
                 Span::raw(" | "),
                 Span::styled(progress_text, Style::default().fg(Color::Yellow)),
-                Span::styled(
-                    " | Navigate: ↑/↓ or j/k | Mode: [/] | Search: / | Update: u | Clone: c | Drop: d | Quit: q or Ctrl-C",
+            ];
+            spans.extend(enter_hint);
+            spans.push(Span::styled(
+                    " | Navigate: ↑/↓ or j/k | Next to check: n/N | Mode: [/] | Sort: s or 1-4 | Group: g | Missing: o | Pin: * | Search: / | Update: u | Update all: U | Strategy: e | Errors: E | Refresh: r | Submodules: m | Default branch: h | Prune branches: l | Worktrees: w | Commit: i | Tags: f | Move: v | Shell: ! | Copy: y | Copy SHA: Y | Copy clone: Ctrl-Y | Archive: a | Discard: x | Full status: Tab | Same host: @ | Compact: . | Preview: p | Clone: c | Drop: d | Quit: q or Ctrl-C",
                     Style::default().fg(Color::DarkGray),
-                ),
-            ])
+            ));
+            Line::from(spans)
         } else {
-            Line::from(vec![
-                Span::styled(repo_count, Style::default().fg(Color::Cyan)),
-                Span::styled(
-                    " | Navigate: ↑/↓ or j/k | Mode: [/] | Search: / | Update: u | Clone: c | Drop: d | Quit: q or Ctrl-C",
+            let mut spans = vec![Span::styled(repo_count, Style::default().fg(Color::Cyan))];
+            spans.extend(enter_hint);
+            spans.push(Span::styled(
+                    " | Navigate: ↑/↓ or j/k | Next to check: n/N | Mode: [/] | Sort: s or 1-4 | Group: g | Missing: o | Pin: * | Search: / | Update: u | Update all: U | Strategy: e | Errors: E | Refresh: r | Submodules: m | Default branch: h | Prune branches: l | Worktrees: w | Commit: i | Tags: f | Move: v | Shell: ! | Copy: y | Copy SHA: Y | Copy clone: Ctrl-Y | Archive: a | Discard: x | Full status: Tab | Same host: @ | Compact: . | Preview: p | Clone: c | Drop: d | Quit: q or Ctrl-C",
                     Style::default().fg(Color::DarkGray),
-                ),
-            ])
+            ));
+            Line::from(spans)
         };
 
         status_text.render(area, buf);
     }
 }
+
+/// Get a popup area centered in `area`, tall enough for `line_count` lines and borders
+fn popup_area(area: Rect, line_count: usize) -> Rect {
+    let [popup_area] = Layout::horizontal([Constraint::Percentage(70)])
+        .flex(ratatui::layout::Flex::Center)
+        .areas(area);
+    let height = (line_count as u16).saturating_add(2).min(area.height);
+    let [popup_area] = Layout::vertical([Constraint::Length(height)])
+        .flex(ratatui::layout::Flex::Center)
+        .areas(popup_area);
+    popup_area
+}
@@ -4,6 +4,53 @@ pub fn strip_unc_prefix(path: &str) -> &str {
     path.strip_prefix(r"\\?\").unwrap_or(path)
 }
 
+/// Parse a duration such as "12h", "7d" or "2w" (hours, days or weeks)
+pub fn parse_duration(value: &str) -> Result<std::time::Duration, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| format!("Missing unit in '{}'. Use h, d or w", value))?;
+    let (amount, unit) = value.split_at(split);
+
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("Invalid duration '{}'", value))?;
+    let seconds_per_unit = match unit {
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "Unknown unit '{}' in '{}'. Use h, d or w",
+                unit, value
+            ));
+        }
+    };
+
+    amount
+        .checked_mul(seconds_per_unit)
+        .map(std::time::Duration::from_secs)
+        .ok_or_else(|| format!("Duration '{}' is too long", value))
+}
+
+/// Format a byte count as a human-readable size (e.g. "1.2 GB")
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
 /// Strip Windows UNC (\\?\) prefix from a PathBuf, returning a new PathBuf
 pub fn strip_unc_pathbuf(path: &std::path::Path) -> std::path::PathBuf {
     if let Some(s) = path.to_str() {
@@ -12,3 +59,13 @@ pub fn strip_unc_pathbuf(path: &std::path::Path) -> std::path::PathBuf {
         path.to_path_buf()
     }
 }
+
+/// Quote an argument for a POSIX shell, leaving it as is when it needs no quoting
+pub fn shell_quote(arg: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "_-./:@~+=,%".contains(c);
+    if !arg.is_empty() && arg.chars().all(is_safe) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
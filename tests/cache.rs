@@ -0,0 +1,65 @@
+//! Location of the repository caches
+
+mod common;
+
+use common::Fixture;
+use std::fs;
+use std::path::PathBuf;
+
+/// Cache files of the cache directory
+fn cache_files(fixture: &Fixture) -> Vec<PathBuf> {
+    match fs::read_dir(fixture.cache_dir()) {
+        Ok(entries) => entries.map(|entry| entry.unwrap().path()).collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+#[test]
+fn caches_are_moved_out_of_the_config_directory() {
+    let fixture = Fixture::new();
+    fixture.repo("project");
+    let root = fixture.root().to_string_lossy().to_string();
+
+    // Legacy cache, shared by all roots
+    fs::create_dir_all(fixture.config_dir()).unwrap();
+    let legacy = fixture.config_dir().join("repos.yaml");
+    fs::write(&legacy, "- path: project\n").unwrap();
+
+    let output = fixture.run(&["cache", "repair", &root]);
+    assert!(output.contains("is fine (1 repository)"), "{}", output);
+    assert!(!legacy.exists());
+    let [cache] = cache_files(&fixture).try_into().expect("One cache file");
+
+    // Cache of the root written next to the configuration by an earlier version
+    let file_name = cache.file_name().unwrap().to_owned();
+    let config_cache = fixture.config_dir().join(&file_name);
+    fs::rename(&cache, &config_cache).unwrap();
+
+    let output = fixture.run(&["cache", "repair", &root]);
+    assert!(output.contains("is fine (1 repository)"), "{}", output);
+    assert!(!config_cache.exists());
+    assert_eq!(cache_files(&fixture), [fixture.cache_dir().join(file_name)]);
+}
+
+#[test]
+fn each_root_keeps_its_own_cache() {
+    let fixture = Fixture::new();
+    fixture.repo("first/project");
+    fixture.repo("second/project");
+    let first = fixture.root().join("first").to_string_lossy().to_string();
+    let second = fixture.root().join("second").to_string_lossy().to_string();
+
+    // Seed the cache of each root through the legacy cache, moved to the root repaired
+    fs::create_dir_all(fixture.config_dir()).unwrap();
+    let legacy = fixture.config_dir().join("repos.yaml");
+    fs::write(&legacy, "- path: project\n").unwrap();
+    fixture.run(&["cache", "repair", &first]);
+    fs::write(&legacy, "- path: project\n- path: other\n").unwrap();
+    fixture.run(&["cache", "repair", &second]);
+    assert_eq!(cache_files(&fixture).len(), 2);
+
+    let output = fixture.run(&["cache", "repair", &first]);
+    assert!(output.contains("is fine (1 repository)"), "{}", output);
+    let output = fixture.run(&["cache", "repair", &second]);
+    assert!(output.contains("is fine (2 repositories)"), "{}", output);
+}
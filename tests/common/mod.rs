@@ -0,0 +1,347 @@
+//! Temporary git repositories to run `git-repos` against
+
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+
+#[cfg(target_os = "linux")]
+pub use interface::Interface;
+
+/// A temporary directory holding repositories, and the home, configuration and cache
+/// directories used by git and `git-repos`, so the tests never read the user configuration
+pub struct Fixture {
+    dir: TempDir,
+}
+
+impl Fixture {
+    pub fn new() -> Self {
+        let dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        for subdir in ["repos", "home", "config"] {
+            fs::create_dir(dir.path().join(subdir)).expect("Failed to create directory");
+        }
+        Self { dir }
+    }
+
+    /// Directory where the repositories are created, to scan
+    pub fn root(&self) -> PathBuf {
+        self.dir
+            .path()
+            .join("repos")
+            .canonicalize()
+            .expect("Failed to resolve root")
+    }
+
+    /// Create an empty repository, on branch `main`
+    pub fn repo(&self, name: &str) -> TestRepo {
+        let path = self.root().join(name);
+        fs::create_dir_all(&path).expect("Failed to create repository directory");
+        let repo = TestRepo {
+            path,
+            home: self.home(),
+        };
+        repo.git(&["init", "--quiet", "--initial-branch=main"]);
+        repo
+    }
+
+    /// Create a bare repository outside of the scanned root, to use as a remote
+    pub fn bare(&self, name: &str) -> PathBuf {
+        let path = self.dir.path().join("remotes").join(name);
+        fs::create_dir_all(&path).expect("Failed to create remote directory");
+        run_git(
+            &path,
+            &self.home(),
+            &["init", "--quiet", "--bare", "--initial-branch=main"],
+        );
+        path
+    }
+
+    /// Clone a repository into the scanned root
+    pub fn clone(&self, remote: &Path, name: &str) -> TestRepo {
+        let path = self.root().join(name);
+        run_git(
+            &self.root(),
+            &self.home(),
+            &["clone", "--quiet", &remote.to_string_lossy(), name],
+        );
+        TestRepo {
+            path,
+            home: self.home(),
+        }
+    }
+
+    /// Create the `git-repos` command, isolated from the user configuration
+    pub fn git_repos(&self) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_git-repos"));
+        isolate(&mut command, &self.home());
+        command
+            .env("XDG_CONFIG_HOME", self.dir.path().join("config"))
+            .env("APPDATA", self.dir.path().join("config"))
+            .env("XDG_CACHE_HOME", self.dir.path().join("cache"))
+            .env("LOCALAPPDATA", self.dir.path().join("cache"))
+            .env_remove("RUST_LOG")
+            .env("NO_COLOR", "1");
+        command
+    }
+
+    /// Start the interface in a terminal, with the `git-repos` arguments
+    #[cfg(target_os = "linux")]
+    pub fn interface(&self, args: &[&str]) -> Interface {
+        Interface::spawn(self.git_repos(), args)
+    }
+
+    /// Run `git-repos` and return its standard output, failing the test if it fails
+    pub fn run(&self, args: &[&str]) -> String {
+        let output = self
+            .git_repos()
+            .args(args)
+            .output()
+            .expect("Failed to run git-repos");
+        assert!(
+            output.status.success(),
+            "git-repos {:?} failed: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).expect("Output is not UTF-8")
+    }
+
+    /// Read the status of a repository with `git-repos status --json`
+    pub fn status(&self, repo: &TestRepo) -> serde_json::Value {
+        let output = self.run(&["status", "--json", &repo.path.to_string_lossy()]);
+        serde_json::from_str(&output).expect("Invalid JSON")
+    }
+
+    /// Directory of the `git-repos` configuration file
+    pub fn config_dir(&self) -> PathBuf {
+        self.dir.path().join("config").join("git-repos")
+    }
+
+    /// Directory of the `git-repos` repository caches
+    pub fn cache_dir(&self) -> PathBuf {
+        self.dir.path().join("cache").join("git-repos")
+    }
+
+    fn home(&self) -> PathBuf {
+        self.dir.path().join("home")
+    }
+}
+
+/// A repository of a fixture
+pub struct TestRepo {
+    pub path: PathBuf,
+    home: PathBuf,
+}
+
+impl TestRepo {
+    /// Run git in the repository and return its standard output
+    pub fn git(&self, args: &[&str]) -> String {
+        run_git(&self.path, &self.home, args)
+    }
+
+    /// Write a file in the working tree, relative to the repository
+    pub fn write(&self, file: &str, contents: &str) {
+        let path = self.path.join(file);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).expect("Failed to create directory");
+        }
+        fs::write(path, contents).expect("Failed to write file");
+    }
+
+    /// Write a file, stage it and commit it
+    pub fn commit(&self, file: &str, contents: &str) {
+        self.write(file, contents);
+        self.git(&["add", file]);
+        self.git(&["commit", "--quiet", "-m", &format!("Update {}", file)]);
+    }
+
+    /// Write a file, stage it and commit it as if it was committed `days_ago` days ago
+    pub fn commit_days_ago(&self, file: &str, contents: &str, days_ago: u64) {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("Clock before the epoch")
+            .as_secs();
+        let date = format!("@{}", now - days_ago * 24 * 60 * 60);
+        self.write(file, contents);
+        self.git(&["add", file]);
+
+        let mut command = Command::new("git");
+        isolate(&mut command, &self.home);
+        let output = command
+            .args(["commit", "--quiet", "-m", &format!("Update {}", file)])
+            .env("GIT_AUTHOR_DATE", &date)
+            .env("GIT_COMMITTER_DATE", &date)
+            .current_dir(&self.path)
+            .output()
+            .expect("Failed to run git");
+        assert!(
+            output.status.success(),
+            "git commit failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    /// Add a linked worktree next to the repository, on a new branch
+    pub fn worktree(&self, name: &str) -> TestRepo {
+        let path = self.path.with_file_name(name);
+        self.git(&[
+            "worktree",
+            "add",
+            "--quiet",
+            "-b",
+            name,
+            &path.to_string_lossy(),
+        ]);
+        TestRepo {
+            path,
+            home: self.home.clone(),
+        }
+    }
+
+    /// Add a remote and fetch it
+    pub fn add_remote(&self, name: &str, url: &Path) {
+        self.git(&["remote", "add", name, &url.to_string_lossy()]);
+        self.git(&["fetch", "--quiet", name]);
+    }
+}
+
+fn run_git(dir: &Path, home: &Path, args: &[&str]) -> String {
+    let mut command = Command::new("git");
+    isolate(&mut command, home);
+    let output = command
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("Failed to run git");
+    assert!(
+        output.status.success(),
+        "git {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("Output is not UTF-8")
+}
+
+/// Make git ignore the user and system configuration, with a fixed identity
+fn isolate(command: &mut Command, home: &Path) {
+    command
+        .env("HOME", home)
+        .env("USERPROFILE", home)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env("GIT_CONFIG_GLOBAL", home.join(".gitconfig"))
+        .env("GIT_AUTHOR_NAME", "Test")
+        .env("GIT_AUTHOR_EMAIL", "test@example.com")
+        .env("GIT_COMMITTER_NAME", "Test")
+        .env("GIT_COMMITTER_EMAIL", "test@example.com");
+}
+
+#[cfg(target_os = "linux")]
+mod interface {
+    use std::io::{Read, Write};
+    use std::process::{Child, ChildStdin, Command, ExitStatus, Stdio};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    /// The interface, running under the util-linux `script` for a terminal
+    pub struct Interface {
+        child: Child,
+        stdin: Option<ChildStdin>,
+        output: Vec<u8>,
+        rx: mpsc::Receiver<Vec<u8>>,
+    }
+
+    /// Quote an argument for `sh`
+    fn quote(arg: &str) -> String {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+
+    impl Interface {
+        pub(super) fn spawn(git_repos: Command, args: &[&str]) -> Self {
+            // `script` runs the interface in a pseudo-terminal, sized to draw the list, and
+            // forwards the keys written to it
+            let mut command = Command::new("script");
+            for (key, value) in git_repos.get_envs() {
+                match value {
+                    Some(value) => command.env(key, value),
+                    None => command.env_remove(key),
+                };
+            }
+            let interface = std::iter::once(git_repos.get_program().to_string_lossy())
+                .chain(args.iter().map(|arg| (*arg).into()))
+                .map(|arg| quote(&arg))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let interface = format!("stty rows 24 cols 100 && exec {}", interface);
+            let mut child = command
+                .args(["--quiet", "--return", "--command", &interface, "/dev/null"])
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()
+                .expect("Failed to run script");
+
+            let mut stdout = child.stdout.take().unwrap();
+            let (tx, rx) = mpsc::channel();
+            std::thread::spawn(move || {
+                let mut buffer = [0; 4096];
+                while let Ok(read @ 1..) = stdout.read(&mut buffer) {
+                    if tx.send(buffer[..read].to_vec()).is_err() {
+                        break;
+                    }
+                }
+            });
+
+            Self {
+                stdin: child.stdin.take(),
+                child,
+                output: Vec::new(),
+                rx,
+            }
+        }
+
+        /// Wait until the interface draws `text`, failing the test after 30 seconds
+        pub fn wait_for(&mut self, text: &str) {
+            self.wait_until(text, || false);
+        }
+
+        /// Wait until the interface draws `text` or `done` returns true, failing the test
+        /// after 30 seconds
+        pub fn wait_until(&mut self, text: &str, mut done: impl FnMut() -> bool) {
+            for _ in 0..300 {
+                if String::from_utf8_lossy(&self.output).contains(text) || done() {
+                    return;
+                }
+                if let Ok(chunk) = self.rx.recv_timeout(Duration::from_millis(100)) {
+                    self.output.extend(chunk);
+                }
+            }
+            let _ = self.child.kill();
+            panic!(
+                "the interface did not show {:?}: {}",
+                text,
+                String::from_utf8_lossy(&self.output)
+            );
+        }
+
+        /// Send keys to the interface
+        pub fn press(&mut self, keys: &str) {
+            let stdin = self.stdin.as_mut().expect("Input already closed");
+            stdin
+                .write_all(keys.as_bytes())
+                .expect("Failed to send keys");
+            stdin.flush().expect("Failed to send keys");
+        }
+
+        /// Send the last keys, then wait for the interface to exit and return its status and
+        /// its whole output
+        pub fn finish(mut self, keys: &str) -> (ExitStatus, String) {
+            self.press(keys);
+            // `script` only exits once its input is closed
+            drop(self.stdin.take());
+            self.output.extend(self.rx.iter().flatten());
+            let status = self.child.wait().expect("Failed to wait for git-repos");
+            (status, String::from_utf8_lossy(&self.output).into_owned())
+        }
+    }
+}
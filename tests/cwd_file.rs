@@ -0,0 +1,33 @@
+//! Shell integration: the repository selected in the interface, written to `--cwd-file`
+//!
+//! The interface runs under the util-linux `script`, for a terminal.
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn unwritable_cwd_file_prints_the_selection() {
+    let fixture = Fixture::new();
+    let repo = fixture.repo("project");
+    let cwd_file = fixture.root().join("missing").join("cwd");
+    let root = fixture.root().to_string_lossy().to_string();
+
+    // Select the repository with Enter once it is listed
+    let cwd = cwd_file.to_string_lossy();
+    let mut interface = fixture.interface(&["--no-fetch", "--cwd-file", &cwd, &root]);
+    interface.wait_for("project");
+    let (status, output) = interface.finish("\r");
+
+    assert!(status.success(), "git-repos failed: {}", output);
+    assert!(
+        output
+            .lines()
+            .any(|line| line.trim_end() == repo.path.to_string_lossy()),
+        "the selected repository is not printed: {}",
+        output
+    );
+    assert!(!cwd_file.exists());
+}
@@ -0,0 +1,41 @@
+//! Repositories deleted by `git-repos drop`, matched by their path relative to the root
+
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn lists_then_deletes_the_matching_repositories() {
+    let fixture = Fixture::new();
+    for name in ["archive/old", "archive/older", "work/project"] {
+        fixture.repo(name).commit("a.txt", "a");
+    }
+    let root = fixture.root().to_string_lossy().to_string();
+
+    let output = fixture.run(&["drop", "archive/*", &root]);
+    assert!(output.contains("Would delete archive/old ("), "{}", output);
+    assert!(
+        output.contains("Would delete archive/older ("),
+        "{}",
+        output
+    );
+    assert!(!output.contains("work/project"), "{}", output);
+    assert!(fixture.root().join("archive/old").exists());
+
+    let output = fixture.run(&["drop", "archive/*", &root, "--force"]);
+    assert!(output.contains("Deleted 2 repositories"), "{}", output);
+    assert!(!fixture.root().join("archive/old").exists());
+    assert!(!fixture.root().join("archive/older").exists());
+    assert!(fixture.root().join("work/project").exists());
+}
+
+#[test]
+fn pattern_without_match() {
+    let fixture = Fixture::new();
+    fixture.repo("project");
+    let root = fixture.root().to_string_lossy().to_string();
+
+    let output = fixture.run(&["drop", "archive/*", &root, "--force"]);
+    assert_eq!(output, "No repository matches the pattern\n");
+    assert!(fixture.root().join("project").exists());
+}
@@ -0,0 +1,60 @@
+//! Repositories cloned more than once, found by their normalized remote
+
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn same_remote_over_ssh_and_https() {
+    let fixture = Fixture::new();
+    let urls = [
+        ("ssh", "git@github.com:user/repo.git"),
+        ("https", "https://github.com/user/repo"),
+        ("ssh-url", "ssh://git@GitHub.com/user/repo/"),
+        ("other", "https://github.com/user/other.git"),
+    ];
+    for (name, url) in urls {
+        fixture.repo(name).git(&["remote", "add", "origin", url]);
+    }
+
+    let root = fixture.root();
+    let expected = format!(
+        "github.com/user/repo\n  {}\n  {}\n  {}\n",
+        root.join("https").display(),
+        root.join("ssh").display(),
+        root.join("ssh-url").display()
+    );
+    assert_eq!(
+        fixture.run(&["duplicates", &root.to_string_lossy()]),
+        expected
+    );
+}
+
+#[test]
+fn local_remote_as_path_and_file_url() {
+    let fixture = Fixture::new();
+    let remote = fixture.bare("project.git");
+    fixture.clone(&remote, "first");
+    let url = format!("file://{}", remote.display());
+    fixture
+        .repo("second")
+        .git(&["remote", "add", "origin", &url]);
+
+    let output = fixture.run(&["duplicates", &fixture.root().to_string_lossy()]);
+    assert_eq!(output.lines().count(), 3, "{}", output);
+    assert!(output.contains("first") && output.contains("second"));
+}
+
+#[test]
+fn no_duplicates() {
+    let fixture = Fixture::new();
+    fixture
+        .repo("alone")
+        .git(&["remote", "add", "origin", "https://example.com/alone.git"]);
+    fixture.repo("local");
+
+    assert_eq!(
+        fixture.run(&["duplicates", &fixture.root().to_string_lossy()]),
+        "No repository is cloned more than once\n"
+    );
+}
@@ -0,0 +1,80 @@
+//! Commits counted by `git-repos report` over a period
+
+mod common;
+
+use common::Fixture;
+
+/// Commit counts printed by `git-repos report --json`, by path relative to the scanned root
+fn report(fixture: &Fixture, extra_args: &[&str]) -> Vec<(String, u64)> {
+    let root = fixture.root();
+    let mut args = vec!["report", "--json"];
+    args.extend_from_slice(extra_args);
+    let root_arg = root.to_string_lossy().to_string();
+    args.push(&root_arg);
+
+    let output: serde_json::Value =
+        serde_json::from_str(&fixture.run(&args)).expect("Invalid JSON");
+    output
+        .as_array()
+        .expect("Not an array")
+        .iter()
+        .map(|entry| {
+            let path = std::path::Path::new(entry["path"].as_str().unwrap())
+                .strip_prefix(&root)
+                .expect("Repository outside of the root")
+                .to_string_lossy()
+                .replace('\\', "/");
+            (path, entry["commits"].as_u64().unwrap())
+        })
+        .collect()
+}
+
+#[test]
+fn counts_recent_commits_only() {
+    let fixture = Fixture::new();
+    let active = fixture.repo("active");
+    active.commit_days_ago("old.txt", "old", 30);
+    active.commit("a.txt", "a");
+    active.commit("b.txt", "b");
+    let idle = fixture.repo("idle");
+    idle.commit_days_ago("old.txt", "old", 30);
+    fixture.repo("empty");
+
+    assert_eq!(
+        report(&fixture, &["--since", "7d"]),
+        [("active".to_string(), 2)]
+    );
+    assert_eq!(
+        report(&fixture, &["--since", "6w"]),
+        [("active".to_string(), 3), ("idle".to_string(), 1)]
+    );
+}
+
+#[test]
+fn until_excludes_recent_commits() {
+    let fixture = Fixture::new();
+    let repo = fixture.repo("project");
+    repo.commit_days_ago("old.txt", "old", 10);
+    repo.commit("new.txt", "new");
+
+    assert_eq!(
+        report(&fixture, &["--since", "2w", "--until", "1w"]),
+        [("project".to_string(), 1)]
+    );
+    assert!(report(&fixture, &["--since", "2d", "--until", "1d"]).is_empty());
+}
+
+#[test]
+fn too_long_duration_is_rejected() {
+    let fixture = Fixture::new();
+    let output = fixture
+        .git_repos()
+        .args(["report", "--since", "99999999999999999w"])
+        .arg(fixture.root())
+        .output()
+        .expect("Failed to run git-repos");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("is too long"), "{}", stderr);
+}
@@ -0,0 +1,76 @@
+//! Repositories found when scanning a directory
+
+mod common;
+
+use common::Fixture;
+
+/// Paths printed by `git-repos list --plain`, relative to the scanned root
+fn list(fixture: &Fixture, extra_args: &[&str]) -> Vec<String> {
+    let root = fixture.root();
+    let mut args = vec!["list", "--plain"];
+    args.extend_from_slice(extra_args);
+    let root_arg = root.to_string_lossy().to_string();
+    args.push(&root_arg);
+
+    fixture
+        .run(&args)
+        .lines()
+        .map(|line| {
+            std::path::Path::new(line)
+                .strip_prefix(&root)
+                .expect("Repository outside of the root")
+                .to_string_lossy()
+                .replace('\\', "/")
+        })
+        .collect()
+}
+
+#[test]
+fn finds_nested_repositories() {
+    let fixture = Fixture::new();
+    fixture.repo("top");
+    fixture.repo("group/project");
+    fixture.repo("group/deeper/tool");
+    std::fs::create_dir_all(fixture.root().join("not-a-repo")).unwrap();
+
+    assert_eq!(
+        list(&fixture, &[]),
+        ["group/deeper/tool", "group/project", "top"]
+    );
+}
+
+#[test]
+fn skips_repositories_inside_repositories() {
+    let fixture = Fixture::new();
+    fixture.repo("outer");
+    fixture.repo("outer/vendor/inner");
+
+    assert_eq!(list(&fixture, &[]), ["outer"]);
+}
+
+#[test]
+fn hidden_directories_need_include_hidden() {
+    let fixture = Fixture::new();
+    fixture.repo("visible");
+    fixture.repo(".hidden/project");
+
+    assert_eq!(list(&fixture, &[]), ["visible"]);
+    assert_eq!(
+        list(&fixture, &["--include-hidden"]),
+        [".hidden/project", "visible"]
+    );
+}
+
+#[test]
+fn flat_scan_only_looks_at_children() {
+    let fixture = Fixture::new();
+    fixture.repo("top");
+    fixture.repo("group/project");
+    fixture.repo(".hidden");
+
+    assert_eq!(list(&fixture, &["--flat"]), ["top"]);
+    assert_eq!(
+        list(&fixture, &["--flat", "--include-hidden"]),
+        [".hidden", "top"]
+    );
+}
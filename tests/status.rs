@@ -0,0 +1,204 @@
+//! Status strings read from real repositories, the vocabulary the list relies on
+
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn clean_repository_without_remote() {
+    let fixture = Fixture::new();
+    let repo = fixture.repo("local");
+    repo.commit("README.md", "hello");
+
+    let status = fixture.status(&repo);
+    assert_eq!(status["branch"], "main");
+    assert_eq!(status["remote_status"], "local-only");
+    assert_eq!(status["status"], "clean");
+}
+
+#[test]
+fn staged_modified_and_untracked_files() {
+    let fixture = Fixture::new();
+    let repo = fixture.repo("dirty");
+    repo.commit("a.txt", "a");
+    repo.commit("b.txt", "b");
+
+    repo.write("a.txt", "changed");
+    repo.write("b.txt", "staged");
+    repo.git(&["add", "b.txt"]);
+    repo.write("new.txt", "untracked");
+
+    assert_eq!(fixture.status(&repo)["status"], "1S 1M 1?");
+}
+
+#[test]
+fn stash_is_reported_on_a_clean_tree() {
+    let fixture = Fixture::new();
+    let repo = fixture.repo("stashed");
+    repo.commit("a.txt", "a");
+    repo.write("a.txt", "changed");
+    repo.git(&["stash", "--quiet"]);
+
+    assert_eq!(fixture.status(&repo)["status"], "clean stash");
+}
+
+#[test]
+fn stash_is_reported_in_a_linked_worktree() {
+    let fixture = Fixture::new();
+    let repo = fixture.repo("main");
+    repo.commit("a.txt", "a");
+    let worktree = repo.worktree("feature");
+    worktree.write("a.txt", "changed");
+    worktree.git(&["stash", "--quiet"]);
+
+    assert_eq!(fixture.status(&worktree)["status"], "clean stash");
+    assert_eq!(fixture.status(&repo)["status"], "clean stash");
+}
+
+#[test]
+fn operation_in_progress_is_reported() {
+    let fixture = Fixture::new();
+    let repo = fixture.repo("busy");
+    repo.commit("a.txt", "a");
+    let head = repo.git(&["rev-parse", "HEAD"]);
+    let git_dir = repo.path.join(".git");
+
+    for (marker, status) in [
+        ("MERGE_HEAD", "MERGING"),
+        ("CHERRY_PICK_HEAD", "CHERRY-PICKING"),
+        ("REVERT_HEAD", "REVERTING"),
+        ("BISECT_LOG", "BISECTING"),
+    ] {
+        std::fs::write(git_dir.join(marker), &head).expect("Failed to write marker");
+        assert_eq!(fixture.status(&repo)["status"], status, "with {}", marker);
+        std::fs::remove_file(git_dir.join(marker)).expect("Failed to remove marker");
+    }
+
+    for marker in ["rebase-merge", "rebase-apply"] {
+        std::fs::create_dir(git_dir.join(marker)).expect("Failed to create marker");
+        assert_eq!(
+            fixture.status(&repo)["status"],
+            "REBASING",
+            "with {}",
+            marker
+        );
+        std::fs::remove_dir(git_dir.join(marker)).expect("Failed to remove marker");
+    }
+
+    // Changes are still listed during the operation
+    std::fs::write(git_dir.join("MERGE_HEAD"), &head).expect("Failed to write marker");
+    repo.write("a.txt", "conflict");
+    assert_eq!(fixture.status(&repo)["status"], "MERGING 1M");
+}
+
+#[test]
+fn ahead_and_behind_upstream() {
+    let fixture = Fixture::new();
+    let remote = fixture.bare("project.git");
+    let first = fixture.clone(&remote, "first");
+    first.commit("a.txt", "a");
+    first.git(&["push", "--quiet", "origin", "main"]);
+
+    let second = fixture.clone(&remote, "second");
+    assert_eq!(fixture.status(&second)["remote_status"], "up-to-date");
+
+    first.commit("b.txt", "b");
+    first.git(&["push", "--quiet", "origin", "main"]);
+    second.commit("c.txt", "c");
+    second.git(&["fetch", "--quiet"]);
+    assert_eq!(fixture.status(&second)["remote_status"], "↑1 ↓1");
+}
+
+#[test]
+fn branch_without_upstream() {
+    let fixture = Fixture::new();
+    let remote = fixture.bare("project.git");
+    let repo = fixture.repo("project");
+    repo.commit("a.txt", "a");
+    repo.add_remote("origin", &remote);
+    repo.git(&["checkout", "--quiet", "-b", "feature"]);
+
+    let status = fixture.status(&repo);
+    assert_eq!(status["branch"], "feature");
+    assert_eq!(status["remote_status"], "no-tracking");
+}
+
+#[test]
+fn repository_without_commits() {
+    let fixture = Fixture::new();
+    let remote = fixture.bare("empty.git");
+    let repo = fixture.repo("empty");
+    repo.add_remote("origin", &remote);
+
+    let status = fixture.status(&repo);
+    assert_eq!(status["branch"], "main");
+    assert_eq!(status["remote_status"], "no-commits");
+}
+
+#[test]
+fn detached_head_shows_the_commit() {
+    let fixture = Fixture::new();
+    let repo = fixture.repo("detached");
+    repo.commit("a.txt", "a");
+    let commit = repo.git(&["rev-parse", "HEAD"]);
+    repo.git(&["checkout", "--quiet", "--detach"]);
+
+    assert_eq!(
+        fixture.status(&repo)["branch"],
+        format!("detached@{}", &commit[..7])
+    );
+}
+
+#[test]
+fn detached_worktree_follows_the_default_branch() {
+    let fixture = Fixture::new();
+    let remote = fixture.bare("project.git");
+    let first = fixture.clone(&remote, "first");
+    first.commit("a.txt", "a");
+    first.git(&["push", "--quiet", "origin", "main"]);
+
+    let repo = fixture.clone(&remote, "project");
+    let worktree = repo.worktree("detached");
+    worktree.git(&["checkout", "--quiet", "--detach", "origin/main"]);
+    std::fs::create_dir_all(fixture.config_dir()).unwrap();
+    std::fs::write(
+        fixture.config_dir().join("config.toml"),
+        "follow_detached_head = true\n",
+    )
+    .unwrap();
+
+    assert_eq!(fixture.status(&worktree)["remote_status"], "up-to-date");
+}
+
+#[test]
+fn upstream_deleted_after_a_branch_rename() {
+    let fixture = Fixture::new();
+    let remote = fixture.bare("project.git");
+    let repo = fixture.clone(&remote, "project");
+    repo.commit("a.txt", "a");
+    repo.git(&["push", "--quiet", "--set-upstream", "origin", "main:old"]);
+    assert_eq!(fixture.status(&repo)["remote_status"], "up-to-date");
+
+    repo.git(&["branch", "--move", "main", "new"]);
+    repo.git(&["push", "--quiet", "origin", "--delete", "old"]);
+    assert_eq!(fixture.status(&repo)["remote_status"], "upstream-gone");
+}
+
+#[test]
+fn corrupt_git_directory() {
+    let fixture = Fixture::new();
+    let repo = fixture.repo("broken");
+    repo.commit("a.txt", "a");
+    std::fs::write(repo.path.join(".git").join("HEAD"), "garbage").expect("Failed to break HEAD");
+
+    let status = fixture.status(&repo);
+    assert_eq!(status["remote_status"], "corrupt");
+    assert_eq!(status["status"], "corrupt");
+    assert!(
+        status["error"]
+            .as_str()
+            .is_some_and(|error| error.contains("not a git repository")),
+        "unexpected error: {}",
+        status["error"]
+    );
+}
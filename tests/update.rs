@@ -0,0 +1,140 @@
+//! Updating a repository whose branch diverged from its upstream, with each update strategy
+//!
+//! The update is triggered with `u` in the interface.
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::{Fixture, TestRepo};
+use std::fs;
+
+/// Clone a repository whose `main` has one commit on `local_file` that is not pushed, and one
+/// commit on `upstream_file` that is only in `origin/main`
+fn diverged_clone(fixture: &Fixture, local_file: &str, upstream_file: &str) -> TestRepo {
+    let remote = fixture.bare("project.git");
+    let repo = fixture.clone(&remote, "project");
+    repo.commit("README", "base\n");
+    repo.git(&["push", "--quiet", "--set-upstream", "origin", "main"]);
+    repo.commit(upstream_file, "upstream\n");
+    repo.git(&["push", "--quiet"]);
+    repo.git(&["reset", "--quiet", "--hard", "HEAD~1"]);
+    repo.commit(local_file, "local\n");
+    repo
+}
+
+fn set_update_strategy(fixture: &Fixture, strategy: &str) {
+    fs::create_dir_all(fixture.config_dir()).unwrap();
+    fs::write(
+        fixture.config_dir().join("config.toml"),
+        format!("update_strategy = \"{}\"\n", strategy),
+    )
+    .unwrap();
+}
+
+/// Whether the upstream was integrated, and the rebase or merge is over
+fn integrated(repo: &TestRepo) -> bool {
+    let git_dir = repo.path.join(".git");
+    repo.git(&["rev-list", "--count", "HEAD..origin/main"])
+        .trim()
+        == "0"
+        && !git_dir.join("rebase-merge").exists()
+        && !git_dir.join("rebase-apply").exists()
+        && !git_dir.join("MERGE_HEAD").exists()
+}
+
+/// Press `u` on the only repository, and quit with `keys` once `text` is shown or `done`
+/// returns true
+fn update(fixture: &Fixture, text: &str, done: impl FnMut() -> bool, keys: &str) -> String {
+    let root = fixture.root().to_string_lossy().to_string();
+    let mut interface = fixture.interface(&["--no-fetch", &root]);
+    interface.wait_for("project");
+    interface.press("u");
+    interface.wait_until(text, done);
+    let (status, output) = interface.finish(keys);
+    assert!(status.success(), "git-repos failed: {}", output);
+    output
+}
+
+#[test]
+fn rebase_replays_local_commits_on_the_upstream() {
+    let fixture = Fixture::new();
+    let repo = diverged_clone(&fixture, "local.txt", "upstream.txt");
+    set_update_strategy(&fixture, "rebase");
+
+    let output = update(&fixture, "Failed to update", || integrated(&repo), "q");
+
+    assert!(integrated(&repo), "not rebased: {}", output);
+    assert_eq!(
+        repo.git(&["log", "--format=%s"]),
+        "Update local.txt\nUpdate upstream.txt\nUpdate README\n"
+    );
+    assert_eq!(repo.git(&["status", "--porcelain"]), "");
+}
+
+#[test]
+fn merge_creates_a_merge_commit() {
+    let fixture = Fixture::new();
+    let repo = diverged_clone(&fixture, "local.txt", "upstream.txt");
+    set_update_strategy(&fixture, "merge");
+
+    let output = update(&fixture, "Failed to update", || integrated(&repo), "q");
+
+    assert!(integrated(&repo), "not merged: {}", output);
+    let parents = repo.git(&["rev-list", "--parents", "--max-count=1", "HEAD"]);
+    assert_eq!(parents.split_whitespace().count(), 3, "not a merge commit");
+    assert!(repo.path.join("local.txt").exists());
+    assert!(repo.path.join("upstream.txt").exists());
+    assert_eq!(repo.git(&["status", "--porcelain"]), "");
+}
+
+#[test]
+fn conflicting_rebase_is_aborted() {
+    let fixture = Fixture::new();
+    let repo = diverged_clone(&fixture, "README", "README");
+    set_update_strategy(&fixture, "rebase");
+    let head = repo.git(&["rev-parse", "HEAD"]);
+
+    // Any key dismisses the error, before quitting
+    update(&fixture, "continue", || false, " q");
+
+    assert_eq!(repo.git(&["rev-parse", "HEAD"]), head);
+    assert_eq!(repo.git(&["status", "--porcelain"]), "");
+    assert!(!repo.path.join(".git").join("rebase-merge").exists());
+    assert_eq!(
+        fs::read_to_string(repo.path.join("README")).unwrap(),
+        "local\n"
+    );
+}
+
+#[test]
+fn conflicting_merge_is_aborted() {
+    let fixture = Fixture::new();
+    let repo = diverged_clone(&fixture, "README", "README");
+    set_update_strategy(&fixture, "merge");
+    let head = repo.git(&["rev-parse", "HEAD"]);
+
+    // Any key dismisses the error, before quitting
+    update(&fixture, "continue", || false, " q");
+
+    assert_eq!(repo.git(&["rev-parse", "HEAD"]), head);
+    assert_eq!(repo.git(&["status", "--porcelain"]), "");
+    assert!(!repo.path.join(".git").join("MERGE_HEAD").exists());
+    assert_eq!(
+        fs::read_to_string(repo.path.join("README")).unwrap(),
+        "local\n"
+    );
+}
+
+#[test]
+fn ff_only_reports_the_divergence() {
+    let fixture = Fixture::new();
+    let repo = diverged_clone(&fixture, "local.txt", "upstream.txt");
+    set_update_strategy(&fixture, "ff-only");
+    let head = repo.git(&["rev-parse", "HEAD"]);
+
+    update(&fixture, "diverged", || false, "q");
+
+    assert_eq!(repo.git(&["rev-parse", "HEAD"]), head);
+    assert_eq!(repo.git(&["status", "--porcelain"]), "");
+}